@@ -19,9 +19,11 @@ export type Operation =
 	| 'DiscardHunk'
 	| 'DiscardFile'
 	| 'AmendCommit'
+	| 'Absorb'
 	| 'UndoCommit'
 	| 'UnapplyBranch'
 	| 'CherryPick'
+	| 'RevertCommit'
 	| 'SquashCommit'
 	| 'UpdateCommitMessage'
 	| 'MoveCommit'
@@ -29,6 +31,8 @@ export type Operation =
 	| 'ReorderCommit'
 	| 'InsertBlankCommit'
 	| 'MoveCommitFile'
+	| 'SplitCommit'
+	| 'Autosquash'
 	| 'FileChanges'
 	| 'EnterEditMode';
 
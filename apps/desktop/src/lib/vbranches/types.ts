@@ -11,7 +11,21 @@ export type ChangeType =
 	/// Entry does not exist in new version
 	| 'deleted'
 	/// Entry content changed between old and new
-	| 'modified';
+	| 'modified'
+	/// Entry was moved from `oldFilePath`, possibly with changes to its content
+	| 'renamed'
+	/// Entry is a copy of `oldFilePath`, possibly with changes to its content
+	| 'copied';
+
+/// The sizes and type of binary content or LFS objects, which can't be shown line by line.
+export class ContentInfo {
+	lfs!: boolean;
+	oldSizeBytes?: number;
+	newSizeBytes?: number;
+	mimeType?: string;
+	/// A description of the change, like `LFS object changed (1.2 KiB → 3.4 KiB)`
+	summary!: string;
+}
 
 export class Hunk {
 	id!: string;
@@ -28,6 +42,8 @@ export class Hunk {
 	/// Indicates that the hunk depends on multiple branches. In this case the hunk cant be moved or comitted.
 	poisoned!: boolean;
 	changeType!: ChangeType;
+	oldFilePath?: string;
+	contentInfo?: ContentInfo;
 	new_start!: number;
 	new_lines!: number;
 }
@@ -68,6 +84,10 @@ export class LocalFile {
 		return this.hunks.map((h) => h.id);
 	}
 
+	get contentSummary(): string | undefined {
+		return this.hunks.find((h) => h.contentInfo)?.contentInfo?.summary;
+	}
+
 	get locked(): boolean {
 		return this.hunks
 			? this.hunks.map((hunk) => hunk.locked).reduce((a, b) => !!(a || b), false)
@@ -255,6 +275,7 @@ export class RemoteHunk {
 	hash?: string;
 	new_start!: number;
 	new_lines!: number;
+	content_info?: ContentInfo;
 
 	get id(): string {
 		return hashCode(this.diff);
@@ -295,6 +316,10 @@ export class RemoteFile {
 		return this.hunks.map((h) => h.id);
 	}
 
+	get contentSummary(): string | undefined {
+		return this.hunks.find((h) => h.content_info)?.content_info?.summary;
+	}
+
 	get lockedIds(): HunkLock[] {
 		return [];
 	}
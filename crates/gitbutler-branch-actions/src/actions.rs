@@ -2,14 +2,15 @@ use super::r#virtual as branch;
 use crate::branch::get_uncommited_files_raw;
 use crate::{
     base::{
-        get_base_branch_data, set_base_branch, set_target_push_remote, update_base_branch,
-        BaseBranch,
+        get_base_branch_data, preview_update_base_branch, set_base_branch, set_target_push_remote,
+        update_base_branch, BaseBranch, BaseBranchUpdatePreview,
     },
     branch::get_uncommited_files,
     branch_manager::BranchManagerExt,
     file::RemoteBranchFile,
     remote::{get_branch_data, list_local_branches, RemoteBranch, RemoteBranchData},
-    VirtualBranchesExt,
+    status::get_applied_status,
+    DiffSettingsExt, VirtualBranchesExt,
 };
 use anyhow::{Context, Result};
 use gitbutler_branch::{
@@ -22,9 +23,9 @@ use gitbutler_oplog::{
     entry::{OperationKind, SnapshotDetails},
     OplogExt, SnapshotExt,
 };
-use gitbutler_project::{FetchResult, Project};
+use gitbutler_project::{Project, RemoteFetchResults};
 use gitbutler_reference::{ReferenceName, Refname, RemoteRefname};
-use gitbutler_repo::{credentials::Helper, RepoActionsExt, RepositoryExt};
+use gitbutler_repo::{credentials::Helper, RepoActionsExt, Series};
 use tracing::instrument;
 
 #[derive(Clone, Copy, Default)]
@@ -58,6 +59,82 @@ impl VirtualBranchActions {
         result
     }
 
+    /// Commit only the given lines of a hunk owned by the branch with `branch_id`, leaving the remaining
+    /// lines of the hunk uncommitted.
+    pub fn commit_lines(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        message: &str,
+        selection: &branch::LineSelection,
+        run_hooks: bool,
+    ) -> Result<git2::Oid> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Creating a commit requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let snapshot_tree = ctx.project().prepare_snapshot(guard.read_permission());
+        let result = branch::commit_lines(&ctx, branch_id, message, selection, run_hooks)
+            .map_err(Into::into);
+        let _ = snapshot_tree.and_then(|snapshot_tree| {
+            ctx.project().snapshot_commit_creation(
+                snapshot_tree,
+                result.as_ref().err(),
+                message.to_owned(),
+                None,
+                guard.write_permission(),
+            )
+        });
+        result
+    }
+
+    /// Commit the given hunks, or all uncommitted hunks of the branch with `branch_id`, as a fixup
+    /// that [`Self::autosquash()`] folds into the commit with `target_change_id`.
+    pub fn create_fixup_commit(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        target_change_id: &str,
+        ownership: Option<&BranchOwnershipClaims>,
+        run_hooks: bool,
+    ) -> Result<git2::Oid> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Creating a commit requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let snapshot_tree = ctx.project().prepare_snapshot(guard.read_permission());
+        let result = branch::commit_fixup(&ctx, branch_id, target_change_id, ownership, run_hooks)
+            .map_err(Into::into);
+        let _ = snapshot_tree.and_then(|snapshot_tree| {
+            ctx.project().snapshot_commit_creation(
+                snapshot_tree,
+                result.as_ref().err(),
+                format!("fixup! {target_change_id}"),
+                None,
+                guard.write_permission(),
+            )
+        });
+        result
+    }
+
+    /// Restore the branch with `branch_id` as it was in the snapshot with `snapshot_commit_id`, as described
+    /// in [`OplogExt::restore_branch_from_snapshot()`], and update the workspace commit to match.
+    pub fn restore_branch_from_snapshot(
+        &self,
+        project: &Project,
+        snapshot_commit_id: git2::Oid,
+        branch_id: BranchId,
+    ) -> Result<Option<git2::Oid>> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Restoring a branch requires open workspace mode")?;
+        let restore_snapshot_id =
+            project.restore_branch_from_snapshot(snapshot_commit_id, branch_id)?;
+        let _guard = project.exclusive_worktree_access();
+        crate::integration::update_workspace_commit(&project.virtual_branches(), &ctx)?;
+        Ok(restore_snapshot_id)
+    }
+
     pub fn can_apply_remote_branch(
         &self,
         project: &Project,
@@ -165,7 +242,12 @@ impl VirtualBranchActions {
         commit_oid: git2::Oid,
     ) -> Result<Vec<RemoteBranchFile>> {
         let ctx = CommandContext::open(project)?;
-        crate::file::list_remote_commit_files(ctx.repository(), commit_oid).map_err(Into::into)
+        crate::file::list_remote_commit_files(
+            ctx.repository(),
+            commit_oid,
+            ctx.project().diff_settings(),
+        )
+        .map_err(Into::into)
     }
 
     pub fn set_base_branch(
@@ -199,6 +281,19 @@ impl VirtualBranchActions {
         branch::integrate_upstream_commits(&ctx, branch_id).map_err(Into::into)
     }
 
+    pub fn preview_integrate_upstream_commits(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+    ) -> Result<branch::UpstreamIntegrationPreview> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx).context(
+            "Previewing the integration of upstream commits requires open workspace mode",
+        )?;
+        let _guard = project.exclusive_worktree_access();
+        branch::preview_integrate_upstream_commits(&ctx, branch_id)
+    }
+
     pub fn update_base_branch(&self, project: &Project) -> Result<Vec<ReferenceName>> {
         let ctx = open_with_verify(project)?;
         assure_open_workspace_mode(&ctx)
@@ -211,6 +306,14 @@ impl VirtualBranchActions {
         update_base_branch(&ctx, guard.write_permission()).map_err(Into::into)
     }
 
+    pub fn preview_update_base_branch(&self, project: &Project) -> Result<BaseBranchUpdatePreview> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Previewing the update of the base branch requires open workspace mode")?;
+        let _guard = project.exclusive_worktree_access();
+        preview_update_base_branch(&ctx)
+    }
+
     pub fn update_virtual_branch(
         &self,
         project: &Project,
@@ -225,7 +328,20 @@ impl VirtualBranchActions {
             .project()
             .virtual_branches()
             .get_branch_in_workspace(branch_update.id)?;
-        let result = branch::update_branch(&ctx, &branch_update);
+        let result = match &branch_update.ownership {
+            // Moving hunks must keep them with the hunks they depend on.
+            Some(ownership) if !old_branch.ownership.eq(ownership) => {
+                get_applied_status(&ctx, None).and_then(|status| {
+                    branch::ensure_dependent_hunks_stay_together(
+                        &status.branches,
+                        branch_update.id,
+                        ownership,
+                    )
+                })
+            }
+            _ => Ok(()),
+        }
+        .and_then(|()| branch::update_branch(&ctx, &branch_update));
         let _ = snapshot_tree.and_then(|snapshot_tree| {
             ctx.project().snapshot_branch_update(
                 snapshot_tree,
@@ -294,7 +410,7 @@ impl VirtualBranchActions {
             SnapshotDetails::new(OperationKind::DiscardFile),
             guard.write_permission(),
         );
-        branch::reset_files(&ctx, files).map_err(Into::into)
+        branch::reset_files(&ctx, files, guard.read_permission()).map_err(Into::into)
     }
 
     pub fn amend(
@@ -315,6 +431,20 @@ impl VirtualBranchActions {
         branch::amend(&ctx, branch_id, commit_oid, ownership)
     }
 
+    /// Amend the uncommitted hunks of the branch with `branch_id` into the commits that last changed
+    /// their lines, and return how many hunks were absorbed.
+    pub fn absorb(&self, project: &Project, branch_id: BranchId) -> Result<usize> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Absorbing changes requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = ctx.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::Absorb),
+            guard.write_permission(),
+        );
+        branch::absorb(&ctx, branch_id)
+    }
+
     pub fn move_commit_file(
         &self,
         project: &Project,
@@ -335,6 +465,24 @@ impl VirtualBranchActions {
             .map_err(Into::into)
     }
 
+    pub fn split_commit(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_oid: git2::Oid,
+        splits: &[branch::CommitSplit],
+    ) -> Result<Vec<git2::Oid>> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Splitting a commit requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = ctx.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::SplitCommit),
+            guard.write_permission(),
+        );
+        branch::split_commit(&ctx, branch_id, commit_oid, splits).map_err(Into::into)
+    }
+
     pub fn undo_commit(
         &self,
         project: &Project,
@@ -359,6 +507,41 @@ impl VirtualBranchActions {
         result
     }
 
+    pub fn cherry_pick(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_oid: git2::Oid,
+    ) -> Result<git2::Oid> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Cherry-picking a commit requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = ctx.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::CherryPick),
+            guard.write_permission(),
+        );
+        branch::cherry_pick(&ctx, branch_id, commit_oid).map_err(Into::into)
+    }
+
+    pub fn revert(
+        &self,
+        project: &Project,
+        branch_id: BranchId,
+        commit_oid: git2::Oid,
+        destination: branch::RevertDestination,
+    ) -> Result<Option<git2::Oid>> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Reverting a commit requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = ctx.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::RevertCommit),
+            guard.write_permission(),
+        );
+        branch::revert(&ctx, branch_id, commit_oid, destination).map_err(Into::into)
+    }
+
     pub fn insert_blank_commit(
         &self,
         project: &Project,
@@ -401,6 +584,11 @@ impl VirtualBranchActions {
         gitbutler_repo::push_change_reference(&ctx, branch_id, name, with_force, &helper)
     }
 
+    pub fn list_series(&self, project: &Project, branch_id: BranchId) -> Result<Vec<Series>> {
+        let ctx = open_with_verify(project)?;
+        gitbutler_repo::list_series(&ctx, branch_id)
+    }
+
     pub fn update_change_reference(
         &self,
         project: &Project,
@@ -516,6 +704,19 @@ impl VirtualBranchActions {
         branch::squash(&ctx, branch_id, commit_oid).map_err(Into::into)
     }
 
+    /// Fold the `fixup!` and `squash!` commits of the branch with `branch_id` into the commits they target.
+    pub fn autosquash(&self, project: &Project, branch_id: BranchId) -> Result<()> {
+        let ctx = open_with_verify(project)?;
+        assure_open_workspace_mode(&ctx)
+            .context("Autosquashing a branch requires open workspace mode")?;
+        let mut guard = project.exclusive_worktree_access();
+        let _ = ctx.project().create_snapshot(
+            SnapshotDetails::new(OperationKind::Autosquash),
+            guard.write_permission(),
+        );
+        branch::autosquash(&ctx, branch_id).map_err(Into::into)
+    }
+
     pub fn update_commit_message(
         &self,
         project: &Project,
@@ -534,35 +735,14 @@ impl VirtualBranchActions {
         branch::update_commit_message(&ctx, branch_id, commit_oid, message).map_err(Into::into)
     }
 
+    /// Fetch all remotes concurrently, returning how fetching each of them went.
     pub fn fetch_from_remotes(
         &self,
         project: &Project,
         askpass: Option<String>,
-    ) -> Result<FetchResult> {
+    ) -> Result<RemoteFetchResults> {
         let ctx = CommandContext::open(project)?;
-
-        let helper = Helper::default();
-        let remotes = ctx.repository().remotes_as_string()?;
-        let fetch_errors: Vec<_> = remotes
-            .iter()
-            .filter_map(|remote| {
-                ctx.fetch(remote, &helper, askpass.clone())
-                    .err()
-                    .map(|err| err.to_string())
-            })
-            .collect();
-
-        let timestamp = std::time::SystemTime::now();
-        let project_data_last_fetched = if fetch_errors.is_empty() {
-            FetchResult::Fetched { timestamp }
-        } else {
-            FetchResult::Error {
-                timestamp,
-                error: fetch_errors.join("\n"),
-            }
-        };
-
-        Ok(project_data_last_fetched)
+        ctx.fetch_remotes(&Helper::default(), askpass)
     }
 
     pub fn move_commit(
@@ -1,4 +1,7 @@
-use std::{path::Path, time};
+use std::{
+    path::{Path, PathBuf},
+    time,
+};
 
 use anyhow::{anyhow, Context, Result};
 use git2::Index;
@@ -10,7 +13,10 @@ use gitbutler_command_context::CommandContext;
 use gitbutler_error::error::Marker;
 use gitbutler_project::{access::WorktreeWritePermission, FetchResult};
 use gitbutler_reference::{ReferenceName, Refname, RemoteRefname};
-use gitbutler_repo::{rebase::cherry_rebase, LogUntil, RepoActionsExt, RepositoryExt};
+use gitbutler_repo::{
+    rebase::{cherry_rebase, cherry_rebase_group_in},
+    LogUntil, RepoActionsExt, RepositoryExt,
+};
 use serde::Serialize;
 
 use crate::{
@@ -18,9 +24,10 @@ use crate::{
     conflicts::RepoConflictsExt,
     hunk::VirtualBranchHunk,
     integration::update_workspace_commit,
+    r#virtual::{conflicted_commit_paths, conflicted_paths},
     remote::{commit_to_remote_commit, RemoteCommit},
-    status::get_applied_status,
-    VirtualBranchesExt,
+    status::{get_applied_hunks_in, get_applied_status},
+    DiffSettingsExt, VirtualBranchesExt,
 };
 
 #[derive(Debug, Serialize, PartialEq, Clone)]
@@ -181,7 +188,11 @@ pub(crate) fn set_base_branch(
         // if there are any commits on the head branch or uncommitted changes in the working directory, we need to
         // put them into a virtual branch
 
-        let wd_diff = gitbutler_diff::workdir(repo, current_head_commit.id())?;
+        let wd_diff = gitbutler_diff::workdir(
+            repo,
+            current_head_commit.id(),
+            ctx.project().diff_settings(),
+        )?;
         if !wd_diff.is_empty() || current_head_commit.id() != target.sha {
             // assign ownership to the branch
             let ownership = wd_diff.iter().fold(
@@ -384,8 +395,12 @@ pub(crate) fn update_base_branch(
                 branch.upstream = None;
                 branch.upstream_head = None;
 
-                let non_commited_files =
-                    gitbutler_diff::trees(ctx.repository(), &branch_head_tree, &branch_tree)?;
+                let non_commited_files = gitbutler_diff::trees(
+                    ctx.repository(),
+                    &branch_head_tree,
+                    &branch_tree,
+                    Default::default(),
+                )?;
                 if non_commited_files.is_empty() {
                     // if there are no commited files, then the branch is fully merged,
                     // and we can delete it.
@@ -553,6 +568,149 @@ pub(crate) fn update_base_branch(
     Ok(unapplied_branch_names)
 }
 
+/// What [`update_base_branch()`] would do with a virtual branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BaseBranchUpdateAction {
+    /// The branch has no commits of its own on top of the new target, so its head is moved to it.
+    FastForward,
+    /// The commits of the branch are rebased onto the new target.
+    Rebase,
+    /// The new target is merged into the branch with a merge commit.
+    Merge,
+    /// All changes of the branch are part of the new target, so the branch is deleted.
+    DeleteIntegrated,
+    /// The branch conflicts with the new target, so it's unapplied.
+    UnapplyConflicted,
+}
+
+/// The outcome of updating the base branch for a single virtual branch.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchBaseUpdatePreview {
+    pub branch_id: BranchId,
+    pub branch_name: String,
+    pub action: BaseBranchUpdateAction,
+    /// The files that conflict with the new target, or that are conflicted in rebased commits.
+    pub conflicting_paths: Vec<PathBuf>,
+}
+
+/// The outcome of [`update_base_branch()`], computed without applying it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseBranchUpdatePreview {
+    /// The commit the target would be updated to.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub new_target_sha: git2::Oid,
+    /// The outcome for each applied virtual branch, empty if the target didn't change.
+    pub branches: Vec<BranchBaseUpdatePreview>,
+}
+
+/// Compute what [`update_base_branch()`] would do with each applied virtual branch, without writing
+/// anything to disk or changing any reference.
+pub(crate) fn preview_update_base_branch(ctx: &CommandContext) -> Result<BaseBranchUpdatePreview> {
+    ctx.assure_resolved()?;
+
+    let target = default_target(&ctx.project().gb_dir())?;
+    let repo = ctx.repository().in_memory_repo()?;
+    let new_target_commit = repo
+        .find_branch_by_refname(&target.branch.clone().into())
+        .context(format!("failed to find branch {}", target.branch))?
+        .ok_or(anyhow!("failed to get branch"))?
+        .get()
+        .peel_to_commit()
+        .context(format!("failed to peel branch {} to commit", target.branch))?;
+
+    let mut preview = BaseBranchUpdatePreview {
+        new_target_sha: new_target_commit.id(),
+        branches: vec![],
+    };
+    if new_target_commit.id() == target.sha {
+        return Ok(preview);
+    }
+
+    let new_target_tree = new_target_commit.tree()?;
+    let old_target_tree = repo.find_commit(target.sha)?.tree()?;
+
+    // Like the update itself, start from the current state of the worktree and not from the persisted trees,
+    // which are only updated when the branches are listed. Their new trees are only written to memory.
+    for (branch, _) in get_applied_hunks_in(ctx, &repo)? {
+        let branch_tree = repo.find_tree(branch.tree)?;
+        let branch_head_tree = repo.find_commit(branch.head)?.tree()?;
+        let mut conflicting_paths = vec![];
+
+        let integrated_action = || -> Result<BaseBranchUpdateAction> {
+            let non_commited_files =
+                gitbutler_diff::trees(&repo, &branch_head_tree, &branch_tree, Default::default())?;
+            Ok(if non_commited_files.is_empty() {
+                BaseBranchUpdateAction::DeleteIntegrated
+            } else {
+                BaseBranchUpdateAction::FastForward
+            })
+        };
+
+        let action = 'action: {
+            if branch_head_tree.id() == new_target_tree.id() {
+                break 'action integrated_action()?;
+            }
+
+            let mut branch_tree_merge_index =
+                repo.merge_trees(&old_target_tree, &branch_tree, &new_target_tree, None)?;
+            if branch_tree_merge_index.has_conflicts() {
+                conflicting_paths = conflicted_paths(&branch_tree_merge_index)?;
+                break 'action BaseBranchUpdateAction::UnapplyConflicted;
+            }
+            if branch_tree_merge_index.write_tree_to(&repo)? == new_target_tree.id() {
+                break 'action integrated_action()?;
+            }
+            if branch.head == target.sha {
+                break 'action BaseBranchUpdateAction::FastForward;
+            }
+
+            let branch_head_merge_index =
+                repo.merge_trees(&old_target_tree, &branch_head_tree, &new_target_tree, None)?;
+            if branch_head_merge_index.has_conflicts() {
+                conflicting_paths = conflicted_paths(&branch_head_merge_index)?;
+                break 'action BaseBranchUpdateAction::UnapplyConflicted;
+            }
+
+            if branch.upstream.is_some() && !branch.allow_rebasing {
+                break 'action BaseBranchUpdateAction::Merge;
+            }
+
+            let mut ids_to_rebase = ctx.l(branch.head, LogUntil::Commit(new_target_commit.id()))?;
+            if ids_to_rebase.is_empty() {
+                break 'action BaseBranchUpdateAction::Merge;
+            }
+            let Ok(rebased_head) = cherry_rebase_group_in(
+                &repo,
+                ctx.project().succeeding_rebases,
+                new_target_commit.id(),
+                &mut ids_to_rebase,
+            ) else {
+                break 'action BaseBranchUpdateAction::Merge;
+            };
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push(rebased_head)?;
+            revwalk.hide(new_target_commit.id())?;
+            for id in revwalk {
+                let commit = repo.find_commit(id?)?;
+                conflicting_paths.extend(conflicted_commit_paths(&repo, &commit)?);
+            }
+            conflicting_paths.sort();
+            conflicting_paths.dedup();
+            BaseBranchUpdateAction::Rebase
+        };
+        preview.branches.push(BranchBaseUpdatePreview {
+            branch_id: branch.id,
+            branch_name: branch.name,
+            action,
+            conflicting_paths,
+        });
+    }
+    Ok(preview)
+}
+
 pub(crate) fn target_to_base_branch(ctx: &CommandContext, target: &Target) -> Result<BaseBranch> {
     let repo = ctx.repository();
     let branch = repo
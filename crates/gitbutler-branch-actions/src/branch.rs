@@ -1,5 +1,5 @@
 use crate::integration::get_workspace_head;
-use crate::{RemoteBranchFile, VirtualBranchesExt};
+use crate::{DiffSettingsExt, RemoteBranchFile, VirtualBranchesExt};
 use anyhow::{bail, Context, Result};
 use bstr::{BStr, ByteSlice};
 use core::fmt;
@@ -31,8 +31,12 @@ pub(crate) fn get_uncommited_files_raw(
     ctx: &CommandContext,
     _permission: &WorktreeReadPermission,
 ) -> Result<DiffByPathMap> {
-    gitbutler_diff::workdir(ctx.repository(), get_workspace_head(ctx)?)
-        .context("Failed to list uncommited files")
+    gitbutler_diff::workdir(
+        ctx.repository(),
+        get_workspace_head(ctx)?,
+        ctx.project().diff_settings(),
+    )
+    .context("Failed to list uncommited files")
 }
 
 pub(crate) fn get_uncommited_files(
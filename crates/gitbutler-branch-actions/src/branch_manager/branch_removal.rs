@@ -12,7 +12,7 @@ use tracing::instrument;
 
 use super::BranchManager;
 use crate::{
-    conflicts::{self},
+    conflicts::{self, RepoConflictsExt},
     ensure_selected_for_changes, get_applied_status,
     hunk::VirtualBranchHunk,
     VirtualBranchesExt,
@@ -102,16 +102,19 @@ impl BranchManager<'_> {
                 .filter(|(branch, _)| branch.id != branch_id)
                 .fold(
                     target_commit.tree().context("failed to get target tree"),
-                    |final_tree, status| {
+                    |final_tree, (branch, files)| {
                         let final_tree = final_tree?;
-                        let branch = status.0;
-                        let files = status
-                            .1
-                            .into_iter()
-                            .map(|file| (file.path, file.hunks))
-                            .collect::<Vec<(PathBuf, Vec<VirtualBranchHunk>)>>();
-                        let tree_oid =
-                            gitbutler_diff::write::hunks_onto_oid(self.ctx, branch.head, files)?;
+                        // Unless conflicts are being resolved, the status just wrote the uncommitted changes
+                        // to the tree of the branch, including the whitespace changes its hunks may ignore.
+                        let tree_oid = if self.ctx.is_resolving() {
+                            let files = files
+                                .into_iter()
+                                .map(|file| (file.path, file.hunks))
+                                .collect::<Vec<(PathBuf, Vec<VirtualBranchHunk>)>>();
+                            gitbutler_diff::write::hunks_onto_oid(self.ctx, branch.head, files)?
+                        } else {
+                            branch.tree
+                        };
                         let branch_tree = repo.find_tree(tree_oid)?;
                         let mut result =
                             repo.merge_trees(&base_tree, &final_tree, &branch_tree, None)?;
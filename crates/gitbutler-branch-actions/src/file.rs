@@ -12,6 +12,7 @@ use serde::Serialize;
 use crate::{
     conflicts,
     hunk::{file_hunks_from_diffs, VirtualBranchHunk},
+    DiffSettingsExt,
 };
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
@@ -25,6 +26,7 @@ pub struct RemoteBranchFile {
 pub(crate) fn list_remote_commit_files(
     repository: &git2::Repository,
     commit_id: git2::Oid,
+    settings: gitbutler_diff::DiffSettings,
 ) -> Result<Vec<RemoteBranchFile>> {
     let commit = repository
         .find_commit(commit_id)
@@ -45,7 +47,7 @@ pub(crate) fn list_remote_commit_files(
     let parent_tree = repository
         .find_real_tree(&parent, Default::default())
         .context("failed to get parent tree")?;
-    let diff_files = gitbutler_diff::trees(repository, &parent_tree, &commit_tree)?;
+    let diff_files = gitbutler_diff::trees(repository, &parent_tree, &commit_tree, settings)?;
 
     Ok(diff_files
         .into_iter()
@@ -105,7 +107,15 @@ pub(crate) fn list_virtual_commit_files(
     let parent_tree = repository
         .find_real_tree(&parent, Default::default())
         .context("failed to get parent tree")?;
-    let diff = gitbutler_diff::trees(ctx.repository(), &parent_tree, &commit_tree)?;
+    let mut diff = gitbutler_diff::trees(
+        ctx.repository(),
+        &parent_tree,
+        &commit_tree,
+        ctx.project().diff_settings(),
+    )?;
+    if let Some(granularity) = ctx.project().intraline_granularity() {
+        gitbutler_diff::intraline::annotate(&mut diff, granularity);
+    }
     let hunks_by_filepath = virtual_hunks_by_file_diffs(&ctx.project().path, diff);
     Ok(virtual_hunks_into_virtual_files(ctx, hunks_by_filepath))
 }
@@ -119,6 +129,7 @@ fn virtual_hunks_by_file_diffs<'a>(
         diff.into_iter()
             .map(move |(file_path, file)| (file_path, file.hunks)),
         None,
+        None,
     )
 }
 
@@ -6,7 +6,7 @@ use std::{
 };
 
 use gitbutler_branch::BranchId;
-use gitbutler_diff::{GitHunk, Hunk, HunkHash};
+use gitbutler_diff::{intraline::TokenRange, ContentInfo, GitHunk, Hunk, HunkHash};
 use gitbutler_serde::BStringForFrontend;
 use itertools::Itertools;
 use md5::Digest;
@@ -38,7 +38,17 @@ pub struct VirtualBranchHunk {
     pub locked: bool,
     pub locked_to: Option<Box<[HunkLock]>>,
     pub change_type: gitbutler_diff::ChangeType,
-    /// Indicates that the hunk depends on multiple branches. In this case the hunk cant be moved or comitted.
+    /// The path the file was renamed or copied from, if it was.
+    pub old_file_path: Option<PathBuf>,
+    /// The words or characters that changed within the lines of this hunk, if they were computed.
+    pub changed_tokens: Option<Vec<TokenRange>>,
+    /// The sizes and type of binary content or LFS objects, which can't be shown line by line.
+    pub content_info: Option<ContentInfo>,
+    /// Uncommitted hunks in other branches that this hunk is adjacent to.
+    /// It can only be committed along with them.
+    pub depends_on: Vec<HunkDependency>,
+    /// Indicates that the hunk depends on multiple branches, through the commits it's locked to
+    /// or the uncommitted hunks it depends on. In this case the hunk cant be moved or comitted.
     pub poisoned: bool,
 }
 
@@ -53,6 +63,17 @@ pub struct HunkLock {
     pub commit_id: git2::Oid,
 }
 
+// A hunk depends on an uncommitted hunk in another branch if both are so close
+// to each other that committing one of them alone changes the context of the other.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkDependency {
+    pub branch_id: BranchId,
+    pub hunk_id: String,
+    #[serde(serialize_with = "gitbutler_branch::serde::hash_to_hex")]
+    pub hash: HunkHash,
+}
+
 /// Lifecycle
 impl VirtualBranchHunk {
     pub(crate) fn gen_id(new_start: u32, new_lines: u32) -> String {
@@ -64,6 +85,7 @@ impl VirtualBranchHunk {
         hunk: GitHunk,
         mtimes: &mut MTimeCache,
         locks: &HashMap<Digest, Vec<HunkLock>>,
+        dependencies: &HashMap<Digest, Vec<HunkDependency>>,
     ) -> Self {
         let hash = Hunk::hash_diff(&hunk.diff_lines);
 
@@ -72,6 +94,8 @@ impl VirtualBranchHunk {
 
         // Get the unique branch ids (lock.branch_id) from hunk.locked_to that a hunk is locked to (if any)
         let branch_deps_count = locked_to.iter().map(|lock| lock.branch_id).unique().count();
+        let depends_on = dependencies.get(&hash).cloned().unwrap_or_default();
+        let poisoned = branch_deps_count > 1 || !depends_on.is_empty();
 
         Self {
             id: Self::gen_id(hunk.new_start, hunk.new_lines),
@@ -87,7 +111,11 @@ impl VirtualBranchHunk {
             locked: !locked_to.is_empty(),
             locked_to: Some(locked_to.clone().into_boxed_slice()),
             change_type: hunk.change_type,
-            poisoned: branch_deps_count > 1,
+            old_file_path: hunk.old_path,
+            changed_tokens: hunk.changed_tokens,
+            content_info: hunk.content_info,
+            depends_on,
+            poisoned,
         }
     }
 }
@@ -102,23 +130,29 @@ impl From<VirtualBranchHunk> for GitHunk {
             diff_lines: val.diff,
             binary: val.binary,
             change_type: val.change_type,
+            old_path: val.old_file_path,
+            changed_tokens: val.changed_tokens,
+            content_info: val.content_info,
         }
     }
 }
 
 /// Takes an iterator with a tuple of a file path and it's corresponding diffs vector
 /// and returns the same structure but with VirtualBranchHunks instead of GitHunks,
-/// adding things like locks and other virtual branch metadata.
+/// adding things like locks, dependencies and other virtual branch metadata.
 pub(crate) fn file_hunks_from_diffs<'a>(
     project_path: &'a Path,
     diff: impl IntoIterator<Item = (PathBuf, Vec<gitbutler_diff::GitHunk>)> + 'a,
     locks: Option<&'a HashMap<Digest, Vec<HunkLock>>>,
+    dependencies: Option<&'a HashMap<Digest, Vec<HunkDependency>>>,
 ) -> HashMap<PathBuf, Vec<VirtualBranchHunk>> {
     let mut mtimes = MTimeCache::default();
     diff.into_iter()
         .map(move |(file_path, hunks)| {
             let binding = HashMap::new();
             let locks = locks.unwrap_or(&binding);
+            let dependencies_binding = HashMap::new();
+            let dependencies = dependencies.unwrap_or(&dependencies_binding);
             let hunks = hunks
                 .into_iter()
                 .map(|hunk| {
@@ -128,6 +162,7 @@ pub(crate) fn file_hunks_from_diffs<'a>(
                         hunk,
                         &mut mtimes,
                         locks,
+                        dependencies,
                     )
                 })
                 .collect::<Vec<_>>();
@@ -31,13 +31,27 @@ pub const GITBUTLER_WORKSPACE_COMMIT_TITLE: &str = "GitButler Workspace Commit";
 /// This is namely the conflicting state, or any head of the virtual branches.
 #[instrument(level = tracing::Level::DEBUG, skip(ctx))]
 pub(crate) fn get_workspace_head(ctx: &CommandContext) -> Result<git2::Oid> {
+    let vb_state = ctx.project().virtual_branches();
+    let (workspace_head_id, conflicting_branches) = workspace_head_in(ctx, ctx.repository())?;
+    for branch in conflicting_branches {
+        vb_state.set_branch(branch)?;
+    }
+    Ok(workspace_head_id)
+}
+
+/// Like [`get_workspace_head()`], but write the workspace commit to `repo`, which may keep it in memory,
+/// and return the branches that conflict with the target as unapplied instead of persisting them.
+pub(crate) fn workspace_head_in(
+    ctx: &CommandContext,
+    repo: &git2::Repository,
+) -> Result<(git2::Oid, Vec<Branch>)> {
     let vb_state = ctx.project().virtual_branches();
     let target = vb_state
         .get_default_target()
         .context("failed to get target")?;
-    let repo: &git2::Repository = ctx.repository();
 
     let mut virtual_branches: Vec<Branch> = vb_state.list_branches_in_workspace()?;
+    let mut conflicting_branches = Vec::new();
 
     let target_commit = repo.find_commit(target.sha)?;
     let mut workspace_tree = repo.find_real_tree(&target_commit, Default::default())?;
@@ -63,7 +77,7 @@ pub(crate) fn get_workspace_head(ctx: &CommandContext) -> Result<git2::Oid> {
                 tracing::warn!("Merge conflict between base and {:?}", branch.name);
                 branch.applied = false;
                 branch.in_workspace = false;
-                vb_state.set_branch(branch.clone())?;
+                conflicting_branches.push(branch.clone());
             }
         }
     }
@@ -93,7 +107,7 @@ pub(crate) fn get_workspace_head(ctx: &CommandContext) -> Result<git2::Oid> {
         &workspace_tree,
         head_refs.as_slice(),
     )?;
-    Ok(workspace_head_id)
+    Ok((workspace_head_id, conflicting_branches))
 }
 
 // Before switching the user to our gitbutler workspace branch we save
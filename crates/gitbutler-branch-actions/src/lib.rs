@@ -9,13 +9,15 @@ mod branch_manager;
 pub use branch_manager::{BranchManager, BranchManagerExt};
 
 mod base;
-pub use base::BaseBranch;
+pub use base::{
+    BaseBranch, BaseBranchUpdateAction, BaseBranchUpdatePreview, BranchBaseUpdatePreview,
+};
 
 mod integration;
 pub use integration::{update_workspace_commit, verify_branch};
 
 mod file;
-pub use file::{Get, RemoteBranchFile};
+pub use file::{Get, RemoteBranchFile, VirtualBranchFile};
 
 mod remote;
 pub use remote::{list_local_branches, RemoteBranch, RemoteBranchData, RemoteCommit};
@@ -25,6 +27,8 @@ pub mod conflicts;
 mod author;
 mod status;
 use gitbutler_branch::VirtualBranchesHandle;
+use gitbutler_diff::{intraline::Granularity, DiffSettings, IgnoreWhitespace, RenameOptions};
+use gitbutler_project::{IntralineDiff, WhitespaceMode};
 pub use status::get_applied_status;
 trait VirtualBranchesExt {
     fn virtual_branches(&self) -> VirtualBranchesHandle;
@@ -36,9 +40,40 @@ impl VirtualBranchesExt for gitbutler_project::Project {
     }
 }
 
+trait DiffSettingsExt {
+    /// The settings to diff the worktree and commits of the project with.
+    fn diff_settings(&self) -> DiffSettings;
+    /// The granularity of the changes within lines to compute for the hunks of the project, if any.
+    fn intraline_granularity(&self) -> Option<Granularity>;
+}
+
+impl DiffSettingsExt for gitbutler_project::Project {
+    fn diff_settings(&self) -> DiffSettings {
+        DiffSettings {
+            renames: self.rename_detection.map(|renames| RenameOptions {
+                threshold: renames.threshold,
+                copies: renames.copies,
+            }),
+            whitespace: self.whitespace_mode.map(|mode| match mode {
+                WhitespaceMode::IgnoreAll => IgnoreWhitespace::All,
+                WhitespaceMode::IgnoreAtEol => IgnoreWhitespace::AtEol,
+                WhitespaceMode::IgnoreBlankLines => IgnoreWhitespace::BlankLines,
+            }),
+        }
+    }
+
+    fn intraline_granularity(&self) -> Option<Granularity> {
+        self.intraline_diff.map(|granularity| match granularity {
+            IntralineDiff::Word => Granularity::Word,
+            IntralineDiff::Char => Granularity::Char,
+        })
+    }
+}
+
 mod branch;
 mod commit;
 mod hunk;
+pub use hunk::{HunkDependency, HunkLock, VirtualBranchHunk};
 
 pub use branch::{
     get_branch_listing_details, list_branches, Author, BranchListing, BranchListingDetails,
@@ -1,22 +1,29 @@
-use std::{collections::HashMap, path::PathBuf, vec};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    vec,
+};
 
-use crate::integration::get_workspace_head;
+use crate::integration::{get_workspace_head, workspace_head_in};
 use crate::{
     conflicts::RepoConflictsExt,
     file::{virtual_hunks_into_virtual_files, VirtualBranchFile},
-    hunk::{file_hunks_from_diffs, HunkLock, VirtualBranchHunk},
-    BranchManagerExt, VirtualBranchesExt,
+    hunk::{file_hunks_from_diffs, HunkDependency, HunkLock, VirtualBranchHunk},
+    r#virtual::BranchStatus,
+    BranchManagerExt, DiffSettingsExt, VirtualBranchesExt,
 };
 use anyhow::{bail, Context, Result};
-use git2::Tree;
+use bstr::ByteSlice;
 use gitbutler_branch::{
     Branch, BranchCreateRequest, BranchId, BranchOwnershipClaims, OwnershipClaim,
 };
 use gitbutler_cherry_pick::RepositoryExt as _;
 use gitbutler_command_context::CommandContext;
-use gitbutler_diff::{diff_files_into_hunks, GitHunk, Hunk, HunkHash};
+use gitbutler_diff::{diff_files_into_hunks, ChangeType, GitHunk, Hunk, HunkHash};
 use gitbutler_operating_modes::assure_open_workspace_mode;
 use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_repo::RepositoryExt as _;
+use itertools::Itertools;
 use tracing::instrument;
 
 /// Represents the uncommitted status of the applied virtual branches in the workspace.
@@ -47,18 +54,22 @@ pub fn get_applied_status_cached(
     worktree_changes: Option<gitbutler_diff::DiffByPathMap>,
 ) -> Result<VirtualBranchesStatus> {
     assure_open_workspace_mode(ctx).context("ng applied status requires open workspace mode")?;
-    let mut virtual_branches = ctx
-        .project()
-        .virtual_branches()
-        .list_branches_in_workspace()?;
-    let base_file_diffs = worktree_changes.map(Ok).unwrap_or_else(|| {
+    let mut workspace_head = None;
+    let mut base_file_diffs = worktree_changes.map(Ok).unwrap_or_else(|| {
         // TODO(ST): Ideally, we can avoid calling `get_workspace_head()` as everyone who modifies
         //           any of its inputs will update the intragration commit right away.
         //           It's for another day though - right now the integration commit may be slightly stale.
-        let workspace_head = get_workspace_head(ctx)?;
-        gitbutler_diff::workdir(ctx.repository(), workspace_head.to_owned())
-            .context("failed to diff workdir")
+        let workspace_head = *workspace_head.insert(get_workspace_head(ctx)?);
+        gitbutler_diff::workdir(
+            ctx.repository(),
+            workspace_head.to_owned(),
+            ctx.project().diff_settings(),
+        )
+        .context("failed to diff workdir")
     })?;
+    if let Some(granularity) = ctx.project().intraline_granularity() {
+        gitbutler_diff::intraline::annotate(&mut base_file_diffs, granularity);
+    }
 
     let mut skipped_files: Vec<gitbutler_diff::FileDiff> = Vec::new();
     for file_diff in base_file_diffs.values() {
@@ -66,6 +77,138 @@ pub fn get_applied_status_cached(
             skipped_files.push(file_diff.clone());
         }
     }
+    let (mut hunks_by_branch, locks) = assign_hunks_to_branches(ctx, perm, base_file_diffs)?;
+
+    // write updated state if not resolving
+    if !ctx.is_resolving() {
+        let vb_state = ctx.project().virtual_branches();
+        let files_to_write = hunks_to_write(ctx, ctx.repository(), &hunks_by_branch, || {
+            workspace_head.map_or_else(|| get_workspace_head(ctx), Ok)
+        })?;
+        for ((vbranch, _), files) in hunks_by_branch.iter_mut().zip(files_to_write) {
+            vbranch.tree = gitbutler_diff::write::hunks_onto_oid(ctx, vbranch.head, &files)?;
+            vb_state
+                .set_branch(vbranch.clone())
+                .context(format!("failed to write virtual branch {}", vbranch.name))?;
+        }
+    }
+    let dependencies = compute_dependencies(&hunks_by_branch);
+    let hunks_by_branch: Vec<(Branch, HashMap<PathBuf, Vec<VirtualBranchHunk>>)> = hunks_by_branch
+        .iter()
+        .map(|(branch, hunks)| {
+            let hunks = file_hunks_from_diffs(
+                &ctx.project().path,
+                hunks.clone(),
+                Some(&locks),
+                Some(&dependencies),
+            );
+            (branch.clone(), hunks)
+        })
+        .collect();
+
+    let files_by_branch: Vec<(Branch, Vec<VirtualBranchFile>)> = hunks_by_branch
+        .iter()
+        .map(|(branch, hunks)| {
+            let files = virtual_hunks_into_virtual_files(ctx, hunks.clone());
+            (branch.clone(), files)
+        })
+        .collect();
+
+    Ok(VirtualBranchesStatus {
+        branches: files_by_branch,
+        skipped_files,
+    })
+}
+
+/// Like [`get_applied_status()`], but only assign the uncommitted hunks to the applied branches and write
+/// their trees to `repo`, which may keep them in memory. Nothing is persisted.
+pub(crate) fn get_applied_hunks_in(
+    ctx: &CommandContext,
+    repo: &git2::Repository,
+) -> Result<Vec<(Branch, BranchStatus)>> {
+    assure_open_workspace_mode(ctx).context("ng applied status requires open workspace mode")?;
+    let (workspace_head, _) = workspace_head_in(ctx, repo)?;
+    let base_file_diffs =
+        gitbutler_diff::workdir(repo, workspace_head, ctx.project().diff_settings())
+            .context("failed to diff workdir")?;
+    let (mut hunks_by_branch, _) = assign_hunks_to_branches(ctx, None, base_file_diffs)?;
+    let files_to_write = hunks_to_write(ctx, repo, &hunks_by_branch, || Ok(workspace_head))?;
+    for ((branch, _), files) in hunks_by_branch.iter_mut().zip(files_to_write) {
+        let head_tree = repo.find_commit(branch.head)?.tree()?;
+        branch.tree = gitbutler_diff::write::hunks_onto_tree_in(
+            repo,
+            &ctx.project().worktree_path(),
+            &head_tree,
+            &files,
+        )?;
+    }
+    Ok(hunks_by_branch)
+}
+
+/// Return the hunks to write to the tree of each branch of `hunks_by_branch`, in the same order.
+///
+/// If whitespace changes are ignored, modified files that only one branch has hunks in are diffed again against
+/// the commit returned by `workspace_head` without ignoring them. That way the ignored changes are saved with the
+/// branch instead of being lost whenever the worktree is checked out from the branches, but they aren't committed.
+fn hunks_to_write(
+    ctx: &CommandContext,
+    repo: &git2::Repository,
+    hunks_by_branch: &[(Branch, BranchStatus)],
+    workspace_head: impl FnOnce() -> Result<git2::Oid>,
+) -> Result<Vec<BranchStatus>> {
+    let mut files_to_write: Vec<BranchStatus> = hunks_by_branch
+        .iter()
+        .map(|(_, files)| files.clone())
+        .collect();
+    if ctx.project().diff_settings().whitespace.is_none() {
+        return Ok(files_to_write);
+    }
+
+    let mut branch_count_by_path = HashMap::<&PathBuf, usize>::new();
+    for path in hunks_by_branch.iter().flat_map(|(_, files)| files.keys()) {
+        *branch_count_by_path.entry(path).or_default() += 1;
+    }
+    let paths: Vec<PathBuf> = hunks_by_branch
+        .iter()
+        .flat_map(|(_, files)| files)
+        .filter(|(path, hunks)| {
+            branch_count_by_path[path] == 1
+                && hunks
+                    .iter()
+                    .all(|hunk| hunk.change_type == ChangeType::Modified && !hunk.binary)
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    if paths.is_empty() {
+        return Ok(files_to_write);
+    }
+
+    let hunks_with_whitespace: HashMap<_, _> = diff_files_into_hunks(
+        gitbutler_diff::workdir_with_whitespace(repo, workspace_head()?, &paths)?,
+    )
+    .collect();
+    for (path, hunks) in files_to_write.iter_mut().flatten() {
+        if let Some(hunks_with_whitespace) = hunks_with_whitespace.get(path) {
+            *hunks = hunks_with_whitespace.clone();
+        }
+    }
+    Ok(files_to_write)
+}
+
+/// Assign the hunks of `base_file_diffs` to the applied virtual branches by their ownership and locks,
+/// and return each branch with its updated ownership and hunks, along with the locks of all hunks.
+fn assign_hunks_to_branches(
+    ctx: &CommandContext,
+    perm: Option<&mut WorktreeWritePermission>,
+    base_file_diffs: gitbutler_diff::DiffByPathMap,
+) -> Result<(
+    Vec<(Branch, BranchStatus)>,
+    HashMap<HunkHash, Vec<HunkLock>>,
+)> {
+    let mut virtual_branches = ctx
+        .project()
+        .virtual_branches()
+        .list_branches_in_workspace()?;
     let mut base_diffs: HashMap<_, _> = diff_files_into_hunks(base_file_diffs).collect();
 
     // sort by order, so that the default branch is first (left in the ui)
@@ -90,11 +233,28 @@ pub fn get_applied_status_cached(
             .collect();
 
     let vb_state = ctx.project().virtual_branches();
-    let base_tree = ctx
-        .repository()
-        .find_commit(vb_state.get_default_target()?.sha)?
-        .tree()?;
-    let locks = compute_locks(ctx.repository(), &base_diffs, &virtual_branches, base_tree)?;
+    let target_sha = vb_state.get_default_target()?.sha;
+    let locks = compute_locks(ctx.repository(), &base_diffs, &virtual_branches, target_sha)?;
+
+    // Files that were moved stay with the branches that owned them before.
+    let renamed_files: Vec<_> = base_diffs
+        .iter()
+        .filter_map(|(path, hunks)| {
+            let old_path = hunks
+                .iter()
+                .filter(|hunk| hunk.change_type == ChangeType::Renamed)
+                .find_map(|hunk| hunk.old_path.clone())?;
+            let hunks: Vec<Hunk> = hunks.iter().map(Hunk::from).collect();
+            Some((old_path, path.clone(), hunks))
+        })
+        .collect();
+    for branch in &mut virtual_branches {
+        for (old_path, new_path, hunks) in &renamed_files {
+            branch
+                .ownership
+                .follow_rename(old_path, new_path, hunks.clone());
+        }
+    }
 
     for branch in &mut virtual_branches {
         let old_claims = branch.ownership.claims.clone();
@@ -196,7 +356,7 @@ pub fn get_applied_status_cached(
         }
     }
 
-    let mut hunks_by_branch = diffs_by_branch
+    let hunks_by_branch = diffs_by_branch
         .into_iter()
         .map(|(branch_id, hunks)| {
             (
@@ -210,43 +370,16 @@ pub fn get_applied_status_cached(
         })
         .collect::<Vec<_>>();
 
-    // write updated state if not resolving
-    if !ctx.is_resolving() {
-        for (vbranch, files) in &mut hunks_by_branch {
-            vbranch.tree = gitbutler_diff::write::hunks_onto_oid(ctx, vbranch.head, files)?;
-            vb_state
-                .set_branch(vbranch.clone())
-                .context(format!("failed to write virtual branch {}", vbranch.name))?;
-        }
-    }
-    let hunks_by_branch: Vec<(Branch, HashMap<PathBuf, Vec<VirtualBranchHunk>>)> = hunks_by_branch
-        .iter()
-        .map(|(branch, hunks)| {
-            let hunks = file_hunks_from_diffs(&ctx.project().path, hunks.clone(), Some(&locks));
-            (branch.clone(), hunks)
-        })
-        .collect();
-
-    let files_by_branch: Vec<(Branch, Vec<VirtualBranchFile>)> = hunks_by_branch
-        .iter()
-        .map(|(branch, hunks)| {
-            let files = virtual_hunks_into_virtual_files(ctx, hunks.clone());
-            (branch.clone(), files)
-        })
-        .collect();
-
-    Ok(VirtualBranchesStatus {
-        branches: files_by_branch,
-        skipped_files,
-    })
+    Ok((hunks_by_branch, locks))
 }
 
 fn compute_locks(
     repository: &git2::Repository,
     unstaged_hunks_by_path: &HashMap<PathBuf, Vec<gitbutler_diff::GitHunk>>,
     virtual_branches: &[Branch],
-    base_tree: Tree,
+    target_sha: git2::Oid,
 ) -> Result<HashMap<HunkHash, Vec<HunkLock>>> {
+    let base_tree = repository.find_commit(target_sha)?.tree()?;
     let mut diff_opts = git2::DiffOptions::new();
     let opts = diff_opts
         .show_binary(true)
@@ -289,39 +422,151 @@ fn compute_locks(
         .iter()
         .filter_map(|(path, hunks)| {
             let workspace_hunks = workspace_hunks_by_path.get(path)?;
-
-            let (unapplied_hunk, branches) = hunks.iter().find_map(|unapplied_hunk| {
+            Some(hunks.iter().filter_map(move |unapplied_hunk| {
                 // Find all branches that have a hunk that intersects with the unapplied hunk
-                let locked_to = workspace_hunks
+                let locks = workspace_hunks
                     .iter()
-                    .filter_map(|(workspace_hunk, branch)| {
-                        if GitHunk::workspace_intersects_unapplied(workspace_hunk, unapplied_hunk) {
-                            Some(*branch)
-                        } else {
-                            None
-                        }
+                    .filter(|(workspace_hunk, _)| {
+                        GitHunk::workspace_intersects_unapplied(workspace_hunk, unapplied_hunk)
+                    })
+                    .map(|(_, branch)| *branch)
+                    .unique_by(|branch| branch.id)
+                    .flat_map(|branch| {
+                        locks_to_commits(repository, path, unapplied_hunk, branch, target_sha)
                     })
                     .collect::<Vec<_>>();
-                if locked_to.is_empty() {
+                if locks.is_empty() {
                     None
                 } else {
-                    Some((unapplied_hunk, locked_to))
+                    Some((Hunk::hash_diff(&unapplied_hunk.diff_lines), locks))
                 }
-            })?;
-
-            let hash = Hunk::hash_diff(&unapplied_hunk.diff_lines);
-            let locks = branches
-                .iter()
-                .map(|b| HunkLock {
-                    branch_id: b.id,
-                    commit_id: b.head,
-                })
-                .collect::<Vec<_>>();
-
-            // For now we're returning an array of locks to align with the original type, even though this implementation doesn't give multiple locks for the same hunk
-            Some((hash, locks))
+            }))
         })
+        .flatten()
         .collect::<HashMap<_, _>>();
 
     Ok(locked_hunks)
 }
+
+/// Return the locks of `hunk` of the file at `path` to the commits of `branch` that last changed the lines it
+/// modifies, or the lines around it if these weren't changed by the branch.
+/// If neither were, as far as blame can tell, the hunk is locked to the head of the branch.
+fn locks_to_commits(
+    repository: &git2::Repository,
+    path: &Path,
+    hunk: &GitHunk,
+    branch: &Branch,
+    target_sha: git2::Oid,
+) -> Vec<HunkLock> {
+    let old_lines = (hunk.old_start..hunk.old_start + hunk.old_lines).collect::<Vec<_>>();
+    let commit_ids = [changed_old_lines(hunk), old_lines]
+        .into_iter()
+        .map(|lines| blamed_commits(repository, path, &lines, branch.head, target_sha))
+        .find(|commit_ids| !commit_ids.is_empty())
+        .unwrap_or_else(|| vec![branch.head]);
+    commit_ids
+        .into_iter()
+        .map(|commit_id| HunkLock {
+            branch_id: branch.id,
+            commit_id,
+        })
+        .collect()
+}
+
+/// Return the commits since `target_sha` that last changed the 1-based `lines` of the file at `path`
+/// as of `head`.
+fn blamed_commits(
+    repository: &git2::Repository,
+    path: &Path,
+    lines: &[u32],
+    head: git2::Oid,
+    target_sha: git2::Oid,
+) -> Vec<git2::Oid> {
+    let (Some(min_line), Some(max_line)) = (lines.iter().min(), lines.iter().max()) else {
+        return vec![];
+    };
+    let Ok(blame) = repository.blame(path, *min_line, *max_line, target_sha, head) else {
+        return vec![];
+    };
+    lines
+        .iter()
+        .filter_map(|line| blame.get_line(*line as usize))
+        .filter(|blame_hunk| !blame_hunk.is_boundary())
+        .map(|blame_hunk| blame_hunk.final_commit_id())
+        .unique()
+        .collect()
+}
+
+/// Return the 1-based line numbers of the old version of the file that `hunk` removes, or the lines around
+/// the places it adds lines if it doesn't remove any.
+fn changed_old_lines(hunk: &GitHunk) -> Vec<u32> {
+    let mut removed = Vec::new();
+    let mut around_added = Vec::new();
+    // without context lines, a hunk that only adds lines starts at the line it adds them after
+    let mut old_line = if hunk.old_lines == 0 {
+        hunk.old_start + 1
+    } else {
+        hunk.old_start
+    };
+    for line in hunk.diff_lines.lines().skip(1) {
+        match line.first() {
+            Some(b'-') => {
+                removed.push(old_line);
+                old_line += 1;
+            }
+            Some(b'+') => around_added.extend([old_line.saturating_sub(1), old_line]),
+            Some(b'\\') => {}
+            _ => old_line += 1,
+        }
+    }
+    if removed.is_empty() {
+        around_added.retain(|line| *line > 0);
+        around_added.dedup();
+        around_added
+    } else {
+        removed
+    }
+}
+
+/// Find the uncommitted hunks that are adjacent to uncommitted hunks of the same file
+/// in other branches, as these can't be committed independently of each other.
+fn compute_dependencies(
+    hunks_by_branch: &[(Branch, HashMap<PathBuf, Vec<GitHunk>>)],
+) -> HashMap<HunkHash, Vec<HunkDependency>> {
+    let mut hunks_by_path = HashMap::<&PathBuf, Vec<(&GitHunk, BranchId)>>::new();
+    for (branch, hunks_by_filepath) in hunks_by_branch {
+        for (path, hunks) in hunks_by_filepath {
+            hunks_by_path
+                .entry(path)
+                .or_default()
+                .extend(hunks.iter().map(|hunk| (hunk, branch.id)));
+        }
+    }
+
+    let mut dependencies = HashMap::<HunkHash, Vec<HunkDependency>>::new();
+    for hunks in hunks_by_path.values() {
+        for (hunk, branch_id) in hunks {
+            for (other_hunk, other_branch_id) in hunks {
+                if branch_id == other_branch_id
+                    || hunk.binary
+                    || other_hunk.binary
+                    || !hunk.is_adjacent_to(other_hunk)
+                {
+                    continue;
+                }
+                dependencies
+                    .entry(Hunk::hash_diff(&hunk.diff_lines))
+                    .or_default()
+                    .push(HunkDependency {
+                        branch_id: *other_branch_id,
+                        hunk_id: VirtualBranchHunk::gen_id(
+                            other_hunk.new_start,
+                            other_hunk.new_lines,
+                        ),
+                        hash: Hunk::hash_diff(&other_hunk.diff_lines),
+                    });
+            }
+        }
+    }
+    dependencies
+}
@@ -7,30 +7,33 @@ use crate::{
     integration::get_workspace_head,
     remote::{branch_to_remote_branch, RemoteBranch},
     status::{get_applied_status, get_applied_status_cached},
-    Get, VirtualBranchesExt,
+    DiffSettingsExt, Get, VirtualBranchesExt,
 };
 use anyhow::{anyhow, bail, Context, Result};
-use bstr::{BString, ByteSlice};
-use git2_hooks::HookResult;
+use bstr::{BString, ByteSlice, ByteVec};
 use gitbutler_branch::{
     dedup, dedup_fmt, reconcile_claims, signature, Branch, BranchId, BranchOwnershipClaims,
     BranchUpdateRequest, OwnershipClaim, SignaturePurpose, Target, VirtualBranchesHandle,
 };
-use gitbutler_cherry_pick::RepositoryExt as _;
+use gitbutler_cherry_pick::{ConflictedTreeKey, RepositoryExt as _};
 use gitbutler_command_context::CommandContext;
-use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
-use gitbutler_diff::{trees, GitHunk, Hunk};
-use gitbutler_error::error::{Code, Marker};
+use gitbutler_commit::{
+    commit_ext::CommitExt,
+    commit_headers::{CommitHeadersV2, HasCommitHeaders},
+};
+use gitbutler_diff::{trees, ChangeType, GitHunk, Hunk};
+use gitbutler_error::error::{self, Code, Marker};
 use gitbutler_operating_modes::assure_open_workspace_mode;
-use gitbutler_project::access::WorktreeWritePermission;
+use gitbutler_project::access::{WorktreeReadPermission, WorktreeWritePermission};
 use gitbutler_reference::{normalize_branch_name, Refname, RemoteRefname};
 use gitbutler_repo::{
     credentials::Helper,
-    rebase::{cherry_rebase, cherry_rebase_group},
+    rebase::{cherry_rebase, cherry_rebase_group, cherry_rebase_group_in},
     LogUntil, RepoActionsExt, RepositoryExt,
 };
 use gitbutler_time::time::now_since_unix_epoch_ms;
-use serde::Serialize;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::{
     borrow::Cow,
@@ -133,7 +136,16 @@ pub fn unapply_ownership(
         .collect::<Vec<_>>();
 
     let mut diff = HashMap::new();
+    // new path -> old path of the renamed files to unapply
+    let mut renames = HashMap::new();
     for h in hunks_to_unapply {
+        // A rename is undone as a whole, along with all changes to the content of the file.
+        if h.1.change_type == ChangeType::Renamed {
+            if let Some(old_path) = h.1.old_path {
+                renames.insert(h.0, old_path);
+                continue;
+            }
+        }
         if let Some(reversed_hunk) = gitbutler_diff::reverse_hunk(&h.1) {
             diff.entry(h.0).or_insert_with(Vec::new).push(reversed_hunk);
         } else {
@@ -166,7 +178,18 @@ pub fn unapply_ownership(
         },
     )?;
 
-    let final_tree_oid = gitbutler_diff::write::hunks_onto_tree(ctx, &final_tree, diff)?;
+    let mut final_tree_oid = gitbutler_diff::write::hunks_onto_tree(ctx, &final_tree, diff)?;
+    if !renames.is_empty() {
+        let mut builder = git2::build::TreeUpdateBuilder::new();
+        for (new_path, old_path) in &renames {
+            builder.remove(new_path);
+            let entry = base_tree
+                .get_path(old_path)
+                .with_context(|| format!("failed to find {} to restore", old_path.display()))?;
+            builder.upsert(old_path, entry.id(), file_mode(&entry));
+        }
+        final_tree_oid = builder.create_updated(repo, &repo.find_tree(final_tree_oid)?)?;
+    }
     let final_tree = repo
         .find_tree(final_tree_oid)
         .context("failed to find tree")?;
@@ -183,14 +206,38 @@ pub fn unapply_ownership(
 }
 
 // reset a file in the project to the index state
-pub(crate) fn reset_files(ctx: &CommandContext, files: &Vec<String>) -> Result<()> {
+pub(crate) fn reset_files(
+    ctx: &CommandContext,
+    files: &Vec<String>,
+    perm: &WorktreeReadPermission,
+) -> Result<()> {
     ctx.assure_resolved()?;
 
+    // new path -> old path of renamed files, whose old path is restored along with them
+    let renames: HashMap<PathBuf, PathBuf> = if ctx.project().rename_detection.is_some() {
+        crate::branch::get_uncommited_files_raw(ctx, perm)?
+            .into_iter()
+            .filter_map(|(path, file)| {
+                file.hunks
+                    .iter()
+                    .find(|hunk| hunk.change_type == ChangeType::Renamed)
+                    .and_then(|hunk| hunk.old_path.clone())
+                    .map(|old_path| (path, old_path))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
     // for each tree, we need to checkout the entry from the index at that path
     // or if it doesn't exist, remove the file from the working directory
     let repo = ctx.repository();
     let index = repo.index().context("failed to get index")?;
     for file in files {
+        if let Some(old_path) = renames.get(Path::new(file)) {
+            repo.checkout_index_path_builder(old_path)
+                .context("failed to checkout index")?;
+        }
         let entry = index.get_path(Path::new(file), 0);
         if entry.is_some() {
             repo.checkout_index_path_builder(Path::new(file))
@@ -568,55 +615,19 @@ pub fn integrate_upstream_commits(ctx: &CommandContext, branch_id: BranchId) ->
     let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
     let default_target = vb_state.get_default_target()?;
 
-    let upstream_branch = branch.upstream.as_ref().context("upstream not found")?;
-    let upstream_oid = repo.refname_to_id(&upstream_branch.to_string())?;
-    let upstream_commit = repo.find_commit(upstream_oid)?;
-
-    if upstream_commit.id() == branch.head {
-        return Ok(());
-    }
-
-    let upstream_commits = ctx.list_commits(upstream_commit.id(), default_target.sha)?;
-    let branch_commits = ctx.list_commits(branch.head, default_target.sha)?;
-
-    let branch_commit_ids = branch_commits.iter().map(|c| c.id()).collect::<Vec<_>>();
-
-    let branch_change_ids = branch_commits
-        .iter()
-        .filter_map(|c| c.change_id())
-        .collect::<Vec<_>>();
-
-    let mut unknown_commits: Vec<git2::Oid> = upstream_commits
-        .iter()
-        .filter(|c| {
-            (!c.change_id()
-                .is_some_and(|cid| branch_change_ids.contains(&cid)))
-                && !branch_commit_ids.contains(&c.id())
-        })
-        .map(|c| c.id())
-        .collect::<Vec<_>>();
-
-    let rebased_commits = upstream_commits
-        .iter()
-        .filter(|c| {
-            c.change_id()
-                .is_some_and(|cid| branch_change_ids.contains(&cid))
-                && !branch_commit_ids.contains(&c.id())
-        })
-        .map(|c| c.id())
-        .collect::<Vec<_>>();
-
-    // If there are no new commits then there is nothing to do.
-    if unknown_commits.is_empty() {
+    let Some(UpstreamCommits {
+        upstream_commit,
+        mut unknown_commits,
+        has_rebased_commits,
+        merge_base,
+    }) = upstream_commits_to_integrate(ctx, &branch, &default_target)?
+    else {
         return Ok(());
     };
 
-    let merge_base = repo.merge_base(default_target.sha, upstream_oid)?;
-
     // Booleans needed for a decision on how integrate upstream commits.
     // let is_same_base = default_target.sha == merge_base;
     let can_use_force = branch.allow_rebasing;
-    let has_rebased_commits = !rebased_commits.is_empty();
 
     // We can't proceed if we rebased local commits but no permission to force push. In this
     // scenario we would need to "cherry rebase" new upstream commits onto the last rebased
@@ -676,6 +687,77 @@ pub fn integrate_upstream_commits(ctx: &CommandContext, branch_id: BranchId) ->
     Ok(())
 }
 
+/// The upstream commits of a branch that aren't part of it yet.
+struct UpstreamCommits<'repo> {
+    upstream_commit: git2::Commit<'repo>,
+    /// The commits that only exist upstream, from the upstream head downwards.
+    unknown_commits: Vec<git2::Oid>,
+    /// Whether commits of the branch were rebased, so they exist upstream under a different id.
+    has_rebased_commits: bool,
+    merge_base: git2::Oid,
+}
+
+/// Return the commits of the upstream of `branch` that would have to be integrated, or `None`
+/// if there is nothing to integrate.
+fn upstream_commits_to_integrate<'repo>(
+    ctx: &'repo CommandContext,
+    branch: &Branch,
+    default_target: &Target,
+) -> Result<Option<UpstreamCommits<'repo>>> {
+    let repo = ctx.repository();
+    let upstream_branch = branch.upstream.as_ref().context("upstream not found")?;
+    let upstream_oid = repo.refname_to_id(&upstream_branch.to_string())?;
+    let upstream_commit = repo.find_commit(upstream_oid)?;
+
+    if upstream_commit.id() == branch.head {
+        return Ok(None);
+    }
+
+    let upstream_commits = ctx.list_commits(upstream_commit.id(), default_target.sha)?;
+    let branch_commits = ctx.list_commits(branch.head, default_target.sha)?;
+
+    let branch_commit_ids = branch_commits.iter().map(|c| c.id()).collect::<Vec<_>>();
+
+    let branch_change_ids = branch_commits
+        .iter()
+        .filter_map(|c| c.change_id())
+        .collect::<Vec<_>>();
+
+    let unknown_commits: Vec<git2::Oid> = upstream_commits
+        .iter()
+        .filter(|c| {
+            (!c.change_id()
+                .is_some_and(|cid| branch_change_ids.contains(&cid)))
+                && !branch_commit_ids.contains(&c.id())
+        })
+        .map(|c| c.id())
+        .collect::<Vec<_>>();
+
+    let rebased_commits = upstream_commits
+        .iter()
+        .filter(|c| {
+            c.change_id()
+                .is_some_and(|cid| branch_change_ids.contains(&cid))
+                && !branch_commit_ids.contains(&c.id())
+        })
+        .map(|c| c.id())
+        .collect::<Vec<_>>();
+
+    // If there are no new commits then there is nothing to do.
+    if unknown_commits.is_empty() {
+        return Ok(None);
+    };
+
+    let merge_base = repo.merge_base(default_target.sha, upstream_oid)?;
+
+    Ok(Some(UpstreamCommits {
+        upstream_commit,
+        unknown_commits,
+        has_rebased_commits: !rebased_commits.is_empty(),
+        merge_base,
+    }))
+}
+
 pub(crate) fn integrate_with_rebase(
     ctx: &CommandContext,
     branch: &mut Branch,
@@ -734,6 +816,223 @@ pub(crate) fn integrate_with_merge(
     )
 }
 
+/// A way of integrating the upstream commits of a branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrationStrategy {
+    /// Put the upstream commits on top of the commits of the branch.
+    Rebase,
+    /// Merge the upstream commits into the branch with a merge commit.
+    Merge,
+}
+
+/// What a branch would look like after integrating its upstream commits with a particular strategy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationStrategyPreview {
+    pub strategy: IntegrationStrategy,
+    /// The commits of the branch after the integration, from its new head down to the default target.
+    /// New commits only exist in memory, so their ids can't be looked up.
+    /// It's empty if a merge conflicts, as conflicts then have to be resolved in the worktree first.
+    pub commits: Vec<IntegrationPreviewCommit>,
+    /// The files that would be conflicting, sorted and without duplicates.
+    pub conflicted_files: Vec<PathBuf>,
+    /// Whether the branch would have to be force-pushed to its upstream afterwards.
+    pub requires_force_push: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrationPreviewCommit {
+    #[serde(with = "gitbutler_serde::oid")]
+    pub id: git2::Oid,
+    pub description: String,
+    pub conflicted: bool,
+}
+
+/// The outcome of integrating the upstream commits of a branch with each of the available strategies.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamIntegrationPreview {
+    /// The strategy [`integrate_upstream_commits()`] would use, or `None` if there is nothing to integrate
+    /// or if it would refuse to integrate.
+    pub selected: Option<IntegrationStrategy>,
+    /// The previews of all strategies, empty if there is nothing to integrate.
+    pub strategies: Vec<IntegrationStrategyPreview>,
+}
+
+/// Compute what [`integrate_upstream_commits()`] would do with the branch identified by `branch_id`,
+/// for each strategy, without writing anything to disk.
+pub fn preview_integrate_upstream_commits(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+) -> Result<UpstreamIntegrationPreview> {
+    let vb_state = ctx.project().virtual_branches();
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+
+    let Some(UpstreamCommits {
+        upstream_commit,
+        mut unknown_commits,
+        has_rebased_commits,
+        merge_base,
+    }) = upstream_commits_to_integrate(ctx, &branch, &default_target)?
+    else {
+        return Ok(UpstreamIntegrationPreview {
+            selected: None,
+            strategies: vec![],
+        });
+    };
+
+    let selected = if branch.allow_rebasing {
+        Some(IntegrationStrategy::Rebase)
+    } else if has_rebased_commits {
+        None
+    } else {
+        Some(IntegrationStrategy::Merge)
+    };
+
+    let repo = ctx.repository().in_memory_repo()?;
+    let upstream_commit = repo.find_commit(upstream_commit.id())?;
+    // Like the integration itself, include uncommitted changes. As the ODB is in memory, nothing is written to disk.
+    let wd_tree = repo.create_wd_tree()?;
+    let workspace_tree = repo.find_commit(get_workspace_head(ctx)?)?.tree()?;
+    let preview = |strategy, new_head: Option<git2::Oid>, conflicted_files| {
+        integration_preview(
+            &repo,
+            strategy,
+            new_head,
+            &workspace_tree,
+            &wd_tree,
+            upstream_commit.id(),
+            default_target.sha,
+            conflicted_files,
+        )
+    };
+
+    // Record conflicts in conflicted commits so they can be shown, even if the project doesn't allow it.
+    let rebased_head = cherry_rebase_group_in(&repo, true, branch.head, &mut unknown_commits)?;
+    let rebase = preview(IntegrationStrategy::Rebase, Some(rebased_head), vec![])?;
+
+    let merge_tree = repo.find_commit(merge_base)?.tree()?;
+    let mut merge_index =
+        repo.merge_trees(&merge_tree, &wd_tree, &upstream_commit.tree()?, None)?;
+    let merge = if merge_index.has_conflicts() {
+        preview(
+            IntegrationStrategy::Merge,
+            None,
+            conflicted_paths(&merge_index)?,
+        )?
+    } else {
+        let upstream_branch = branch.upstream.as_ref().context("upstream not found")?;
+        let merge_tree = repo.find_tree(merge_index.write_tree_to(&repo)?)?;
+        let head_commit = repo.find_commit(branch.head)?;
+        let committer = signature(SignaturePurpose::Committer)?;
+        let merge_commit = repo.commit(
+            None,
+            &committer,
+            &committer,
+            &format!(
+                "Merged {}/{} into {}",
+                upstream_branch.remote(),
+                upstream_branch.branch(),
+                branch.name
+            ),
+            &merge_tree,
+            &[&head_commit, &upstream_commit],
+        )?;
+        preview(IntegrationStrategy::Merge, Some(merge_commit), vec![])?
+    };
+
+    Ok(UpstreamIntegrationPreview {
+        selected,
+        strategies: vec![rebase, merge],
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn integration_preview(
+    repo: &git2::Repository,
+    strategy: IntegrationStrategy,
+    new_head: Option<git2::Oid>,
+    workspace_tree: &git2::Tree,
+    wd_tree: &git2::Tree,
+    upstream_id: git2::Oid,
+    target_id: git2::Oid,
+    mut conflicted_files: Vec<PathBuf>,
+) -> Result<IntegrationStrategyPreview> {
+    let mut commits = Vec::new();
+    let mut requires_force_push = false;
+    if let Some(new_head) = new_head {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push(new_head)?;
+        revwalk.hide(target_id)?;
+        for id in revwalk {
+            let commit = repo.find_commit(id?)?;
+            conflicted_files.extend(conflicted_commit_paths(repo, &commit)?);
+            commits.push(IntegrationPreviewCommit {
+                id: commit.id(),
+                description: commit.message_bstr().to_str_lossy().into_owned(),
+                conflicted: commit.is_conflicted(),
+            });
+        }
+
+        // The new head is merged with the uncommitted changes, which may conflict as well.
+        let new_head_tree = repo.find_commit(new_head)?.tree()?;
+        let merge_index = repo.merge_trees(workspace_tree, &new_head_tree, wd_tree, None)?;
+        conflicted_files.extend(conflicted_paths(&merge_index)?);
+
+        requires_force_push =
+            new_head != upstream_id && !repo.graph_descendant_of(new_head, upstream_id)?;
+    }
+    conflicted_files.sort();
+    conflicted_files.dedup();
+
+    Ok(IntegrationStrategyPreview {
+        strategy,
+        commits,
+        conflicted_files,
+        requires_force_push,
+    })
+}
+
+/// Return the paths of the files that are conflicted in `commit`, if it's a conflicted commit.
+pub(crate) fn conflicted_commit_paths(
+    repo: &git2::Repository,
+    commit: &git2::Commit,
+) -> Result<Vec<PathBuf>> {
+    if !commit.is_conflicted() {
+        return Ok(vec![]);
+    }
+    let tree = commit.tree()?;
+    let entry = tree
+        .get_name(&ConflictedTreeKey::ConflictFiles)
+        .context("conflicted commit is missing its conflicted files")?;
+    let blob = repo.find_blob(entry.id())?;
+    Ok(blob
+        .content()
+        .lines()
+        .map(|path| gix::path::from_bstr(path.as_bstr()).into_owned())
+        .collect())
+}
+
+/// Return the paths of the files that are conflicted in `index`.
+pub(crate) fn conflicted_paths(index: &git2::Index) -> Result<Vec<PathBuf>> {
+    if !index.has_conflicts() {
+        return Ok(vec![]);
+    }
+    index
+        .conflicts()?
+        .flatten()
+        .filter_map(|conflict| conflict.our.or(conflict.their))
+        .map(|entry| {
+            gix::path::try_from_bstr(Cow::Owned(entry.path.into()))
+                .map(Cow::into_owned)
+                .map_err(Into::into)
+        })
+        .collect()
+}
+
 pub fn update_branch(ctx: &CommandContext, branch_update: &BranchUpdateRequest) -> Result<Branch> {
     let vb_state = ctx.project().virtual_branches();
     let mut branch = vb_state.get_branch_in_workspace(branch_update.id)?;
@@ -866,6 +1165,60 @@ pub(crate) fn set_ownership(
     Ok(())
 }
 
+/// Refuse to move uncommitted hunks into `target_branch_id` as claimed by `ownership` if that would
+/// separate them from the hunks they are adjacent to in `statuses`.
+pub(crate) fn ensure_dependent_hunks_stay_together(
+    statuses: &[(Branch, Vec<VirtualBranchFile>)],
+    target_branch_id: BranchId,
+    ownership: &BranchOwnershipClaims,
+) -> Result<()> {
+    let hunks: Vec<_> = statuses
+        .iter()
+        .flat_map(|(branch, files)| {
+            files.iter().flat_map(move |file| {
+                file.hunks
+                    .iter()
+                    .map(move |hunk| (branch.id, GitHunk::from(hunk.clone()), hunk))
+            })
+        })
+        .collect();
+    let is_claimed = |git_hunk: &GitHunk, hunk: &VirtualBranchHunk| {
+        ownership.claims.iter().any(|claim| {
+            claim.file_path == hunk.file_path
+                && claim.hunks.iter().any(|claimed_hunk| {
+                    claimed_hunk == &Hunk::from(git_hunk) || claimed_hunk.intersects(git_hunk)
+                })
+        })
+    };
+
+    for (branch_id, git_hunk, hunk) in &hunks {
+        if *branch_id == target_branch_id || !is_claimed(git_hunk, hunk) {
+            continue;
+        }
+        let separated_hunk = hunks
+            .iter()
+            .find(|(other_branch_id, other_git_hunk, other)| {
+                other.file_path == hunk.file_path
+                    && other.id != hunk.id
+                    && *other_branch_id != target_branch_id
+                    && !is_claimed(other_git_hunk, other)
+                    && !git_hunk.binary
+                    && !other_git_hunk.binary
+                    && git_hunk.is_adjacent_to(other_git_hunk)
+            });
+        if let Some((_, _, other)) = separated_hunk {
+            bail!(error::Context::new(format!(
+                "hunk {path}:{id} can't be moved without the adjacent hunk {path}:{other_id}",
+                path = hunk.file_path.display(),
+                id = hunk.id,
+                other_id = other.id
+            ))
+            .with_code(Code::Validation));
+        }
+    }
+    Ok(())
+}
+
 pub type BranchStatus = HashMap<PathBuf, Vec<gitbutler_diff::GitHunk>>;
 pub type VirtualBranchHunksByPathMap = HashMap<PathBuf, Vec<VirtualBranchHunk>>;
 
@@ -913,9 +1266,23 @@ pub(crate) fn reset_branch(
             .find_commit(old_head)?
             .tree()
             .map_err(anyhow::Error::from)?,
+        Default::default(),
     )?;
 
     // Assign the new hunks to the branch we're working on.
+    claim_hunks(&mut branch, diff)?;
+    vb_state
+        .set_branch(branch)
+        .context("failed to write branch")?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+
+    Ok(())
+}
+
+/// Add all hunks of `diff`, a diff from the workspace head, to the ownership of `branch`.
+fn claim_hunks(branch: &mut Branch, diff: gitbutler_diff::DiffByPathMap) -> Result<()> {
     for (path, filediff) in diff {
         for hunk in filediff.hunks {
             let hash = Hunk::hash_diff(&hunk.diff_lines);
@@ -931,13 +1298,6 @@ pub(crate) fn reset_branch(
             );
         }
     }
-    vb_state
-        .set_branch(branch)
-        .context("failed to write branch")?;
-
-    crate::integration::update_workspace_commit(&vb_state, ctx)
-        .context("failed to update gitbutler workspace")?;
-
     Ok(())
 }
 
@@ -949,34 +1309,147 @@ pub fn commit(
     ownership: Option<&BranchOwnershipClaims>,
     run_hooks: bool,
 ) -> Result<git2::Oid> {
-    let mut message_buffer = message.to_owned();
+    commit_selected(ctx, branch_id, message, None, run_hooks, |files| {
+        Ok(owned_hunks(files, ownership))
+    })
+}
 
-    if run_hooks {
-        let hook_result = git2_hooks::hooks_commit_msg(
-            ctx.repository(),
-            Some(&["../.husky"]),
-            &mut message_buffer,
-        )
-        .context("failed to run hook")
-        .context(Code::CommitHookFailed)?;
+/// Commit the hunks in `ownership`, or all uncommitted hunks of the branch with `branch_id`, as a fixup for
+/// the commit of the branch with `target_change_id`.
+///
+/// The commit is titled like `git commit --fixup` would, and remembers its target by change-id so that
+/// [`autosquash()`] folds it into the right commit even if that commit is reworded in the meantime.
+pub(crate) fn commit_fixup(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    target_change_id: &str,
+    ownership: Option<&BranchOwnershipClaims>,
+    run_hooks: bool,
+) -> Result<git2::Oid> {
+    let vb_state = ctx.project().virtual_branches();
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let target_commit = ctx
+        .log(branch.head, LogUntil::Commit(default_target.sha))?
+        .into_iter()
+        .find(|commit| commit.change_id().as_deref() == Some(target_change_id))
+        .with_context(|| format!("no commit with change-id {target_change_id} in the branch"))
+        .context(Code::Validation)?;
+
+    let message = format!("fixup! {}", commit_title(&target_commit).as_bstr());
+    let commit_headers = CommitHeadersV2 {
+        fixup_of: Some(target_change_id.to_owned()),
+        ..CommitHeadersV2::new()
+    };
+    commit_selected(
+        ctx,
+        branch_id,
+        &message,
+        Some(commit_headers),
+        run_hooks,
+        |files| Ok(owned_hunks(files, ownership)),
+    )
+}
 
-        if let HookResult::RunNotSuccessful { stdout, .. } = hook_result {
-            return Err(anyhow!("commit-msg hook rejected: {}", stdout.trim())
-                .context(Code::CommitHookFailed));
-        }
+/// Return the hunks of `files` that are in `ownership`, or all of them if there is no `ownership`.
+fn owned_hunks(
+    files: Vec<VirtualBranchFile>,
+    ownership: Option<&BranchOwnershipClaims>,
+) -> Vec<(PathBuf, Vec<VirtualBranchHunk>)> {
+    if let Some(ownership) = ownership {
+        files
+            .into_iter()
+            .filter_map(|file| {
+                let hunks = file
+                    .hunks
+                    .into_iter()
+                    .filter(|hunk| {
+                        let hunk: GitHunk = hunk.clone().into();
+                        ownership
+                            .claims
+                            .iter()
+                            .find(|f| f.file_path.eq(&file.path))
+                            .map_or(false, |f| {
+                                f.hunks.iter().any(|h| {
+                                    h.start == hunk.new_start
+                                        && h.end == hunk.new_start + hunk.new_lines
+                                })
+                            })
+                    })
+                    .collect::<Vec<_>>();
+                if hunks.is_empty() {
+                    None
+                } else {
+                    Some((file.path, hunks))
+                }
+            })
+            .collect()
+    } else {
+        files
+            .into_iter()
+            .map(|file| (file.path, file.hunks))
+            .collect()
+    }
+}
 
-        let hook_result = git2_hooks::hooks_pre_commit(ctx.repository(), Some(&["../.husky"]))
-            .context("failed to run hook")
-            .context(Code::CommitHookFailed)?;
+/// Lines of an uncommitted hunk, see [`commit_lines()`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineSelection {
+    /// The path of the file the hunk belongs to, relative to the worktree.
+    pub file_path: PathBuf,
+    /// The id of the hunk, as in [`VirtualBranchHunk::id`].
+    pub hunk_id: String,
+    /// The 0-based indices of the added or removed lines to commit within the hunk's diff, with the `@@` header
+    /// being the first line.
+    pub lines: Vec<u32>,
+}
 
-        if let HookResult::RunNotSuccessful { stdout, .. } = hook_result {
-            return Err(
-                anyhow!("commit hook rejected: {}", stdout.trim()).context(Code::CommitHookFailed)
-            );
-        }
-    }
+/// Commit only the lines in `selection` of a hunk owned by the branch with `branch_id`.
+/// The lines that aren't selected stay uncommitted, and owned by the branch.
+pub(crate) fn commit_lines(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    message: &str,
+    selection: &LineSelection,
+    run_hooks: bool,
+) -> Result<git2::Oid> {
+    commit_selected(ctx, branch_id, message, None, run_hooks, |files| {
+        let mut hunk = files
+            .into_iter()
+            .filter(|file| file.path == selection.file_path)
+            .flat_map(|file| file.hunks)
+            .find(|hunk| hunk.id == selection.hunk_id)
+            .with_context(|| {
+                format!(
+                    "hunk {}:{} isn't owned by branch {branch_id}",
+                    selection.file_path.display(),
+                    selection.hunk_id
+                )
+            })
+            .context(Code::Validation)?;
+        let partial = GitHunk::from(hunk.clone())
+            .select_lines(&selection.lines)
+            .context(Code::Validation)?;
+        hunk.diff = partial.diff_lines;
+        hunk.old_lines = partial.old_lines;
+        hunk.end = partial.new_start + partial.new_lines;
+        Ok(vec![(selection.file_path.clone(), vec![hunk])])
+    })
+}
 
-    let message = &message_buffer;
+/// Commit the hunks that `select` picks from the uncommitted files of the branch with `branch_id`,
+/// using `commit_headers`, or new ones if `None`.
+fn commit_selected(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    message: &str,
+    commit_headers: Option<CommitHeadersV2>,
+    run_hooks: bool,
+    select: impl FnOnce(Vec<VirtualBranchFile>) -> Result<Vec<(PathBuf, Vec<VirtualBranchHunk>)>>,
+) -> Result<git2::Oid> {
+    let mut message_buffer = message.to_owned();
+    let run_hooks = run_hooks && *ctx.project().run_git_hooks;
 
     // get the files to commit
     let statuses = get_applied_status(ctx, None)
@@ -993,39 +1466,37 @@ pub fn commit(
     ctx.assure_unconflicted()
         .context(Code::CommitMergeConflictFailure)?;
 
-    let tree_oid = if let Some(ownership) = ownership {
-        let files = files.into_iter().filter_map(|file| {
-            let hunks = file
-                .hunks
-                .into_iter()
-                .filter(|hunk| {
-                    let hunk: GitHunk = hunk.clone().into();
-                    ownership
-                        .claims
-                        .iter()
-                        .find(|f| f.file_path.eq(&file.path))
-                        .map_or(false, |f| {
-                            f.hunks.iter().any(|h| {
-                                h.start == hunk.new_start
-                                    && h.end == hunk.new_start + hunk.new_lines
-                            })
-                        })
-                })
-                .collect::<Vec<_>>();
-            if hunks.is_empty() {
-                None
-            } else {
-                Some((file.path, hunks))
-            }
-        });
-        gitbutler_diff::write::hunks_onto_commit(ctx, branch.head, files)?
-    } else {
-        let files = files
-            .into_iter()
-            .map(|file| (file.path, file.hunks))
-            .collect::<Vec<(PathBuf, Vec<VirtualBranchHunk>)>>();
-        gitbutler_diff::write::hunks_onto_commit(ctx, branch.head, files)?
-    };
+    let files = select(files)?;
+
+    // Committing a hunk without the uncommitted hunks it depends on in other branches would break them.
+    let selected_hashes: HashSet<_> = files
+        .iter()
+        .flat_map(|(_, hunks)| hunks.iter().map(|hunk| hunk.hash))
+        .collect();
+    if let Some(hunk) = files.iter().flat_map(|(_, hunks)| hunks).find(|hunk| {
+        hunk.depends_on.iter().any(|dependency| {
+            dependency.branch_id != branch.id && !selected_hashes.contains(&dependency.hash)
+        })
+    }) {
+        bail!(error::Context::new(format!(
+            "hunk {}:{} can't be committed without the adjacent hunks in other branches",
+            hunk.file_path.display(),
+            hunk.id
+        ))
+        .with_code(Code::Validation));
+    }
+
+    let tree_oid = gitbutler_diff::write::hunks_onto_commit(ctx, branch.head, files)?;
+
+    if run_hooks {
+        gitbutler_repo::hooks::run_commit_hooks(
+            ctx.repository(),
+            branch.head,
+            tree_oid,
+            &mut message_buffer,
+        )?;
+    }
+    let message = &gitbutler_repo::message_policy::apply(ctx.repository(), &message_buffer)?;
 
     let git_repository = ctx.repository();
     let parent_commit = git_repository
@@ -1045,19 +1516,22 @@ pub fn commit(
             let merge_parent = git_repository
                 .find_commit(merge_parent)
                 .context(format!("failed to find merge parent {:?}", merge_parent))?;
-            let commit_oid = ctx.commit(message, &tree, &[&parent_commit, &merge_parent], None)?;
+            let commit_oid = ctx.commit(
+                message,
+                &tree,
+                &[&parent_commit, &merge_parent],
+                commit_headers,
+            )?;
             conflicts::clear(ctx)
                 .context("failed to clear conflicts")
                 .context(Code::CommitMergeConflictFailure)?;
             commit_oid
         }
-        None => ctx.commit(message, &tree, &[&parent_commit], None)?,
+        None => ctx.commit(message, &tree, &[&parent_commit], commit_headers)?,
     };
 
     if run_hooks {
-        git2_hooks::hooks_post_commit(ctx.repository(), Some(&["../.husky"]))
-            .context("failed to run hook")
-            .context(Code::CommitHookFailed)?;
+        gitbutler_repo::hooks::run_post_commit_hook(ctx.repository())?;
     }
 
     let vb_state = ctx.project().virtual_branches();
@@ -1116,10 +1590,26 @@ pub(crate) fn push(
         ))
     };
 
+    if *ctx.project().run_git_hooks {
+        let remote_id = ctx
+            .repository()
+            .refname_to_id(&remote_branch.to_string())
+            .ok();
+        gitbutler_repo::hooks::run_pre_push_hook(
+            ctx.repository(),
+            remote_branch.remote(),
+            &vbranch.refname()?.to_string(),
+            vbranch.head,
+            &format!("refs/heads/{}", remote_branch.branch()),
+            remote_id,
+        )?;
+    }
+
     ctx.push(
         vbranch.head,
         &remote_branch,
         with_force,
+        None,
         credentials,
         None,
         askpass,
@@ -1289,8 +1779,12 @@ pub(crate) fn move_commit_file(
     let mut upstream_commits = ctx.l(target_branch.head, LogUntil::Commit(amend_commit.id()))?;
 
     // get a list of all the diffs across all the virtual branches
-    let base_file_diffs = gitbutler_diff::workdir(ctx.repository(), default_target.sha)
-        .context("failed to diff workdir")?;
+    let base_file_diffs = gitbutler_diff::workdir(
+        ctx.repository(),
+        default_target.sha,
+        ctx.project().diff_settings(),
+    )
+    .context("failed to diff workdir")?;
 
     // filter base_file_diffs to HashMap<filepath, Vec<GitHunk>> only for hunks in target_ownership
     // this is essentially the group of patches that we're "moving"
@@ -1347,9 +1841,13 @@ pub(crate) fn move_commit_file(
         // we need to remove the parts of this patch that are in target_ownership (the parts we're moving)
         // and then apply the rest to the parent tree of the "from" commit to
         // create the new "from" commit without the changes we're moving
-        let from_commit_diffs =
-            gitbutler_diff::trees(ctx.repository(), &from_parent_tree, &from_tree)
-                .context("failed to diff trees")?;
+        let from_commit_diffs = gitbutler_diff::trees(
+            ctx.repository(),
+            &from_parent_tree,
+            &from_tree,
+            Default::default(),
+        )
+        .context("failed to diff trees")?;
 
         // filter from_commit_diffs to HashMap<filepath, Vec<GitHunk>> only for hunks NOT in target_ownership
         // this is the patch parts we're keeping
@@ -1612,6 +2110,146 @@ pub(crate) fn amend(
     }
 }
 
+/// Amend each uncommitted hunk of the branch with `branch_id` into the commit of the branch it's locked to,
+/// like `git absorb` does, and return the number of hunks that were absorbed.
+///
+/// A hunk is locked to the commits that last changed the lines it modifies, or the lines around it if it only
+/// adds lines. Hunks that are locked to other branches, to more than one commit or to a pushed commit that may
+/// not be rewritten stay uncommitted, as do hunks that conflict with the commits they would have to be
+/// reordered with.
+pub(crate) fn absorb(ctx: &CommandContext, branch_id: BranchId) -> Result<usize> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let default_target = vb_state.get_default_target()?;
+    let (mut branch, files) = get_applied_status(ctx, None)?
+        .branches
+        .into_iter()
+        .find(|(branch, _)| branch.id == branch_id)
+        .with_context(|| format!("branch {branch_id} not found"))?;
+
+    let mut commits = ctx.log(branch.head, LogUntil::Commit(default_target.sha))?;
+    commits.reverse();
+    if commits.iter().any(|commit| commit.is_conflicted()) {
+        bail!("Can not absorb into conflicted commits");
+    }
+    let pushed_commit_oids = branch.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| ctx.l(upstream_head, LogUntil::Commit(default_target.sha)),
+    )?;
+    let Some(head) = commits.last().cloned() else {
+        return Ok(0);
+    };
+
+    let repo = ctx.repository();
+    // fold a fixup commit with the hunks of `files` into the commit at `idx`, which fails with
+    // a validation error if these conflict with the commits after it
+    let fold_into = |idx: usize, files: &HashMap<PathBuf, Vec<VirtualBranchHunk>>| {
+        let fixup = absorb_commit(ctx, &head, &commits[idx], files)?;
+        let mut commits = commits.clone();
+        commits.push(fixup);
+        let mut folded = vec![vec![]; commits.len()];
+        folded[idx].push((commits.len() - 1, FoldKind::Fixup));
+        fold_commits(repo, &commits, &folded)
+    };
+    let is_conflict = |err: &anyhow::Error| err.downcast_ref::<Code>() == Some(&Code::Validation);
+
+    // the hunks to amend into each commit, by index into `commits`
+    let mut hunks_by_commit: Vec<HashMap<PathBuf, Vec<VirtualBranchHunk>>> =
+        vec![HashMap::new(); commits.len()];
+    let mut absorbed = 0;
+    for file in files {
+        for hunk in file.hunks {
+            if hunk.binary
+                || hunk.poisoned
+                || !hunk.depends_on.is_empty()
+                || hunk.change_type != ChangeType::Modified
+            {
+                continue;
+            }
+            let locks = hunk.locked_to.as_deref().unwrap_or_default();
+            // hunks that depend on commits of other branches can't go into this one
+            if locks.iter().any(|lock| lock.branch_id != branch_id) {
+                continue;
+            }
+            let Some(idx) = locks
+                .iter()
+                .map(|lock| lock.commit_id)
+                .unique()
+                .exactly_one()
+                .ok()
+                .and_then(|commit_id| commits.iter().position(|commit| commit.id() == commit_id))
+            else {
+                continue;
+            };
+            if pushed_commit_oids.contains(&commits[idx].id()) && !branch.allow_rebasing {
+                continue;
+            }
+            // hunks that conflict with the commits after the one they are locked to stay uncommitted
+            let files = HashMap::from([(file.path.clone(), vec![hunk.clone()])]);
+            match fold_into(idx, &files) {
+                Ok(_) => {}
+                Err(err) if is_conflict(&err) => continue,
+                Err(err) => return Err(err),
+            }
+            hunks_by_commit[idx]
+                .entry(file.path.clone())
+                .or_default()
+                .push(hunk);
+            absorbed += 1;
+        }
+    }
+    if absorbed == 0 {
+        return Ok(0);
+    }
+
+    // commit the hunks of each commit on top of the branch, and fold these into the commit they belong to
+    let mut folded = vec![vec![]; commits.len()];
+    for (idx, files) in hunks_by_commit.iter().enumerate() {
+        if files.is_empty() {
+            continue;
+        }
+        let fixup = absorb_commit(ctx, &head, &commits[idx], files)?;
+        folded[idx].push((commits.len(), FoldKind::Fixup));
+        folded.push(vec![]);
+        commits.push(fixup);
+    }
+    let new_head = fold_commits(repo, &commits, &folded)?;
+
+    branch.head = new_head.id();
+    branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+    vb_state.set_branch(branch.clone())?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(absorbed)
+}
+
+/// Commit the hunks of `files` on top of `head` as a fixup commit of `target`.
+fn absorb_commit<'repo>(
+    ctx: &'repo CommandContext,
+    head: &git2::Commit,
+    target: &git2::Commit,
+    files: &HashMap<PathBuf, Vec<VirtualBranchHunk>>,
+) -> Result<git2::Commit<'repo>> {
+    let repo = ctx.repository();
+    let tree_oid = gitbutler_diff::write::hunks_onto_commit(ctx, head.id(), files)?;
+    let tree = repo.find_tree(tree_oid).context("failed to find tree")?;
+    let commit_oid = repo
+        .commit_with_signature(
+            None,
+            &head.author(),
+            &head.committer(),
+            &format!("fixup! {}", commit_title(target).as_bstr()),
+            &tree,
+            &[head],
+            None,
+        )
+        .context("failed to commit")?;
+    repo.find_commit(commit_oid)
+        .context("failed to find commit")
+}
+
 // move a given commit in a branch up one or down one
 // if the offset is positive, move the commit down one
 // if the offset is negative, move the commit up one
@@ -1842,6 +2480,246 @@ pub(crate) fn undo_commit(
     Ok(())
 }
 
+/// Cherry-pick `commit_oid`, which may be on any branch, onto the head of the virtual branch
+/// identified by `branch_id` and apply its changes to the worktree.
+/// If it doesn't apply cleanly, the new commit is recorded as conflicted instead of failing.
+///
+/// Returns the id of the new commit.
+pub(crate) fn cherry_pick(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    commit_oid: git2::Oid,
+) -> Result<git2::Oid> {
+    ctx.assure_resolved()?;
+
+    let repo = ctx.repository();
+    let vb_state = ctx.project().virtual_branches();
+    let branch = vb_state.get_branch_in_workspace(branch_id)?;
+
+    let commit = repo
+        .find_commit(commit_oid)
+        .context("failed to find commit")?;
+    if commit.parent_count() != 1 {
+        return Err(anyhow!(
+            "can only cherry-pick commits with exactly one parent"
+        ))
+        .context(Code::Validation);
+    }
+
+    // the picked commit gets its own change-id, so it isn't mistaken for the original
+    let commit_headers = CommitHeadersV2 {
+        conflicted: commit
+            .gitbutler_headers()
+            .and_then(|headers| headers.conflicted),
+        ..Default::default()
+    };
+    let picked_commit_oid = repo
+        .commit_with_signature(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            &commit.message_bstr().to_str_lossy(),
+            &commit.tree().context("failed to find tree")?,
+            &[&commit.parent(0).context("failed to find parent")?],
+            Some(commit_headers),
+        )
+        .context("failed to copy commit")?;
+
+    let new_head_oid = cherry_rebase_group_in(repo, true, branch.head, &mut [picked_commit_oid])
+        .context("failed to cherry-pick")?;
+    if new_head_oid == branch.head {
+        return Err(anyhow!(
+            "commit {commit_oid} has no changes that aren't in the branch already"
+        ))
+        .context(Code::Validation);
+    }
+
+    set_head_and_worktree(ctx, branch, new_head_oid)?;
+    Ok(new_head_oid)
+}
+
+/// Make `new_head_oid`, which must be a descendant of the head of `branch`, its new head,
+/// and apply the changes between the two to the worktree.
+/// Fails without changing anything if they conflict with uncommitted changes.
+fn set_head_and_worktree(
+    ctx: &CommandContext,
+    mut branch: Branch,
+    new_head_oid: git2::Oid,
+) -> Result<()> {
+    let repo = ctx.repository();
+    let vb_state = ctx.project().virtual_branches();
+
+    let head_tree = repo.find_real_tree(&repo.find_commit(branch.head)?, Default::default())?;
+    let new_head_tree =
+        repo.find_real_tree(&repo.find_commit(new_head_oid)?, Default::default())?;
+    let wd_tree = repo.create_wd_tree()?;
+    let mut merge_index = repo.merge_trees(&head_tree, &new_head_tree, &wd_tree, None)?;
+    if merge_index.has_conflicts() {
+        return Err(anyhow!("the new commit conflicts with uncommitted changes"))
+            .context(Code::Validation);
+    }
+
+    branch.head = new_head_oid;
+    branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+    vb_state.set_branch(branch)?;
+
+    repo.checkout_index_builder(&mut merge_index)
+        .force()
+        .checkout()?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")
+}
+
+/// Where the changes that revert a commit end up, see [`revert()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevertDestination {
+    /// Uncommitted changes in the worktree, assigned to the branch.
+    Worktree,
+    /// A new commit on top of the branch.
+    Commit,
+}
+
+/// Revert the commit with `commit_oid`, which may be on any branch or the target, by applying the inverse
+/// of its changes to the virtual branch identified by `branch_id`, as given by `destination`.
+///
+/// As a commit, the revert is cherry-picked onto the head of the branch, and conflicts are recorded
+/// in a conflicted commit. As uncommitted changes, it's applied to the worktree and must neither conflict
+/// nor change lines next to the uncommitted changes of other branches, as there is no way to record the
+/// conflicts or to tell the changes apart.
+///
+/// Returns the id of the new commit, if one was created.
+pub(crate) fn revert(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    commit_oid: git2::Oid,
+    destination: RevertDestination,
+) -> Result<Option<git2::Oid>> {
+    ctx.assure_resolved()?;
+
+    let repo = ctx.repository();
+    let vb_state = ctx.project().virtual_branches();
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+
+    let commit = repo
+        .find_commit(commit_oid)
+        .context("failed to find commit")?;
+    if commit.parent_count() != 1 {
+        return Err(anyhow!("can only revert commits with exactly one parent"))
+            .context(Code::Validation);
+    }
+    if commit.is_conflicted() {
+        return Err(anyhow!("Can not revert a conflicted commit")).context(Code::Validation);
+    }
+    let commit_tree = repo.find_real_tree(&commit, Default::default())?;
+    let parent_tree = repo.find_real_tree(&commit.parent(0)?, Default::default())?;
+
+    match destination {
+        RevertDestination::Commit => {
+            let message = format!(
+                "Revert \"{}\"\n\nThis reverts commit {commit_oid}.\n",
+                commit_title(&commit).to_str_lossy()
+            );
+            // the inverse of the commit on top of it, to be cherry-picked like any other commit
+            let revert_commit_oid = ctx
+                .commit(&message, &parent_tree, &[&commit], None)
+                .context("failed to create revert commit")?;
+            let new_head_oid =
+                cherry_rebase_group_in(repo, true, branch.head, &mut [revert_commit_oid])
+                    .context("failed to cherry-pick revert commit")?;
+            if new_head_oid == branch.head {
+                return Err(anyhow!(
+                    "reverting commit {commit_oid} doesn't change the branch"
+                ))
+                .context(Code::Validation);
+            }
+            set_head_and_worktree(ctx, branch, new_head_oid)?;
+            Ok(Some(new_head_oid))
+        }
+        RevertDestination::Worktree => {
+            // Unlike a commit, whose tree can record conflicts with the `ConflictedTreeKey` layout,
+            // the worktree has no place for them but conflict markers in files, which would be
+            // indistinguishable from content and end up in the next commit. Conflicts are refused instead.
+            let wd_tree = repo.create_wd_tree()?;
+            let mut merge_index = repo.merge_trees(&commit_tree, &wd_tree, &parent_tree, None)?;
+            if merge_index.has_conflicts() {
+                return Err(anyhow!(
+                    "reverting commit {commit_oid} conflicts with the workspace, revert it as a commit instead"
+                ))
+                .context(Code::Validation);
+            }
+            let new_wd_tree = repo.find_tree(merge_index.write_tree_to(repo)?)?;
+            if new_wd_tree.id() == wd_tree.id() {
+                return Err(anyhow!(
+                    "reverting commit {commit_oid} doesn't change the workspace"
+                ))
+                .context(Code::Validation);
+            }
+
+            // Assign the hunks introduced by the revert to the branch, leaving the hunks that were
+            // already in the worktree with the branches that own them. A hunk of the revert that merges
+            // with a hunk of another branch can't be assigned without taking it along, so it's refused.
+            let reverted_paths = trees(repo, &wd_tree, &new_wd_tree, Default::default())?;
+            let workspace_tree = repo.find_commit(get_workspace_head(ctx)?)?.tree()?;
+            let existing_hunks: Vec<(PathBuf, GitHunk)> =
+                trees(repo, &workspace_tree, &wd_tree, Default::default())?
+                    .into_iter()
+                    .filter(|(path, _)| reverted_paths.contains_key(path))
+                    .flat_map(|(path, diff)| {
+                        diff.hunks.into_iter().map(move |hunk| (path.clone(), hunk))
+                    })
+                    .collect();
+            let is_owned_by_branch = |path: &PathBuf, hunk: &GitHunk| {
+                branch.ownership.claims.iter().any(|claim| {
+                    claim.file_path == *path
+                        && claim.hunks.iter().any(|claimed| claimed.intersects(hunk))
+                })
+            };
+            let mut diff: gitbutler_diff::DiffByPathMap =
+                trees(repo, &workspace_tree, &new_wd_tree, Default::default())?
+                    .into_iter()
+                    .filter(|(path, _)| reverted_paths.contains_key(path))
+                    .collect();
+            for (path, file_diff) in &mut diff {
+                file_diff.hunks.retain(|hunk| {
+                    let hash = Hunk::hash_diff(&hunk.diff_lines);
+                    !existing_hunks.iter().any(|(existing_path, existing)| {
+                        existing_path == path && Hunk::hash_diff(&existing.diff_lines) == hash
+                    })
+                });
+                // both diffs start at the workspace head, so their old lines can be compared
+                let merges_with_other_branch = file_diff.hunks.iter().any(|hunk| {
+                    existing_hunks.iter().any(|(existing_path, existing)| {
+                        existing_path == path
+                            && existing.old_start <= hunk.old_start + hunk.old_lines
+                            && hunk.old_start <= existing.old_start + existing.old_lines
+                            && !is_owned_by_branch(path, existing)
+                    })
+                });
+                if merges_with_other_branch {
+                    return Err(anyhow!(
+                        "reverting commit {commit_oid} changes {} next to the changes of another branch, revert it as a commit instead",
+                        path.display()
+                    ))
+                    .context(Code::Validation);
+                }
+            }
+
+            repo.checkout_index_builder(&mut merge_index)
+                .force()
+                .checkout()?;
+
+            claim_hunks(&mut branch, diff)?;
+            branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+            vb_state
+                .set_branch(branch)
+                .context("failed to write branch")?;
+            Ok(None)
+        }
+    }
+}
+
 /// squashes a commit from a virtual branch into its parent.
 pub(crate) fn squash(
     ctx: &CommandContext,
@@ -1891,6 +2769,14 @@ pub(crate) fn squash(
     //  * has the message combined of the target commit and parent commit
     //  * has parents of the parents commit.
     let parents: Vec<_> = parent_commit.parents().collect();
+    let message = gitbutler_repo::message_policy::apply(
+        ctx.repository(),
+        &format!(
+            "{}\n{}",
+            parent_commit.message_bstr(),
+            commit_to_squash.message_bstr(),
+        ),
+    )?;
 
     let new_commit_oid = ctx
         .repository()
@@ -1898,11 +2784,7 @@ pub(crate) fn squash(
             None,
             &commit_to_squash.author(),
             &commit_to_squash.committer(),
-            &format!(
-                "{}\n{}",
-                parent_commit.message_bstr(),
-                commit_to_squash.message_bstr(),
-            ),
+            &message,
             &commit_to_squash.tree().context("failed to find tree")?,
             &parents.iter().collect::<Vec<_>>(),
             // use the squash commit's headers
@@ -1934,6 +2816,480 @@ pub(crate) fn squash(
     }
 }
 
+/// How a commit is folded into the commit it targets when autosquashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FoldKind {
+    /// Keep only the message of the target commit, like `fixup!`.
+    Fixup,
+    /// Append the message body of the folded commit to the one of the target commit, like `squash!`.
+    Squash,
+}
+
+/// Fold the fixup commits of the branch with `branch_id` into the commits they target, like
+/// `git rebase --autosquash` does.
+///
+/// Commits created by [`commit_fixup()`] target the commit with their `fixup_of` change-id. Other commits
+/// titled `fixup! <target>` or `squash! <target>` target the oldest earlier commit whose title, change-id
+/// or commit id matches `<target>`, or whose title starts with it. Commits without a target stay as they are.
+pub(crate) fn autosquash(ctx: &CommandContext, branch_id: BranchId) -> Result<()> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let mut commits = ctx.log(branch.head, LogUntil::Commit(default_target.sha))?;
+    commits.reverse();
+
+    // the commits folded into each commit, in order
+    let mut folded: Vec<Vec<(usize, FoldKind)>> = vec![vec![]; commits.len()];
+    for (idx, commit) in commits.iter().enumerate() {
+        if let Some((mut target, kind)) = fixup_target(commit, &commits[..idx]) {
+            // fixups of fixups go into the commit that their target is folded into
+            if let Some(root) = folded
+                .iter()
+                .position(|fixups| fixups.iter().any(|(fixup_idx, _)| *fixup_idx == target))
+            {
+                target = root;
+            }
+            folded[target].push((idx, kind));
+        }
+    }
+    let Some(first_rewritten) = folded.iter().position(|fixups| !fixups.is_empty()) else {
+        return Ok(());
+    };
+
+    if commits[first_rewritten..]
+        .iter()
+        .any(|commit| commit.is_conflicted())
+    {
+        bail!("Can not autosquash conflicted commits");
+    }
+
+    let pushed_commit_oids = branch.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| ctx.l(upstream_head, LogUntil::Commit(default_target.sha)),
+    )?;
+    if commits[first_rewritten..]
+        .iter()
+        .any(|commit| pushed_commit_oids.contains(&commit.id()))
+        && !branch.allow_rebasing
+    {
+        // rewriting pushed commits will cause a force push that is not allowed
+        bail!("force push not allowed");
+    }
+
+    let head = fold_commits(ctx.repository(), &commits, &folded)?;
+
+    branch.head = head.id();
+    branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+    vb_state.set_branch(branch.clone())?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+    Ok(())
+}
+
+/// Rewrite `commits`, ordered from oldest to newest, so that the commits in `folded` at the index of a commit
+/// are folded into it, in order, and return the new head.
+fn fold_commits<'repo>(
+    repo: &'repo git2::Repository,
+    commits: &[git2::Commit<'repo>],
+    folded: &[Vec<(usize, FoldKind)>],
+) -> Result<git2::Commit<'repo>> {
+    let Some(first_rewritten) = folded.iter().position(|fixups| !fixups.is_empty()) else {
+        return commits.last().cloned().context("no commits to fold");
+    };
+    let is_folded = |idx: usize| {
+        folded
+            .iter()
+            .flatten()
+            .any(|(fixup_idx, _)| *fixup_idx == idx)
+    };
+
+    let mut head = commits[first_rewritten]
+        .parent(0)
+        .context("failed to find parent commit")?;
+    for (idx, commit) in commits.iter().enumerate().skip(first_rewritten) {
+        if is_folded(idx) {
+            continue;
+        }
+
+        head = if commit.parent_id(0)? == head.id() {
+            commit.clone()
+        } else {
+            let tree = cherry_pick_tree(repo, &head, commit)?;
+            let new_commit_oid = repo
+                .commit_with_signature(
+                    None,
+                    &commit.author(),
+                    &commit.committer(),
+                    &commit.message_bstr().to_str_lossy(),
+                    &tree,
+                    &[&head],
+                    commit.gitbutler_headers(),
+                )
+                .context("failed to commit")?;
+            repo.find_commit(new_commit_oid)?
+        };
+
+        for (fixup_idx, kind) in &folded[idx] {
+            let fixup = &commits[*fixup_idx];
+            let tree = cherry_pick_tree(repo, &head, fixup)?;
+            let message = match kind {
+                FoldKind::Fixup => head.message_bstr().to_str_lossy().into_owned(),
+                FoldKind::Squash => {
+                    let body = fixup
+                        .message_bytes()
+                        .lines()
+                        .skip(1)
+                        .collect::<Vec<_>>()
+                        .join(&b'\n')
+                        .trim()
+                        .to_str_lossy()
+                        .into_owned();
+                    if body.is_empty() {
+                        head.message_bstr().to_str_lossy().into_owned()
+                    } else {
+                        gitbutler_repo::message_policy::apply(
+                            repo,
+                            &format!(
+                                "{}\n\n{}",
+                                head.message_bstr().to_str_lossy().trim_end(),
+                                body
+                            ),
+                        )?
+                    }
+                }
+            };
+            let parents: Vec<_> = head.parents().collect();
+            let new_commit_oid = repo
+                .commit_with_signature(
+                    None,
+                    &head.author(),
+                    &head.committer(),
+                    &message,
+                    &tree,
+                    &parents.iter().collect::<Vec<_>>(),
+                    // keep the target commit's change-id
+                    head.gitbutler_headers(),
+                )
+                .context("failed to commit")?;
+            head = repo.find_commit(new_commit_oid)?;
+        }
+    }
+
+    Ok(head)
+}
+
+/// Return the index of the commit in `earlier` that `commit` should be folded into when autosquashing,
+/// and how, or `None` if it isn't a fixup commit or its target isn't one of `earlier`.
+fn fixup_target(commit: &git2::Commit, earlier: &[git2::Commit]) -> Option<(usize, FoldKind)> {
+    if let Some(change_id) = commit
+        .gitbutler_headers()
+        .and_then(|headers| headers.fixup_of)
+    {
+        if let Some(idx) = earlier
+            .iter()
+            .position(|commit| commit.change_id().as_deref() == Some(change_id.as_str()))
+        {
+            return Some((idx, FoldKind::Fixup));
+        }
+    }
+
+    let title = commit_title(commit);
+    let (kind, mut target) = if let Some(target) = title.strip_prefix(b"fixup! ") {
+        (FoldKind::Fixup, target)
+    } else if let Some(target) = title.strip_prefix(b"squash! ") {
+        (FoldKind::Squash, target)
+    } else {
+        return None;
+    };
+    // like git, `fixup! fixup! <target>` targets `<target>`
+    while let Some(rest) = target
+        .strip_prefix(b"fixup! ")
+        .or_else(|| target.strip_prefix(b"squash! "))
+    {
+        target = rest;
+    }
+    if target.is_empty() {
+        return None;
+    }
+
+    let is_commit_id_prefix = target.len() >= 4 && target.iter().all(u8::is_ascii_hexdigit);
+    earlier
+        .iter()
+        .position(|commit| commit_title(commit) == target)
+        .or_else(|| {
+            earlier.iter().position(|commit| {
+                commit.change_id().is_some_and(|id| id.as_bytes() == target)
+                    || (is_commit_id_prefix
+                        && commit.id().to_string().as_bytes().starts_with(target))
+            })
+        })
+        .or_else(|| {
+            earlier
+                .iter()
+                .position(|commit| commit_title(commit).starts_with(target))
+        })
+        .map(|idx| (idx, kind))
+}
+
+/// Return the first line of the message of `commit`.
+fn commit_title<'a>(commit: &'a git2::Commit) -> &'a [u8] {
+    commit.message_bytes().lines().next().unwrap_or_default()
+}
+
+/// Cherry-pick `commit` onto `head` and return the resulting tree, failing if it doesn't apply cleanly.
+fn cherry_pick_tree<'repo>(
+    repo: &'repo git2::Repository,
+    head: &git2::Commit,
+    commit: &git2::Commit,
+) -> Result<git2::Tree<'repo>> {
+    let mut index = repo
+        .cherry_pick_gitbutler(head, commit, None)
+        .context("failed to cherry pick")?;
+    if index.has_conflicts() {
+        return Err(anyhow!(
+            "commit {} conflicts with the commits it is reordered with",
+            commit.id()
+        ))
+        .context(Code::Validation);
+    }
+    let tree_oid = index.write_tree_to(repo).context("failed to write tree")?;
+    repo.find_tree(tree_oid).context("failed to find tree")
+}
+
+/// One of the commits that a commit is split into, see [`split_commit()`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitSplit {
+    /// The hunks of the split commit that go into this commit, by file and hunk range.
+    pub ownership: BranchOwnershipClaims,
+    /// The message of this commit, or the message of the split commit if `None`.
+    pub message: Option<String>,
+}
+
+/// Split the commit with `commit_id` of the branch with `branch_id` into one commit per group of `splits`, in order,
+/// with the first commit being the parent of the second and so on. Each hunk of the commit must be part
+/// of exactly one group.
+///
+/// The new commits keep the author and committer of the split commit but get new change-ids, and all commits
+/// above it are rebased onto the last of them. Returns the ids of the new commits.
+pub(crate) fn split_commit(
+    ctx: &CommandContext,
+    branch_id: BranchId,
+    commit_id: git2::Oid,
+    splits: &[CommitSplit],
+) -> Result<Vec<git2::Oid>> {
+    ctx.assure_resolved()?;
+
+    let vb_state = ctx.project().virtual_branches();
+    let mut branch = vb_state.get_branch_in_workspace(branch_id)?;
+    let default_target = vb_state.get_default_target()?;
+    let branch_commit_oids = ctx.l(branch.head, LogUntil::Commit(default_target.sha))?;
+
+    if !branch_commit_oids.contains(&commit_id) {
+        bail!("commit {commit_id} not in the branch")
+    }
+    if splits.len() < 2 {
+        return Err(anyhow!("a commit must be split into at least two commits"))
+            .context(Code::Validation);
+    }
+    if splits.iter().any(|split| split.ownership.claims.is_empty()) {
+        return Err(anyhow!("each commit of a split needs at least one hunk"))
+            .context(Code::Validation);
+    }
+
+    let repo = ctx.repository();
+    let commit = repo
+        .find_commit(commit_id)
+        .context("failed to find commit")?;
+    if commit.is_conflicted() {
+        bail!("Can not split a conflicted commit");
+    }
+    let parent_commit = commit.parent(0).context("failed to find parent commit")?;
+
+    let pushed_commit_oids = branch.upstream_head.map_or_else(
+        || Ok(vec![]),
+        |upstream_head| ctx.l(upstream_head, LogUntil::Commit(default_target.sha)),
+    )?;
+    if pushed_commit_oids.contains(&commit_id) && !branch.allow_rebasing {
+        // splitting a pushed commit will cause a force push that is not allowed
+        bail!("force push not allowed");
+    }
+
+    let parent_tree = parent_commit.tree().context("failed to find parent tree")?;
+    let commit_tree = commit.tree().context("failed to find tree")?;
+    let commit_diffs = trees(repo, &parent_tree, &commit_tree, Default::default())
+        .context("failed to diff trees")?;
+
+    // the index of the group each hunk of the commit goes into, by file
+    let mut group_by_hunk: HashMap<&Path, Vec<Option<usize>>> = commit_diffs
+        .iter()
+        .map(|(path, file)| (path.as_path(), vec![None; file.hunks.len()]))
+        .collect();
+    for (group, split) in splits.iter().enumerate() {
+        for claim in &split.ownership.claims {
+            let (file, groups) = commit_diffs
+                .get(&claim.file_path)
+                .zip(group_by_hunk.get_mut(claim.file_path.as_path()))
+                .with_context(|| {
+                    format!(
+                        "file {} isn't changed by the commit",
+                        claim.file_path.display()
+                    )
+                })
+                .context(Code::Validation)?;
+            for claimed_hunk in &claim.hunks {
+                let idx = file
+                    .hunks
+                    .iter()
+                    .position(|hunk| {
+                        claimed_hunk.start == hunk.new_start
+                            && claimed_hunk.end == hunk.new_start + hunk.new_lines
+                    })
+                    .with_context(|| {
+                        format!(
+                            "hunk {}:{claimed_hunk} isn't part of the commit",
+                            claim.file_path.display()
+                        )
+                    })
+                    .context(Code::Validation)?;
+                if groups[idx].replace(group).is_some() {
+                    return Err(anyhow!(
+                        "hunk {}:{claimed_hunk} is part of more than one group",
+                        claim.file_path.display()
+                    ))
+                    .context(Code::Validation);
+                }
+            }
+        }
+    }
+    if let Some((path, _)) = group_by_hunk
+        .iter()
+        .find(|(_, groups)| groups.iter().any(Option::is_none))
+    {
+        return Err(anyhow!(
+            "all hunks must be assigned to a group, but some of {} aren't",
+            path.display()
+        ))
+        .context(Code::Validation);
+    }
+
+    let mut new_commit_ids = Vec::with_capacity(splits.len());
+    let mut new_parent = parent_commit;
+    for (group, split) in splits.iter().enumerate() {
+        let tree = if group + 1 == splits.len() {
+            commit_tree.clone()
+        } else {
+            let tree_id = tree_with_hunks(
+                repo,
+                &parent_tree,
+                &commit_tree,
+                commit_diffs.iter().map(|(path, file)| {
+                    let hunks = file
+                        .hunks
+                        .iter()
+                        .zip(&group_by_hunk[path.as_path()])
+                        .filter(|(_, hunk_group)| hunk_group.is_some_and(|g| g <= group))
+                        .map(|(hunk, _)| hunk)
+                        .collect::<Vec<_>>();
+                    (path.as_path(), hunks, file.hunks.len())
+                }),
+            )?;
+            repo.find_tree(tree_id).context("failed to find tree")?
+        };
+        let message = match &split.message {
+            Some(message) => gitbutler_repo::message_policy::apply(repo, message)?,
+            None => commit.message_bstr().to_str_lossy().into_owned(),
+        };
+        let new_commit_id = repo
+            .commit_with_signature(
+                None,
+                &commit.author(),
+                &commit.committer(),
+                &message,
+                &tree,
+                &[&new_parent],
+                Some(Default::default()),
+            )
+            .context("failed to commit")?;
+        new_commit_ids.push(new_commit_id);
+        new_parent = repo.find_commit(new_commit_id)?;
+    }
+
+    let mut ids_to_rebase = branch_commit_oids
+        .split(|oid| oid.eq(&commit_id))
+        .next()
+        .unwrap_or_default()
+        .to_vec();
+    match cherry_rebase_group(ctx, new_parent.id(), &mut ids_to_rebase) {
+        Ok(new_head_id) => {
+            branch.head = new_head_id;
+            branch.updated_timestamp_ms = gitbutler_time::time::now_ms();
+            vb_state.set_branch(branch.clone())?;
+
+            crate::integration::update_workspace_commit(&vb_state, ctx)
+                .context("failed to update gitbutler workspace")?;
+            Ok(new_commit_ids)
+        }
+        Err(err) => Err(err.context("rebase error").context(Code::Unknown)),
+    }
+}
+
+/// Return the mode of the tree `entry` as needed to write it with a tree builder.
+fn file_mode(entry: &git2::TreeEntry) -> git2::FileMode {
+    match entry.filemode() {
+        0o100755 => git2::FileMode::BlobExecutable,
+        0o120000 => git2::FileMode::Link,
+        0o160000 => git2::FileMode::Commit,
+        _ => git2::FileMode::Blob,
+    }
+}
+
+/// Write a tree that is `parent_tree` with `files`, as `(path, hunks, total hunk count)` of the changes from
+/// `parent_tree` to `commit_tree`, applied to it.
+/// Files with all of their hunks are taken from `commit_tree` as they are.
+fn tree_with_hunks<'a>(
+    repo: &git2::Repository,
+    parent_tree: &git2::Tree,
+    commit_tree: &git2::Tree,
+    files: impl IntoIterator<Item = (&'a Path, Vec<&'a GitHunk>, usize)>,
+) -> Result<git2::Oid> {
+    let mut builder = git2::build::TreeUpdateBuilder::new();
+    for (path, mut hunks, hunk_count) in files {
+        if hunks.is_empty() {
+            continue;
+        }
+        let Ok(entry) = commit_tree.get_path(path) else {
+            builder.remove(path);
+            continue;
+        };
+        let mode = file_mode(&entry);
+        if hunks.len() == hunk_count {
+            builder.upsert(path, entry.id(), mode);
+            continue;
+        }
+
+        let base = match parent_tree.get_path(path) {
+            Ok(parent_entry) => repo.find_blob(parent_entry.id())?.content().to_owned(),
+            Err(_) => Vec::new(),
+        };
+        hunks.sort_by_key(|hunk| hunk.new_start);
+        let mut all_diffs = BString::default();
+        for hunk in hunks {
+            all_diffs.push_str(&hunk.diff_lines);
+        }
+        let patch = diffy::Patch::from_bytes(&all_diffs)?;
+        let content = gitbutler_diff::write::apply(&base, &patch)
+            .with_context(|| format!("failed to apply hunks of {}", path.display()))?;
+        builder.upsert(path, repo.blob(&content)?, mode);
+    }
+    builder
+        .create_updated(repo, parent_tree)
+        .context("failed to write updated tree")
+}
+
 // changes a commit message for commit_oid, rebases everything above it, updates branch head if successful
 pub(crate) fn update_commit_message(
     ctx: &CommandContext,
@@ -1944,6 +3300,7 @@ pub(crate) fn update_commit_message(
     if message.is_empty() {
         bail!("commit message can not be empty");
     }
+    let message = &gitbutler_repo::message_policy::apply(ctx.repository(), message)?;
     ctx.assure_unconflicted()?;
 
     let vb_state = ctx.project().virtual_branches();
@@ -2055,6 +3412,7 @@ pub(crate) fn move_commit(
         ctx.repository(),
         &source_branch_head_parent_tree,
         &source_branch_head_tree,
+        Default::default(),
     )?;
 
     let branch_head_diff: HashMap<_, _> =
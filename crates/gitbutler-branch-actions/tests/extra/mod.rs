@@ -13,13 +13,15 @@ use std::{
 use anyhow::{Context, Result};
 use git2::TreeEntry;
 use gitbutler_branch::{
-    BranchCreateRequest, BranchOwnershipClaims, BranchUpdateRequest, Target, VirtualBranchesHandle,
+    BranchCreateRequest, BranchId, BranchOwnershipClaims, BranchUpdateRequest, Target,
+    VirtualBranchesHandle,
 };
 use gitbutler_branch_actions::{
     commit, get_applied_status, integrate_upstream_commits, is_remote_branch_mergeable,
-    list_virtual_branches, unapply_ownership, update_branch, update_workspace_commit,
-    verify_branch, BranchManagerExt, Get,
+    list_virtual_branches, preview_integrate_upstream_commits, unapply_ownership, update_branch,
+    update_workspace_commit, verify_branch, BranchManagerExt, Get, IntegrationStrategy,
 };
+use gitbutler_command_context::CommandContext;
 use gitbutler_commit::{commit_ext::CommitExt, commit_headers::CommitHeadersV2};
 use gitbutler_reference::{Refname, RemoteRefname};
 use gitbutler_repo::RepositoryExt;
@@ -532,6 +534,154 @@ fn move_hunks_multiple_sources() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn adjacent_hunks_in_different_branches_depend_on_each_other() -> Result<()> {
+    let suite = Suite::default();
+    let Case { ctx, project, .. } = &suite.new_case_with_files(HashMap::from([(
+        PathBuf::from("test.txt"),
+        "line1\nline2\nline3\nline4\nline5\nline6\nline7\nline8\nline9\nline10\nline11\nline12\nline13\nline14\nline15\n",
+    )]));
+
+    set_test_target(ctx)?;
+
+    let branch_manager = ctx.branch_manager();
+    let mut guard = project.exclusive_worktree_access();
+    let branch1_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+    let branch2_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+
+    // three unchanged lines separate the context lines of both hunks
+    std::fs::write(
+        Path::new(&project.path).join("test.txt"),
+        "line1\nline2 changed\nline3\nline4\nline5\nline6\nline7\nline8\nline9\nline10\nline11\nline12 changed\nline13\nline14\nline15\n",
+    )?;
+
+    let statuses = get_applied_status(ctx, None)?.branches;
+    let files_by_branch_id = statuses
+        .iter()
+        .map(|(branch, files)| (branch.id, files))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(files_by_branch_id[&branch1_id][0].hunks.len(), 2);
+    assert!(
+        files_by_branch_id[&branch1_id][0]
+            .hunks
+            .iter()
+            .all(|hunk| hunk.depends_on.is_empty() && !hunk.poisoned),
+        "adjacent hunks in the same branch don't depend on each other"
+    );
+
+    let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let mut branch2 = vb_state.get_branch_in_workspace(branch2_id)?;
+    branch2.ownership = BranchOwnershipClaims {
+        claims: vec!["test.txt:1-6".parse()?],
+    };
+    vb_state.set_branch(branch2)?;
+    let mut branch1 = vb_state.get_branch_in_workspace(branch1_id)?;
+    branch1.ownership = BranchOwnershipClaims {
+        claims: vec!["test.txt:9-16".parse()?],
+    };
+    vb_state.set_branch(branch1)?;
+
+    let statuses = get_applied_status(ctx, None)?.branches;
+    let files_by_branch_id = statuses
+        .iter()
+        .map(|(branch, files)| (branch.id, files))
+        .collect::<HashMap<_, _>>();
+    let hunk1 = &files_by_branch_id[&branch1_id][0].hunks[0];
+    let hunk2 = &files_by_branch_id[&branch2_id][0].hunks[0];
+    assert!(hunk1.poisoned && hunk2.poisoned);
+    assert_eq!(hunk1.depends_on.len(), 1);
+    assert_eq!(hunk1.depends_on[0].branch_id, branch2_id);
+    assert_eq!(hunk1.depends_on[0].hunk_id, hunk2.id);
+    assert_eq!(hunk2.depends_on.len(), 1);
+    assert_eq!(hunk2.depends_on[0].branch_id, branch1_id);
+    assert_eq!(hunk2.depends_on[0].hunk_id, hunk1.id);
+
+    let err = commit(ctx, branch1_id, "test commit", None, false).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "hunk test.txt:9-16 can't be committed without the adjacent hunks in other branches"
+    );
+
+    update_branch(
+        ctx,
+        &BranchUpdateRequest {
+            id: branch1_id,
+            ownership: Some("test.txt:1-6,9-16".parse()?),
+            ..Default::default()
+        },
+    )?;
+    commit(ctx, branch1_id, "test commit", None, false)?;
+
+    Ok(())
+}
+
+#[test]
+fn hunks_separated_by_unchanged_lines_in_different_branches_are_independent() -> Result<()> {
+    let suite = Suite::default();
+    let Case { ctx, project, .. } = &suite.new_case_with_files(HashMap::from([(
+        PathBuf::from("test.txt"),
+        "line1\nline2\nline3\nline4\nline5\nline6\nline7\nline8\nline9\nline10\nline11\nline12\nline13\nline14\nline15\nline16\n",
+    )]));
+
+    set_test_target(ctx)?;
+
+    let branch_manager = ctx.branch_manager();
+    let mut guard = project.exclusive_worktree_access();
+    let branch1_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+    let branch2_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+
+    // four unchanged lines separate the context lines of both hunks
+    std::fs::write(
+        Path::new(&project.path).join("test.txt"),
+        "line1\nline2 changed\nline3\nline4\nline5\nline6\nline7\nline8\nline9\nline10\nline11\nline12\nline13 changed\nline14\nline15\nline16\n",
+    )?;
+
+    let statuses = get_applied_status(ctx, None)?.branches;
+    let files_by_branch_id = statuses
+        .iter()
+        .map(|(branch, files)| (branch.id, files))
+        .collect::<HashMap<_, _>>();
+    assert_eq!(files_by_branch_id[&branch1_id][0].hunks.len(), 2);
+
+    update_branch(
+        ctx,
+        &BranchUpdateRequest {
+            id: branch2_id,
+            ownership: Some("test.txt:1-6".parse()?),
+            ..Default::default()
+        },
+    )?;
+
+    let statuses = get_applied_status(ctx, None)?.branches;
+    let files_by_branch_id = statuses
+        .iter()
+        .map(|(branch, files)| (branch.id, files))
+        .collect::<HashMap<_, _>>();
+    let hunk1 = &files_by_branch_id[&branch1_id][0].hunks[0];
+    let hunk2 = &files_by_branch_id[&branch2_id][0].hunks[0];
+    assert_eq!(hunk1.id, "10-17");
+    assert_eq!(hunk2.id, "1-6");
+    assert!(hunk1.depends_on.is_empty() && !hunk1.poisoned);
+    assert!(hunk2.depends_on.is_empty() && !hunk2.poisoned);
+
+    commit(ctx, branch1_id, "commit line 13", None, false)?;
+    commit(ctx, branch2_id, "commit line 2", None, false)?;
+
+    Ok(())
+}
+
 #[test]
 fn move_hunks_partial_explicitly() -> Result<()> {
     let suite = Suite::default();
@@ -743,6 +893,7 @@ fn commit_id_can_be_generated_or_specified() -> Result<()> {
             Some(CommitHeadersV2 {
                 change_id: "my-change-id".to_string(),
                 conflicted: None,
+                fixup_of: None,
             }),
         )
         .expect("failed to commit");
@@ -994,6 +1145,226 @@ fn merge_vbranch_upstream_conflict() -> Result<()> {
     Ok(())
 }
 
+/// The commits of `test.txt` shared by the upstream integration previews.
+struct UpstreamHistory {
+    last_push: git2::Oid,
+    coworker_work: git2::Oid,
+}
+
+/// Commit `test.txt` three times, with the target at the first commit and `origin/master` at the
+/// last one, a coworker's, and leave the worktree at the second commit, the one we pushed last.
+fn upstream_history(ctx: &CommandContext) -> Result<UpstreamHistory> {
+    let file_path = Path::new(&ctx.project().path).join("test.txt");
+    std::fs::write(&file_path, "line1\nline2\nline3\nline4\n")?;
+    commit_all(ctx.repository());
+    let target_oid = ctx.repository().head().unwrap().target().unwrap();
+
+    std::fs::write(&file_path, "line1\nline2\nline3\nline4\nupstream\n")?;
+    commit_all(ctx.repository());
+    let last_push = ctx.repository().head().unwrap().target().unwrap();
+
+    std::fs::write(
+        &file_path,
+        "line1\nline2\nline3\nline4\nupstream\ncoworker work\n",
+    )?;
+    commit_all(ctx.repository());
+    let coworker_work = ctx.repository().head().unwrap().target().unwrap();
+    ctx.repository().reference(
+        "refs/remotes/origin/master",
+        coworker_work,
+        true,
+        "update target",
+    )?;
+
+    std::fs::write(&file_path, "line1\nline2\nline3\nline4\nupstream\n")?;
+
+    set_test_target(ctx)?;
+    VirtualBranchesHandle::new(ctx.project().gb_dir()).set_default_target(Target {
+        branch: "refs/remotes/origin/master".parse().unwrap(),
+        remote_url: "origin".to_string(),
+        sha: target_oid,
+        push_remote_name: None,
+    })?;
+
+    Ok(UpstreamHistory {
+        last_push,
+        coworker_work,
+    })
+}
+
+/// Create a branch at `head` which tracks `origin/master`.
+fn create_branch_tracking_upstream(
+    ctx: &CommandContext,
+    head: git2::Oid,
+    allow_rebasing: bool,
+) -> Result<BranchId> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    let mut branch = ctx
+        .branch_manager()
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch");
+    branch.upstream = Some("refs/remotes/origin/master".parse().unwrap());
+    branch.head = head;
+    VirtualBranchesHandle::new(ctx.project().gb_dir()).set_branch(branch.clone())?;
+
+    update_branch(
+        ctx,
+        &BranchUpdateRequest {
+            id: branch.id,
+            allow_rebasing: Some(allow_rebasing),
+            ..Default::default()
+        },
+    )?;
+    Ok(branch.id)
+}
+
+#[test]
+fn preview_upstream_integration_conflict() -> Result<()> {
+    let suite = Suite::default();
+    let Case { ctx, project, .. } = &suite.new_case();
+    let UpstreamHistory {
+        last_push,
+        coworker_work,
+    } = upstream_history(ctx)?;
+
+    let file_path = Path::new("test.txt");
+    std::fs::write(
+        Path::new(&project.path).join(file_path),
+        "line1\nline2\nline3\nline4\nupstream\nother side\n",
+    )?;
+    let branch_id = create_branch_tracking_upstream(ctx, last_push, false)?;
+
+    let preview = preview_integrate_upstream_commits(ctx, branch_id)?;
+    assert_eq!(preview.selected, Some(IntegrationStrategy::Merge));
+    let [rebase, merge] = &preview.strategies[..] else {
+        panic!("expected a preview for each strategy")
+    };
+    assert_eq!(rebase.strategy, IntegrationStrategy::Rebase);
+    assert_eq!(
+        rebase
+            .commits
+            .iter()
+            .map(|commit| commit.id)
+            .collect::<Vec<_>>(),
+        [coworker_work, last_push],
+        "the upstream commit is on top of the branch already"
+    );
+    assert!(!rebase.requires_force_push);
+    assert_eq!(
+        rebase.conflicted_files,
+        [file_path],
+        "the upstream commit conflicts with uncommitted changes"
+    );
+    assert_eq!(merge.strategy, IntegrationStrategy::Merge);
+    assert!(merge.commits.is_empty());
+    assert!(!merge.requires_force_push);
+    assert_eq!(merge.conflicted_files, [file_path]);
+    assert_eq!(
+        std::fs::read_to_string(Path::new(&project.path).join(file_path))?,
+        "line1\nline2\nline3\nline4\nupstream\nother side\n",
+        "previews don't touch the worktree"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn preview_upstream_integration_clean_merge() -> Result<()> {
+    let suite = Suite::default();
+    let Case { ctx, project, .. } = &suite.new_case();
+    let UpstreamHistory {
+        last_push,
+        coworker_work,
+    } = upstream_history(ctx)?;
+
+    std::fs::write(Path::new(&project.path).join("test2.txt"), "file2\n")?;
+    let branch_id = create_branch_tracking_upstream(ctx, last_push, false)?;
+
+    let preview = preview_integrate_upstream_commits(ctx, branch_id)?;
+    assert_eq!(preview.selected, Some(IntegrationStrategy::Merge));
+    let [_rebase, merge] = &preview.strategies[..] else {
+        panic!("expected a preview for each strategy")
+    };
+    assert_eq!(merge.strategy, IntegrationStrategy::Merge);
+    let [merge_commit, rest @ ..] = &merge.commits[..] else {
+        panic!("expected a merge commit")
+    };
+    assert!(merge_commit
+        .description
+        .starts_with("Merged origin/master into "));
+    assert!(!merge_commit.conflicted);
+    assert_eq!(
+        rest.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+        [coworker_work, last_push]
+    );
+    assert!(
+        merge.conflicted_files.is_empty(),
+        "uncommitted changes in other files don't conflict"
+    );
+    assert!(!merge.requires_force_push);
+    assert!(
+        ctx.repository().find_commit(merge_commit.id).is_err(),
+        "previews don't write the merge commit"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn preview_upstream_integration_rebase_requires_force_push() -> Result<()> {
+    let suite = Suite::default();
+    let Case { ctx, project, .. } = &suite.new_case();
+    let UpstreamHistory {
+        last_push,
+        coworker_work,
+    } = upstream_history(ctx)?;
+
+    // Commit local work on top of the last push, next to the coworker's work.
+    let repo = ctx.repository();
+    let local_path = Path::new("local.txt");
+    std::fs::write(Path::new(&project.path).join(local_path), "local work\n")?;
+    let last_push_commit = repo.find_commit(last_push)?;
+    let mut tree = repo.treebuilder(Some(&last_push_commit.tree()?))?;
+    tree.insert(local_path, repo.blob(b"local work\n")?, 0o100644)?;
+    let signature = git2::Signature::now("test", "test@email.com")?;
+    let local_work = repo.commit(
+        None,
+        &signature,
+        &signature,
+        "local work",
+        &repo.find_tree(tree.write()?)?,
+        &[&last_push_commit],
+    )?;
+    let branch_id = create_branch_tracking_upstream(ctx, local_work, true)?;
+
+    let preview = preview_integrate_upstream_commits(ctx, branch_id)?;
+    assert_eq!(preview.selected, Some(IntegrationStrategy::Rebase));
+    let [rebase, merge] = &preview.strategies[..] else {
+        panic!("expected a preview for each strategy")
+    };
+    assert_eq!(rebase.strategy, IntegrationStrategy::Rebase);
+    let [rebased_coworker_work, rest @ ..] = &rebase.commits[..] else {
+        panic!("expected the upstream commit to be rebased")
+    };
+    assert_ne!(rebased_coworker_work.id, coworker_work);
+    assert_eq!(
+        rest.iter().map(|commit| commit.id).collect::<Vec<_>>(),
+        [local_work, last_push],
+        "the upstream commit is rebased on top of the local work"
+    );
+    assert!(rebase.conflicted_files.is_empty());
+    assert!(
+        rebase.requires_force_push,
+        "the rebased branch doesn't contain the upstream commit anymore"
+    );
+    assert!(
+        !merge.requires_force_push,
+        "the merge commit keeps the upstream commit"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn unapply_ownership_partial() -> Result<()> {
     let suite = Suite::default();
@@ -2111,6 +2482,103 @@ fn commit_msg_hook_rejection() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn commit_hooks_see_the_commit_in_a_worktree() -> Result<()> {
+    let suite = Suite::default();
+    let Case { project, ctx, .. } = &suite.new_case_with_files(HashMap::from([
+        (PathBuf::from("test.txt"), "line1\nline2\nline3\nline4\n"),
+        (PathBuf::from("test2.txt"), "line5\nline6\nline7\nline8\n"),
+    ]));
+
+    set_test_target(ctx)?;
+
+    let branch_manager = ctx.branch_manager();
+    let mut guard = project.exclusive_worktree_access();
+    let branch1_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+    let branch2_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+
+    std::fs::write(
+        Path::new(&project.path).join("test.txt"),
+        "line0\nline1\nline2\nline3\nline4\n",
+    )?;
+    std::fs::write(
+        Path::new(&project.path).join("test2.txt"),
+        "line5\nline6\nline7\nline8\nline9\n",
+    )?;
+    update_branch(
+        ctx,
+        &BranchUpdateRequest {
+            id: branch2_id,
+            ownership: Some("test2.txt:1-5".parse()?),
+            ..Default::default()
+        },
+    )?;
+
+    let hook = b"#!/bin/sh
+    test \"$(git diff --cached --name-only)\" = test.txt || exit 1
+    test \"$(head -n 1 test2.txt)\" = line5 || exit 1
+    test \"$(tail -n 1 test2.txt)\" = line8 || exit 1
+            ";
+    git2_hooks::create_hook(ctx.repository(), git2_hooks::HOOK_PRE_COMMIT, hook);
+
+    let hook = b"#!/bin/sh
+    printf '\\nhooked' >> \"$1\"
+            ";
+    git2_hooks::create_hook(ctx.repository(), git2_hooks::HOOK_COMMIT_MSG, hook);
+
+    let commit_id = commit(ctx, branch1_id, "test commit", None, true)?;
+
+    assert_eq!(
+        ctx.repository().find_commit(commit_id)?.message(),
+        Some("test commit\nhooked"),
+        "the hook only sees the changes of the committed branch, and can edit the message"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn hooks_are_not_run_if_disabled_for_project() -> Result<()> {
+    let suite = Suite::default();
+    let Case { project, ctx, .. } = &suite.new_case_with_files(HashMap::from([(
+        PathBuf::from("test.txt"),
+        "line1\nline2\nline3\nline4\n",
+    )]));
+    let mut project = project.clone();
+    *project.run_git_hooks = false;
+    let ctx = &CommandContext::open(&project)?;
+
+    set_test_target(ctx)?;
+
+    let branch_manager = ctx.branch_manager();
+    let mut guard = project.exclusive_worktree_access();
+    let branch1_id = branch_manager
+        .create_virtual_branch(&BranchCreateRequest::default(), guard.write_permission())
+        .expect("failed to create virtual branch")
+        .id;
+
+    std::fs::write(
+        Path::new(&project.path).join("test.txt"),
+        "line0\nline1\nline2\nline3\nline4\n",
+    )?;
+
+    let hook = b"#!/bin/sh
+    echo 'rejected'
+    exit 1
+            ";
+    git2_hooks::create_hook(ctx.repository(), git2_hooks::HOOK_PRE_COMMIT, hook);
+
+    commit(ctx, branch1_id, "test commit", None, true)?;
+
+    Ok(())
+}
+
 fn walk<C>(tree: &git2::Tree, mut callback: C) -> Result<()>
 where
     C: FnMut(&str, &TreeEntry) -> TreeWalkResult,
@@ -0,0 +1,206 @@
+use bstr::ByteSlice;
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_commit::commit_ext::CommitExt;
+
+use super::*;
+
+fn blob_id(content: &[String]) -> git2::Oid {
+    git2::Oid::hash_object(git2::ObjectType::Blob, content.join("\n").as_bytes()).unwrap()
+}
+
+#[test]
+fn hunks_are_amended_into_the_commits_that_changed_their_lines() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let mut lines = repository.gen_file("file.txt", 20);
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    lines[15] = "line fifteen".into();
+    repository.write_file("file.txt", &lines);
+    fs::write(repository.path().join("other.txt"), "content").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+    let commit2 = repository.find_commit(commit2_id).unwrap();
+
+    let mut lines_of_commit1: Vec<_> = (0..20).map(|i| format!("line {i}")).collect();
+    lines_of_commit1[2] = "line two".into();
+    lines[2] = "line two".into();
+    lines[15] = "line 15 again".into();
+    repository.write_file("file.txt", &lines);
+    // a new file can't be attributed to any commit
+    fs::write(repository.path().join("new.txt"), "new").unwrap();
+
+    assert_eq!(controller.absorb(project, branch_id).unwrap(), 2);
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].path.display().to_string(), "new.txt");
+
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.commits[1].change_id, commit1.change_id());
+    assert_eq!(branch.commits[1].description, "commit one");
+    assert_eq!(
+        repository
+            .find_commit(branch.commits[1].id)
+            .unwrap()
+            .tree()
+            .unwrap()
+            .get_name("file.txt")
+            .unwrap()
+            .id(),
+        blob_id(&lines_of_commit1)
+    );
+
+    assert_eq!(branch.commits[0].change_id, commit2.change_id());
+    assert_eq!(branch.commits[0].description, "commit two");
+    assert_eq!(branch.commits[0].files.len(), 2);
+    assert_eq!(
+        repository
+            .find_commit(branch.commits[0].id)
+            .unwrap()
+            .tree()
+            .unwrap()
+            .get_name("file.txt")
+            .unwrap()
+            .id(),
+        blob_id(&lines)
+    );
+}
+
+#[test]
+fn hunks_changing_lines_of_multiple_commits_stay_uncommitted() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let mut lines = repository.gen_file("file.txt", 20);
+    controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+
+    lines[10] = "line ten".into();
+    repository.write_file("file.txt", &lines);
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+
+    lines[9] = "line nine".into();
+    lines[10] = "line 10 again".into();
+    repository.write_file("file.txt", &lines);
+
+    assert_eq!(controller.absorb(project, branch_id).unwrap(), 0);
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.commits[0].id, commit2_id);
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].hunks.len(), 1);
+}
+
+#[test]
+fn hunks_conflicting_with_later_commits_stay_uncommitted() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let mut lines = repository.gen_file("file.txt", 20);
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    lines[9] = "line ten".into();
+    repository.write_file("file.txt", &lines);
+    controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+
+    let mut lines_of_commit1: Vec<_> = (0..20).map(|i| format!("line {i}")).collect();
+    lines_of_commit1[17] = "line seventeen".into();
+    lines[17] = "line seventeen".into();
+    // the line next to the one changed by commit two was last changed by commit one,
+    // but can't be amended into it without conflicting with commit two
+    lines[8] = "line eight".into();
+    repository.write_file("file.txt", &lines);
+
+    assert_eq!(controller.absorb(project, branch_id).unwrap(), 1);
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].hunks.len(), 1);
+    assert!(branch.files[0].hunks[0].diff.contains_str("+line eight"));
+
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.commits[1].change_id, commit1.change_id());
+    assert_eq!(
+        repository
+            .find_commit(branch.commits[1].id)
+            .unwrap()
+            .tree()
+            .unwrap()
+            .get_name("file.txt")
+            .unwrap()
+            .id(),
+        blob_id(&lines_of_commit1)
+    );
+}
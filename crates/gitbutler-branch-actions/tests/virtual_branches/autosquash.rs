@@ -0,0 +1,240 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_config::git::{GbConfig, GitConfig};
+
+use super::*;
+
+#[test]
+fn fixup_commit_is_folded_into_its_target() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+    let commit2 = repository.find_commit(commit2_id).unwrap();
+
+    fs::write(repository.path().join("file.txt"), "fixed content").unwrap();
+    let fixup_id = controller
+        .create_fixup_commit(
+            project,
+            branch_id,
+            &commit1.change_id().unwrap(),
+            None,
+            false,
+        )
+        .unwrap();
+    let fixup = repository.find_commit(fixup_id).unwrap();
+    assert_eq!(fixup.message(), Some("fixup! commit one"));
+    assert_ne!(fixup.change_id(), commit1.change_id());
+
+    // rewording the target doesn't matter as the fixup finds it by change-id
+    controller
+        .update_commit_message(project, branch_id, commit1_id, "first commit")
+        .unwrap();
+
+    controller.autosquash(project, branch_id).unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 2);
+    assert!(branch.files.is_empty());
+
+    assert_eq!(branch.commits[1].change_id, commit1.change_id());
+    assert_eq!(branch.commits[1].description, "first commit");
+    assert_eq!(branch.commits[1].files.len(), 1);
+    let tree = repository
+        .find_commit(branch.commits[1].id)
+        .unwrap()
+        .tree()
+        .unwrap();
+    assert_eq!(
+        tree.get_name("file.txt").unwrap().id(),
+        git2::Oid::hash_object(git2::ObjectType::Blob, b"fixed content").unwrap()
+    );
+
+    assert_eq!(branch.commits[0].change_id, commit2.change_id());
+    assert_eq!(branch.commits[0].description, "commit two");
+    assert_eq!(branch.commits[0].files.len(), 1);
+}
+
+#[test]
+fn fixup_and_squash_messages_are_honored() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+
+    fs::write(repository.path().join("file3.txt"), "content3").unwrap();
+    controller
+        .create_commit(
+            project,
+            branch_id,
+            "squash! commit one\n\nmore details",
+            None,
+            false,
+        )
+        .unwrap();
+
+    fs::write(repository.path().join("file4.txt"), "content4").unwrap();
+    controller
+        .create_commit(project, branch_id, "fixup! fixup! commit", None, false)
+        .unwrap();
+
+    fs::write(repository.path().join("file5.txt"), "content5").unwrap();
+    controller
+        .create_commit(project, branch_id, "fixup! unknown commit", None, false)
+        .unwrap();
+
+    controller.autosquash(project, branch_id).unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 3);
+    assert_eq!(branch.commits[0].description, "fixup! unknown commit");
+    assert_eq!(branch.commits[0].files.len(), 1);
+    assert_eq!(branch.commits[1].description, "commit two");
+    assert_eq!(branch.commits[1].files.len(), 1);
+    assert_eq!(branch.commits[2].description, "commit one\n\nmore details");
+    assert_eq!(branch.commits[2].change_id, commit1.change_id());
+    assert_eq!(branch.commits[2].files.len(), 3);
+}
+
+#[test]
+fn fixup_commit_is_created_even_if_a_trailer_is_required() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .set_gb_config(GbConfig {
+            commit_message_required_trailer: Some("Ticket".into()),
+            ..GbConfig::default()
+        })
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one\n\nTicket: 1", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    fs::write(repository.path().join("file.txt"), "fixed content").unwrap();
+    let fixup_id = controller
+        .create_fixup_commit(
+            project,
+            branch_id,
+            &commit1.change_id().unwrap(),
+            None,
+            false,
+        )
+        .unwrap();
+    assert_eq!(
+        repository.find_commit(fixup_id).unwrap().message(),
+        Some("fixup! commit one")
+    );
+
+    controller.autosquash(project, branch_id).unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(branch.commits[0].description, "commit one\n\nTicket: 1");
+    assert_eq!(branch.commits[0].change_id, commit1.change_id());
+}
+
+#[test]
+fn fixup_target_must_be_in_the_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let err = controller
+        .create_fixup_commit(project, branch_id, "unknown-change-id", None, false)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "no commit with change-id unknown-change-id in the branch"
+    );
+}
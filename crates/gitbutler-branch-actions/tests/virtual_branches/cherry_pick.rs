@@ -0,0 +1,153 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_reference::LocalRefname;
+
+use super::*;
+
+/// Create a commit with `file.txt` set to `content` on a remote branch and return its id.
+fn remote_commit(repository: &TestProject, content: &str) -> git2::Oid {
+    let branch_name: LocalRefname = "refs/heads/branch".parse().unwrap();
+    repository.checkout(&branch_name);
+    fs::write(repository.path().join("file.txt"), content).unwrap();
+    let commit_id = repository.commit_all("remote commit");
+    repository.push_branch(&branch_name);
+    repository.checkout(&"refs/heads/master".parse().unwrap());
+    commit_id
+}
+
+#[test]
+fn commit_from_remote_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let remote_commit_id = remote_commit(repository, "first");
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("other.txt"), "other").unwrap();
+    controller
+        .create_commit(project, branch_id, "local commit", None, false)
+        .unwrap();
+
+    let new_commit_id = controller
+        .cherry_pick(project, branch_id, remote_commit_id)
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 2);
+    assert!(branch.files.is_empty());
+    assert_eq!(branch.commits[0].id, new_commit_id);
+    assert_eq!(branch.commits[0].description, "remote commit");
+    assert!(!branch.commits[0].conflicted);
+    assert_eq!(branch.commits[1].description, "local commit");
+
+    let remote_commit = repository.find_commit(remote_commit_id).unwrap();
+    let new_commit = repository.find_commit(new_commit_id).unwrap();
+    assert!(new_commit.change_id().is_some());
+    assert_eq!(new_commit.author().email(), remote_commit.author().email());
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "first"
+    );
+}
+
+#[test]
+fn conflicts_are_recorded_in_the_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let remote_commit_id = remote_commit(repository, "first");
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "conflict").unwrap();
+    controller
+        .create_commit(project, branch_id, "local commit", None, false)
+        .unwrap();
+
+    let new_commit_id = controller
+        .cherry_pick(project, branch_id, remote_commit_id)
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.commits[0].id, new_commit_id);
+    assert!(branch.commits[0].conflicted);
+    assert!(repository
+        .find_commit(new_commit_id)
+        .unwrap()
+        .is_conflicted());
+
+    // the worktree has the automatic resolution, which favors the branch
+    assert!(branch.files.is_empty());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "conflict"
+    );
+}
+
+#[test]
+fn commit_already_in_the_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit", None, false)
+        .unwrap();
+
+    let err = controller
+        .cherry_pick(project, branch_id, commit_id)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!("commit {commit_id} has no changes that aren't in the branch already")
+    );
+}
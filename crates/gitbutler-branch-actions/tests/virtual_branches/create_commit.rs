@@ -1,6 +1,8 @@
 use gitbutler_branch::{Branch, BranchCreateRequest, BranchUpdateRequest};
-use gitbutler_branch_actions::VirtualBranch;
+use gitbutler_branch_actions::{LineSelection, VirtualBranch};
+use gitbutler_diff::ChangeType;
 use gitbutler_id::id::Id;
+use gitbutler_project::RenameDetection;
 
 use super::*;
 
@@ -114,6 +116,203 @@ fn should_reset_into_same_branch() {
     assert_eq!(files.len(), 1);
 }
 
+#[test]
+fn should_commit_selected_lines_only() {
+    let Test {
+        project,
+        controller,
+        repository,
+        ..
+    } = &Test::default();
+
+    let mut lines = repository.gen_file("file.txt", 7);
+    commit_and_push_initial(repository);
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    lines[1] = "change 1".to_string();
+    lines[2] = "change 2".to_string();
+    repository.write_file("file.txt", &lines);
+
+    let hunk = get_virtual_branch(controller, project, branch_id).files[0].hunks[0].clone();
+    assert_eq!(
+        hunk.diff,
+        "@@ -1,6 +1,6 @@\n line 0\n-line 1\n-line 2\n+change 1\n+change 2\n line 3\n line 4\n line 5\n"
+    );
+
+    let commit_id = controller
+        .commit_lines(
+            project,
+            branch_id,
+            "first change",
+            &LineSelection {
+                file_path: "file.txt".into(),
+                hunk_id: hunk.id,
+                lines: vec![2, 4],
+            },
+            false,
+        )
+        .unwrap();
+
+    let tree = repository.find_commit(commit_id).unwrap().tree().unwrap();
+    let git_repo = git2::Repository::open(repository.path()).unwrap();
+    let blob = git_repo
+        .find_blob(tree.get_path("file.txt".as_ref()).unwrap().id())
+        .unwrap();
+    assert_eq!(
+        blob.content(),
+        b"line 0\nchange 1\nline 2\nline 3\nline 4\nline 5\nline 6"
+    );
+
+    let branch = get_virtual_branch(controller, project, branch_id);
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(
+        branch.files.len(),
+        1,
+        "the rest of the hunk stays on the branch"
+    );
+    assert_eq!(
+        branch.files[0].hunks[0].diff,
+        "@@ -1,6 +1,6 @@\n line 0\n change 1\n-line 2\n+change 2\n line 3\n line 4\n line 5\n"
+    );
+}
+
+#[test]
+fn should_commit_selected_lines_of_added_file_only() {
+    let Test {
+        project,
+        controller,
+        repository,
+        ..
+    } = &Test::default();
+
+    repository.gen_file("file.txt", 1);
+    commit_and_push_initial(repository);
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    repository.gen_file("new.txt", 3);
+
+    let hunk = get_virtual_branch(controller, project, branch_id).files[0].hunks[0].clone();
+    assert_eq!(
+        hunk.diff,
+        "@@ -0,0 +1,3 @@\n+line 0\n+line 1\n+line 2\n\\ No newline at end of file\n"
+    );
+
+    let commit_id = controller
+        .commit_lines(
+            project,
+            branch_id,
+            "add part of the file",
+            &LineSelection {
+                file_path: "new.txt".into(),
+                hunk_id: hunk.id,
+                lines: vec![1, 2],
+            },
+            false,
+        )
+        .unwrap();
+
+    let tree = repository.find_commit(commit_id).unwrap().tree().unwrap();
+    let git_repo = git2::Repository::open(repository.path()).unwrap();
+    let blob = git_repo
+        .find_blob(tree.get_path("new.txt".as_ref()).unwrap().id())
+        .unwrap();
+    assert_eq!(blob.content(), b"line 0\nline 1\n");
+
+    let branch = get_virtual_branch(controller, project, branch_id);
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(
+        branch.files.len(),
+        1,
+        "the rest of the file stays on the branch"
+    );
+}
+
+#[test]
+fn should_commit_selected_lines_of_renamed_file_only() {
+    let Test {
+        project,
+        controller,
+        repository,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "1\n2\n3\n4\n5\n6\n7\n8\n",
+    )
+    .unwrap();
+    commit_and_push_initial(repository);
+
+    let mut project = project.clone();
+    project.rename_detection = Some(RenameDetection::default());
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(&project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::remove_file(repository.path().join("file.txt")).unwrap();
+    fs::write(
+        repository.path().join("moved.txt"),
+        "1\ntwo\n3\n4\n5\n6\nseven\n8\n",
+    )
+    .unwrap();
+
+    let file = get_virtual_branch(controller, &project, branch_id).files[0].clone();
+    assert_eq!(file.path, PathBuf::from("moved.txt"));
+    let hunk = file.hunks[0].clone();
+    assert_eq!(hunk.change_type, ChangeType::Renamed);
+    assert_eq!(
+        hunk.diff,
+        "@@ -1,8 +1,8 @@\n 1\n-2\n+two\n 3\n 4\n 5\n 6\n-7\n+seven\n 8\n"
+    );
+
+    let commit_id = controller
+        .commit_lines(
+            &project,
+            branch_id,
+            "move the file with part of its changes",
+            &LineSelection {
+                file_path: "moved.txt".into(),
+                hunk_id: hunk.id,
+                lines: vec![2, 3],
+            },
+            false,
+        )
+        .unwrap();
+
+    let tree = repository.find_commit(commit_id).unwrap().tree().unwrap();
+    assert!(tree.get_path("file.txt".as_ref()).is_err());
+    let git_repo = git2::Repository::open(repository.path()).unwrap();
+    let blob = git_repo
+        .find_blob(tree.get_path("moved.txt".as_ref()).unwrap().id())
+        .unwrap();
+    assert_eq!(blob.content(), b"1\ntwo\n3\n4\n5\n6\n7\n8\n");
+
+    let branch = get_virtual_branch(controller, &project, branch_id);
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(
+        branch.files[0].hunks[0].diff, "@@ -4,5 +4,5 @@\n 4\n 5\n 6\n-7\n+seven\n 8\n",
+        "the rest of the changes stays on the branch"
+    );
+}
+
 fn commit_and_push_initial(repository: &TestProject) {
     repository.commit_all("initial commit");
     repository.push();
@@ -0,0 +1,75 @@
+use gitbutler_project::{AuthKey, RemoteFetchErrorKind};
+
+use super::*;
+
+#[test]
+fn reports_each_remote() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let missing_remote = tempfile::tempdir().unwrap();
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .remote(
+            "missing",
+            missing_remote.path().join("repo").to_str().unwrap(),
+        )
+        .unwrap();
+
+    let results = controller.fetch_from_remotes(project, None).unwrap();
+
+    assert_eq!(
+        results.keys().collect::<Vec<_>>(),
+        ["missing", "origin"],
+        "all remotes are fetched"
+    );
+    assert_eq!(results["origin"].error, None);
+    let error = results["missing"]
+        .error
+        .as_ref()
+        .expect("the remote doesn't exist");
+    assert_eq!(
+        error.kind,
+        RemoteFetchErrorKind::Other,
+        "a missing repository is neither a network nor an authentication problem"
+    );
+    assert!(!error.message.is_empty());
+
+    let fetch_result = projects::FetchResult::from_remotes(&results);
+    let projects::FetchResult::Error { error, .. } = fetch_result else {
+        panic!("one remote failed to fetch");
+    };
+    assert!(error.starts_with("missing: "));
+}
+
+#[test]
+fn network_failures_of_git2_are_not_auth_failures() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .remote("unreachable", "ssh://git@127.0.0.1:1/repo.git")
+        .unwrap();
+
+    let mut project = project.clone();
+    project.preferred_key = AuthKey::Local {
+        private_key_path: repository.path().join("missing-key"),
+    };
+    let results = controller.fetch_from_remotes(&project, None).unwrap();
+
+    assert_eq!(results["origin"].error, None);
+    let error = results["unreachable"]
+        .error
+        .as_ref()
+        .expect("nothing listens on the port");
+    assert_eq!(error.kind, RemoteFetchErrorKind::Network);
+}
@@ -0,0 +1,144 @@
+use gitbutler_diff::lfs::LfsPointer;
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails},
+    OplogExt,
+};
+
+use super::*;
+
+/// Enough of a PNG for its type to be detected, and for Git to consider it binary.
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+
+fn pointer(size: u64) -> Vec<u8> {
+    LfsPointer {
+        oid: "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393".into(),
+        size,
+    }
+    .to_bytes()
+}
+
+#[test]
+fn lfs_pointer_changes_are_summarized() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("asset.bin"), pointer(12)).unwrap();
+    repository.commit_all("add asset");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    fs::write(repository.path().join("asset.bin"), pointer(3500)).unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).unwrap();
+    let hunks = &branches[0].files[0].hunks;
+    assert_eq!(hunks.len(), 1);
+    let info = hunks[0]
+        .content_info
+        .as_ref()
+        .expect("pointers are detected");
+    assert!(info.lfs);
+    assert_eq!(info.summary, "LFS object changed (12 B → 3.4 KiB)");
+}
+
+#[test]
+fn binary_files_have_size_and_mime_type() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    fs::write(repository.path().join("image.png"), PNG).unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).unwrap();
+    let hunk = &branches[0].files[0].hunks[0];
+    assert!(hunk.binary);
+    let info = hunk
+        .content_info
+        .as_ref()
+        .expect("binary files are described");
+    assert!(!info.lfs);
+    assert_eq!(info.mime_type.as_deref(), Some("image/png"));
+    assert_eq!(info.summary, "Binary file added (image/png, 29 B)");
+}
+
+#[test]
+fn snapshots_record_lfs_pointers_instead_of_content() -> anyhow::Result<()> {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join(".gitattributes"),
+        "*.png filter=lfs diff=lfs merge=lfs -text\n",
+    )?;
+    repository.commit_all("track images in lfs");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    let branch_id = controller.create_virtual_branch(project, &BranchCreateRequest::default())?;
+
+    fs::write(repository.path().join("image.png"), PNG)?;
+    controller.list_virtual_branches(project)?;
+    let snapshot_id = {
+        let mut guard = project.exclusive_worktree_access();
+        project
+            .create_snapshot(
+                SnapshotDetails::new(OperationKind::FileChanges),
+                guard.write_permission(),
+            )?
+            .expect("snapshots are enabled")
+    };
+
+    let repo = git2::Repository::open(repository.path())?;
+    let entry = repo.find_commit(snapshot_id)?.tree()?.get_path(
+        &PathBuf::from("virtual_branches")
+            .join(branch_id.to_string())
+            .join("tree")
+            .join("image.png"),
+    )?;
+    assert_eq!(
+        entry.id(),
+        git2::Oid::hash_object(
+            git2::ObjectType::Blob,
+            &LfsPointer::for_content(PNG).to_bytes()
+        )?,
+        "the snapshot tree records the pointer"
+    );
+    assert_ne!(
+        entry.id(),
+        git2::Oid::hash_object(git2::ObjectType::Blob, PNG)?,
+        "the snapshot tree doesn't record the content"
+    );
+    let pointer = LfsPointer::from_bytes(repo.find_blob(entry.id())?.content())
+        .expect("the snapshot contains the pointer");
+    assert_eq!(
+        fs::read(pointer.object_path(repo.path()))?,
+        PNG,
+        "the content is in the LFS store"
+    );
+
+    fs::write(repository.path().join("image.png"), "changed")?;
+    project.restore_paths_from_snapshot(snapshot_id, &["image.png".into()])?;
+    assert_eq!(
+        fs::read(repository.path().join("image.png"))?,
+        PNG,
+        "the object is put back from the LFS store"
+    );
+    Ok(())
+}
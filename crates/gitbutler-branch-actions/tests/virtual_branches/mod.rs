@@ -57,24 +57,33 @@ impl Test {
     }
 }
 
+mod absorb;
 mod amend;
 mod apply_virtual_branch;
+mod autosquash;
+mod cherry_pick;
 mod convert_to_real_branch;
 mod create_commit;
 mod create_virtual_branch_from_branch;
 mod delete_virtual_branch;
+mod fetch_from_remotes;
 mod init;
 mod insert_blank_commit;
+mod lfs;
 mod list;
 mod list_details;
 mod move_commit_file;
 mod move_commit_to_vbranch;
 mod oplog;
+mod push_virtual_branch;
 mod references;
+mod rename_detection;
 mod reorder_commit;
 mod reset_virtual_branch;
+mod revert;
 mod selected_for_changes;
 mod set_base_branch;
+mod split_commit;
 mod squash;
 mod unapply_ownership;
 mod undo_commit;
@@ -82,6 +91,7 @@ mod update_base_branch;
 mod update_commit_message;
 mod upstream;
 mod verify_branch;
+mod whitespace_mode;
 mod workspace_migration;
 
 #[test]
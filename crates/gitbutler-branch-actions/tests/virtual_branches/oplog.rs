@@ -1,7 +1,11 @@
 use std::{io::Write, path::Path, time::Duration};
 
-use gitbutler_branch::{BranchCreateRequest, VirtualBranchesHandle};
-use gitbutler_oplog::OplogExt;
+use gitbutler_branch::{BranchCreateRequest, BranchId, VirtualBranchesHandle};
+use gitbutler_oplog::{
+    entry::{OperationKind, SnapshotDetails, SnapshotQuery, Trailer},
+    OplogBundleManifest, OplogExt,
+};
+use gitbutler_project::OplogRetention;
 use itertools::Itertools;
 
 use super::*;
@@ -84,6 +88,538 @@ fn workdir_vbranch_restore() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn query_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let worktree_dir = repository.path();
+    for round in 0..3 {
+        fs::write(worktree_dir.join(format!("file{round}.txt")), make_lines(5))?;
+        let branch_id = controller.create_virtual_branch(
+            project,
+            &BranchCreateRequest {
+                name: Some(round.to_string()),
+                ..Default::default()
+            },
+        )?;
+        controller.create_commit(project, branch_id, &format!("commit {round}"), None, false)?;
+    }
+    let all = project.list_snapshots(100, None)?;
+    assert_eq!(
+        project.query_snapshots(&SnapshotQuery::default(), 100, None)?,
+        all,
+        "the default query matches everything"
+    );
+
+    let commits = project.query_snapshots(
+        &SnapshotQuery {
+            operations: vec![OperationKind::CreateCommit],
+            ..Default::default()
+        },
+        100,
+        None,
+    )?;
+    assert_eq!(commits.len(), 3);
+    assert!(commits
+        .iter()
+        .all(|s| s.details.as_ref().unwrap().operation == OperationKind::CreateCommit));
+    assert_eq!(
+        project
+            .query_snapshots(
+                &SnapshotQuery {
+                    operations: vec![OperationKind::CreateCommit],
+                    ..Default::default()
+                },
+                1,
+                None
+            )?
+            .len(),
+        1,
+        "the limit applies to matching snapshots"
+    );
+
+    let by_trailer = project.query_snapshots(
+        &SnapshotQuery {
+            operations: vec![OperationKind::CreateCommit],
+            trailers: vec![Trailer {
+                key: "message".into(),
+                value: "commit 1".into(),
+            }],
+            ..Default::default()
+        },
+        100,
+        None,
+    )?;
+    assert_eq!(by_trailer.len(), 1);
+    assert_eq!(by_trailer[0].commit_id, commits[1].commit_id);
+
+    let by_path = project.query_snapshots(
+        &SnapshotQuery {
+            path: Some("file1.txt".into()),
+            ..Default::default()
+        },
+        100,
+        None,
+    )?;
+    assert!(!by_path.is_empty());
+    assert!(by_path
+        .iter()
+        .all(|s| s.files_changed.iter().any(|p| p == Path::new("file1.txt"))));
+
+    let newest = all[0].created_at.seconds();
+    assert!(project
+        .query_snapshots(
+            &SnapshotQuery {
+                since: Some(newest + 1),
+                ..Default::default()
+            },
+            100,
+            None
+        )?
+        .is_empty());
+    assert_eq!(
+        project
+            .query_snapshots(
+                &SnapshotQuery {
+                    until: Some(newest),
+                    ..Default::default()
+                },
+                100,
+                None
+            )?
+            .len(),
+        all.len()
+    );
+    Ok(())
+}
+
+#[test]
+fn page_through_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let worktree_dir = repository.path();
+    for round in 0..3 {
+        fs::write(worktree_dir.join(format!("file{round}.txt")), make_lines(5))?;
+        let branch_id = controller.create_virtual_branch(project, &Default::default())?;
+        controller.create_commit(project, branch_id, &format!("commit {round}"), None, false)?;
+    }
+    let all = project.list_snapshots(100, None)?;
+    let commits_query = SnapshotQuery {
+        operations: vec![OperationKind::CreateCommit],
+        ..Default::default()
+    };
+    let all_commits = project.query_snapshots(&commits_query, 100, None)?;
+    assert_eq!(all_commits.len(), 3);
+
+    assert!(project.list_snapshots(0, None)?.is_empty());
+    assert!(
+        project.query_snapshots(&commits_query, 0, None)?.is_empty(),
+        "an empty page stays empty even though there are matches"
+    );
+
+    let first_page = project.query_snapshots(&commits_query, 2, None)?;
+    assert_eq!(first_page, all_commits[..2]);
+    let second_page = project.query_snapshots(&commits_query, 2, Some(first_page[1].commit_id))?;
+    assert_eq!(
+        second_page,
+        all_commits[1..],
+        "the cursor is part of the next page as it matches the query"
+    );
+
+    let oldest = all.last().unwrap();
+    assert_eq!(
+        project.list_snapshots(100, Some(oldest.commit_id))?,
+        [oldest.clone()],
+        "nothing lies past the oldest snapshot"
+    );
+    assert_ne!(
+        oldest.details.as_ref().map(|details| details.operation),
+        Some(OperationKind::CreateCommit)
+    );
+    assert!(project
+        .query_snapshots(&commits_query, 100, Some(oldest.commit_id))?
+        .is_empty());
+
+    let message_query = SnapshotQuery {
+        trailers: vec![Trailer {
+            key: "message".into(),
+            value: "commit 1".into(),
+        }],
+        ..commits_query.clone()
+    };
+    assert_eq!(
+        project.query_snapshots(&message_query, 1, None)?,
+        all_commits[1..2],
+        "pages skip the snapshots that don't match"
+    );
+    assert!(
+        project
+            .query_snapshots(&message_query, 1, Some(all_commits[2].commit_id))?
+            .is_empty(),
+        "matches more recent than the cursor aren't part of the page"
+    );
+    Ok(())
+}
+
+#[test]
+fn prune_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    for round in 0..3 {
+        fs::write(
+            repository.path().join(format!("file{round}.txt")),
+            make_lines(5),
+        )?;
+        controller.create_virtual_branch(project, &Default::default())?;
+    }
+    let snapshots = project.list_snapshots(100, None)?;
+    assert_eq!(snapshots.len(), 3);
+
+    let mut guard = project.exclusive_worktree_access();
+    assert!(!project.should_prune_snapshots(Duration::ZERO)?);
+    assert_eq!(
+        project.prune_snapshots(guard.write_permission())?,
+        0,
+        "without retention policy, nothing is dropped"
+    );
+
+    let mut project = project.clone();
+    project.oplog_retention = Some(OplogRetention {
+        keep_all_days: 1,
+        hourly_days: 0,
+        daily_days: Some(0),
+    });
+    let day = Duration::from_secs(24 * 60 * 60);
+    assert!(project.should_prune_snapshots(day)?, "never pruned before");
+    assert_eq!(
+        project.prune_snapshots(guard.write_permission())?,
+        0,
+        "all snapshots are recent"
+    );
+    assert!(
+        !project.should_prune_snapshots(day)?,
+        "pruned just now, even if nothing was dropped"
+    );
+    assert_eq!(project.list_snapshots(100, None)?, snapshots);
+
+    project.oplog_retention = Some(OplogRetention {
+        keep_all_days: 0,
+        hourly_days: 0,
+        daily_days: Some(0),
+    });
+    let modified_at = || -> anyhow::Result<String> {
+        let oplog =
+            fs::read_to_string(repository.path().join(".git/gitbutler/operations-log.toml"))?;
+        Ok(oplog
+            .lines()
+            .skip_while(|line| *line != "[modified_at]")
+            .skip(1)
+            .take_while(|line| !line.is_empty() && !line.starts_with('['))
+            .join("\n"))
+    };
+    let modified_at_before_pruning = modified_at()?;
+    assert!(!modified_at_before_pruning.is_empty());
+    assert_eq!(project.prune_snapshots(guard.write_permission())?, 2);
+    assert_eq!(
+        modified_at()?,
+        modified_at_before_pruning,
+        "pruning doesn't count as creating a snapshot, which would delay the next automatic one"
+    );
+    let remaining = project.list_snapshots(100, None)?;
+    assert_eq!(
+        remaining.len(),
+        1,
+        "the most recent snapshot is always kept"
+    );
+    assert_ne!(
+        remaining[0].commit_id, snapshots[0].commit_id,
+        "it was rewritten to not have a parent"
+    );
+    assert_eq!(remaining[0].details, snapshots[0].details);
+    assert_eq!(project.oplog_head()?, Some(remaining[0].commit_id));
+
+    let reflog = fs::read_to_string(
+        repository
+            .path()
+            .join(".git/logs/refs/heads/gitbutler/target"),
+    )?;
+    assert!(
+        reflog.contains(&remaining[0].commit_id.to_string())
+            && !reflog.contains(&snapshots[0].commit_id.to_string()),
+        "the reflog keeps the new oplog head reachable, and the old one unreachable"
+    );
+    drop(guard);
+
+    project.restore_snapshot(remaining[0].commit_id)?;
+    Ok(())
+}
+
+#[test]
+fn restore_paths_from_snapshot() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let worktree_dir = repository.path();
+    fs::write(worktree_dir.join("file0.txt"), "content 0")?;
+    fs::write(worktree_dir.join("file1.txt"), "content 1")?;
+    let branch_id = controller.create_virtual_branch(project, &Default::default())?;
+    controller.create_commit(project, branch_id, "commit", None, false)?;
+    let snapshot_id = {
+        let mut guard = project.exclusive_worktree_access();
+        project
+            .create_snapshot(
+                SnapshotDetails::new(OperationKind::FileChanges),
+                guard.write_permission(),
+            )?
+            .expect("snapshots are enabled")
+    };
+
+    fs::write(worktree_dir.join("file0.txt"), "changed")?;
+    fs::remove_file(worktree_dir.join("file1.txt"))?;
+    fs::write(worktree_dir.join("file2.txt"), "new")?;
+
+    let restore_snapshot_id = project
+        .restore_paths_from_snapshot(snapshot_id, &["file0.txt".into(), "file1.txt".into()])?
+        .expect("snapshots are enabled");
+    assert_eq!(
+        fs::read_to_string(worktree_dir.join("file0.txt"))?,
+        "content 0"
+    );
+    assert_eq!(
+        fs::read_to_string(worktree_dir.join("file1.txt"))?,
+        "content 1"
+    );
+    assert_eq!(
+        fs::read_to_string(worktree_dir.join("file2.txt"))?,
+        "new",
+        "paths that weren't mentioned are left alone"
+    );
+
+    let latest = &project.list_snapshots(1, None)?[0];
+    assert_eq!(latest.commit_id, restore_snapshot_id);
+    let details = latest.details.as_ref().expect("parseable");
+    assert_eq!(details.operation, OperationKind::RestoreFromSnapshot);
+    assert!(details.trailers.contains(&Trailer {
+        key: "restored_paths".into(),
+        value: "file0.txt\nfile1.txt".into(),
+    }));
+
+    project.restore_paths_from_snapshot(snapshot_id, &["file2.txt".into()])?;
+    assert!(
+        !worktree_dir.join("file2.txt").exists(),
+        "paths that didn't exist in the snapshot are removed"
+    );
+
+    assert!(project
+        .restore_paths_from_snapshot(snapshot_id, &["../outside".into()])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn restore_branch_from_snapshot() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let worktree_dir = repository.path();
+    fs::write(worktree_dir.join("file.txt"), "one")?;
+    let branch_id = controller.create_virtual_branch(project, &Default::default())?;
+    let commit1_id = controller.create_commit(project, branch_id, "commit 1", None, false)?;
+    let snapshot_id = {
+        let mut guard = project.exclusive_worktree_access();
+        project
+            .create_snapshot(
+                SnapshotDetails::new(OperationKind::FileChanges),
+                guard.write_permission(),
+            )?
+            .expect("snapshots are enabled")
+    };
+
+    fs::write(worktree_dir.join("file.txt"), "two")?;
+    let commit2_id = controller.create_commit(project, branch_id, "commit 2", None, false)?;
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    assert_eq!(vb_state.get_branch(branch_id)?.head, commit2_id);
+
+    controller.restore_branch_from_snapshot(project, snapshot_id, branch_id)?;
+    let branch = vb_state.get_branch(branch_id)?;
+    assert_eq!(branch.head, commit1_id);
+    assert_eq!(
+        fs::read_to_string(worktree_dir.join("file.txt"))?,
+        "one",
+        "the files changed by the branch are restored as well"
+    );
+
+    let repo = git2::Repository::open(worktree_dir)?;
+    assert_eq!(
+        repo.find_reference(&branch.refname()?.to_string())?
+            .peel_to_commit()?
+            .id(),
+        commit1_id,
+        "the branch reference points to the restored head"
+    );
+    let workspace_parents: Vec<_> = repo.head()?.peel_to_commit()?.parent_ids().collect();
+    assert!(
+        workspace_parents.contains(&commit1_id) && !workspace_parents.contains(&commit2_id),
+        "the workspace commit is based on the restored head"
+    );
+
+    let details = project.list_snapshots(1, None)?[0]
+        .details
+        .clone()
+        .expect("parseable");
+    assert_eq!(details.operation, OperationKind::RestoreFromSnapshot);
+    assert!(details.trailers.contains(&Trailer {
+        key: "restored_branch".into(),
+        value: branch.name,
+    }));
+
+    assert!(controller
+        .restore_branch_from_snapshot(project, snapshot_id, BranchId::generate())
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn export_and_import_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test {
+        repository,
+        controller,
+        project,
+        ..
+    } = &test;
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    for round in 0..3 {
+        fs::write(
+            repository.path().join(format!("file{round}.txt")),
+            make_lines(5),
+        )?;
+        controller.create_virtual_branch(project, &Default::default())?;
+    }
+    let snapshots = project.list_snapshots(100, None)?;
+    assert_eq!(snapshots.len(), 3);
+
+    let tmp = tempfile::tempdir()?;
+    let bundle_path = tmp.path().join("all.bundle");
+    let manifest = project.export_snapshots(&bundle_path, None, None)?;
+    assert!(bundle_path.is_file());
+    assert!(OplogBundleManifest::path_for(&bundle_path).is_file());
+    assert_eq!(manifest.head, snapshots[0].commit_id);
+    assert_eq!(
+        manifest.snapshots.iter().map(|s| s.id).collect::<Vec<_>>(),
+        snapshots.iter().map(|s| s.commit_id).collect::<Vec<_>>(),
+        "the complete chain doesn't need to be rewritten"
+    );
+
+    let range_bundle_path = tmp.path().join("range.bundle");
+    let range_manifest = project.export_snapshots(
+        &range_bundle_path,
+        Some(snapshots[0].commit_id),
+        Some(snapshots[1].commit_id),
+    )?;
+    assert_eq!(range_manifest.snapshots.len(), 2);
+    assert_eq!(
+        range_manifest
+            .snapshots
+            .iter()
+            .map(|s| s.original_id)
+            .collect::<Vec<_>>(),
+        [snapshots[0].commit_id, snapshots[1].commit_id]
+    );
+    assert_ne!(
+        range_manifest.head, snapshots[0].commit_id,
+        "partial chains are rewritten to be self-contained"
+    );
+
+    let other = Test::default();
+    other
+        .controller
+        .set_base_branch(
+            &other.project,
+            &"refs/remotes/origin/master".parse().unwrap(),
+        )
+        .unwrap();
+    other
+        .controller
+        .create_virtual_branch(&other.project, &Default::default())?;
+    let other_snapshots = other.project.list_snapshots(100, None)?;
+    assert_eq!(other_snapshots.len(), 1);
+
+    let imported = other.project.import_snapshots(&range_bundle_path)?;
+    assert_eq!(imported.len(), 2);
+    let all_other_snapshots = other.project.list_snapshots(100, None)?;
+    assert_eq!(
+        all_other_snapshots
+            .iter()
+            .map(|s| s.commit_id)
+            .collect::<Vec<_>>(),
+        [imported[0], imported[1], other_snapshots[0].commit_id],
+        "imported snapshots are grafted onto the existing oplog"
+    );
+    assert_eq!(
+        all_other_snapshots[0].details, snapshots[0].details,
+        "the snapshot details are retained"
+    );
+
+    fs::write(OplogBundleManifest::path_for(&bundle_path), "version = 1")?;
+    assert!(
+        other.project.import_snapshots(&bundle_path).is_err(),
+        "the manifest must match the bundle"
+    );
+    Ok(())
+}
+
 fn wd_file_count(worktree_dir: &&Path) -> anyhow::Result<usize> {
     Ok(glob::glob(&worktree_dir.join("file*").to_string_lossy())?.count())
 }
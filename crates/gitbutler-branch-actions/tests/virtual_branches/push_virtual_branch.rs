@@ -0,0 +1,99 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_error::error::{AnyhowContextExt, Code};
+
+use super::*;
+
+#[test]
+fn pre_push_hook_rejection() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch1_id, "commit", None, false)
+        .unwrap();
+
+    let hook = b"#!/bin/sh
+    read local_ref local_sha remote_ref remote_sha
+    test -f file.txt || exit 0
+    echo \"rejected $remote_ref\" >&2
+    exit 1
+            ";
+    git2_hooks::create_hook(
+        &git2::Repository::open(repository.path()).unwrap(),
+        "pre-push",
+        hook,
+    );
+
+    let err = controller
+        .push_virtual_branch(project, branch1_id, false, None)
+        .unwrap_err();
+    assert_eq!(
+        err.custom_context().map(|ctx| ctx.code),
+        Some(Code::HookRejected)
+    );
+    assert!(
+        err.source()
+            .unwrap()
+            .to_string()
+            .starts_with("pre-push hook rejected: rejected refs/heads/"),
+        "the hook runs in a worktree with the pushed commit checked out, and sees what's pushed"
+    );
+}
+
+#[test]
+fn pre_push_hook_is_not_run_if_disabled_for_project() {
+    let Test {
+        repository,
+        project_id,
+        projects,
+        controller,
+        ..
+    } = &Test::default();
+
+    let project = &projects
+        .update(&projects::UpdateRequest {
+            id: *project_id,
+            run_git_hooks: Some(false),
+            ..Default::default()
+        })
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch1_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    controller
+        .create_commit(project, branch1_id, "commit", None, false)
+        .unwrap();
+
+    let hook = b"#!/bin/sh
+    exit 1
+            ";
+    git2_hooks::create_hook(
+        &git2::Repository::open(repository.path()).unwrap(),
+        "pre-push",
+        hook,
+    );
+
+    controller
+        .push_virtual_branch(project, branch1_id, false, None)
+        .unwrap();
+}
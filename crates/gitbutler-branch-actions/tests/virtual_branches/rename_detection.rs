@@ -0,0 +1,121 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_diff::ChangeType;
+use gitbutler_project::RenameDetection;
+
+use super::*;
+
+#[test]
+fn moved_file_stays_on_its_branch() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "1\n2\n3\n4\n5\n6\n7\n8\n",
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    let mut project = project.clone();
+    project.rename_detection = Some(RenameDetection::default());
+
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(&project, &BranchCreateRequest::default())
+        .unwrap();
+    fs::write(
+        repository.path().join("file.txt"),
+        "1\n2\n3\n4\n5\n6\n7\n8\n9\n",
+    )
+    .unwrap();
+    controller.list_virtual_branches(&project).unwrap();
+
+    // new changes would go to the other branch
+    let other_branch_id = controller
+        .create_virtual_branch(
+            &project,
+            &BranchCreateRequest {
+                selected_for_changes: Some(true),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    fs::rename(
+        repository.path().join("file.txt"),
+        repository.path().join("moved.txt"),
+    )
+    .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    let branch = branches.iter().find(|b| b.id == branch_id).unwrap();
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].path, PathBuf::from("moved.txt"));
+    let hunk = &branch.files[0].hunks[0];
+    assert_eq!(hunk.change_type, ChangeType::Renamed);
+    assert_eq!(hunk.old_file_path, Some(PathBuf::from("file.txt")));
+
+    let other_branch = branches.iter().find(|b| b.id == other_branch_id).unwrap();
+    assert!(other_branch.files.is_empty());
+
+    let commit_id = controller
+        .create_commit(&project, branch_id, "move file", None, false)
+        .unwrap();
+    let tree = repository.find_commit(commit_id).unwrap().tree().unwrap();
+    assert!(tree.get_path("file.txt".as_ref()).is_err());
+    assert!(tree.get_path("moved.txt".as_ref()).is_ok());
+}
+
+#[test]
+fn without_detection_moved_file_is_deleted_and_added() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "1\n2\n3\n4\n5\n6\n7\n8\n",
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::rename(
+        repository.path().join("file.txt"),
+        repository.path().join("moved.txt"),
+    )
+    .unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(project).unwrap();
+    let mut files = branches[0]
+        .files
+        .iter()
+        .map(|file| (file.path.clone(), file.hunks[0].change_type))
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        files,
+        [
+            (PathBuf::from("file.txt"), ChangeType::Deleted),
+            (PathBuf::from("moved.txt"), ChangeType::Added)
+        ]
+    );
+}
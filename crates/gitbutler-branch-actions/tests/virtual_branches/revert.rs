@@ -0,0 +1,289 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::RevertDestination;
+use gitbutler_error::error::Code;
+
+use super::*;
+
+/// Commit `file.txt` with `content` to the target branch and return the commit id.
+fn target_commit(repository: &TestProject, content: &str) -> git2::Oid {
+    fs::write(repository.path().join("file.txt"), content).unwrap();
+    let commit_id = repository.commit_all(&format!("write {content}"));
+    repository.push();
+    commit_id
+}
+
+#[test]
+fn target_commit_as_uncommitted_changes() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let commit_id = target_commit(repository, "content");
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let new_commit_id = controller
+        .revert(project, branch_id, commit_id, RevertDestination::Worktree)
+        .unwrap();
+    assert_eq!(new_commit_id, None);
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert!(branch.commits.is_empty());
+    assert_eq!(branch.files.len(), 1);
+    assert_eq!(branch.files[0].path.display().to_string(), "file.txt");
+    assert!(!repository.path().join("file.txt").exists());
+}
+
+#[test]
+fn target_commit_as_uncommitted_changes_leaves_hunks_of_other_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let lines: Vec<_> = (1..=20).map(|i| i.to_string()).collect();
+    target_commit(repository, &format!("{}\n", lines.join("\n")));
+    let mut reverted_lines = lines.clone();
+    reverted_lines[1] = "two".to_string();
+    let commit_id = target_commit(repository, &format!("{}\n", reverted_lines.join("\n")));
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let other_branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    let mut changed_lines = reverted_lines.clone();
+    changed_lines[18] = "nineteen".to_string();
+    fs::write(
+        repository.path().join("file.txt"),
+        format!("{}\n", changed_lines.join("\n")),
+    )
+    .unwrap();
+    controller.list_virtual_branches(project).unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    controller
+        .revert(project, branch_id, commit_id, RevertDestination::Worktree)
+        .unwrap();
+
+    let branches = controller.list_virtual_branches(project).unwrap().0;
+    let hunk_diffs = |id| {
+        let branch = branches.iter().find(|b| b.id == id).unwrap();
+        branch
+            .files
+            .iter()
+            .flat_map(|file| {
+                file.hunks
+                    .iter()
+                    .map(|hunk| String::from_utf8_lossy(&hunk.diff).into_owned())
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let reverted_hunks = hunk_diffs(branch_id);
+    assert_eq!(reverted_hunks.len(), 1);
+    assert!(reverted_hunks[0].contains("-two\n+2\n"));
+
+    let other_hunks = hunk_diffs(other_branch_id);
+    assert_eq!(other_hunks.len(), 1);
+    assert!(other_hunks[0].contains("-19\n+nineteen\n"));
+
+    changed_lines[1] = "2".to_string();
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        format!("{}\n", changed_lines.join("\n"))
+    );
+}
+
+#[test]
+fn target_commit_as_uncommitted_changes_next_to_hunks_of_other_branches() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let lines: Vec<_> = (1..=20).map(|i| i.to_string()).collect();
+    target_commit(repository, &format!("{}\n", lines.join("\n")));
+    let mut reverted_lines = lines.clone();
+    reverted_lines[1] = "two".to_string();
+    let commit_id = target_commit(repository, &format!("{}\n", reverted_lines.join("\n")));
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let other_branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    let mut changed_lines = reverted_lines.clone();
+    changed_lines[4] = "five".to_string();
+    let changed_content = format!("{}\n", changed_lines.join("\n"));
+    fs::write(repository.path().join("file.txt"), &changed_content).unwrap();
+    controller.list_virtual_branches(project).unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+    let err = controller
+        .revert(project, branch_id, commit_id, RevertDestination::Worktree)
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!(
+            "reverting commit {commit_id} changes file.txt next to the changes of another branch, revert it as a commit instead"
+        ),
+        "the reverted line would end up in the same hunk as the change of the other branch"
+    );
+
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        changed_content
+    );
+    let branches = controller.list_virtual_branches(project).unwrap().0;
+    let other_branch = branches.iter().find(|b| b.id == other_branch_id).unwrap();
+    assert_eq!(other_branch.files.len(), 1);
+    let branch = branches.iter().find(|b| b.id == branch_id).unwrap();
+    assert!(branch.files.is_empty());
+}
+
+#[test]
+fn target_commit_as_new_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let commit_id = target_commit(repository, "content");
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let new_commit_id = controller
+        .revert(project, branch_id, commit_id, RevertDestination::Commit)
+        .unwrap()
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert!(branch.files.is_empty());
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(branch.commits[0].id, new_commit_id);
+    assert!(!branch.commits[0].conflicted);
+    assert_eq!(
+        branch.commits[0].description,
+        format!("Revert \"write content\"\n\nThis reverts commit {commit_id}.\n").as_str()
+    );
+    assert!(!repository.path().join("file.txt").exists());
+}
+
+#[test]
+fn conflicts_are_recorded_in_the_commit() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    target_commit(repository, "first");
+    let commit_id = target_commit(repository, "second");
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "changed").unwrap();
+    controller
+        .create_commit(project, branch_id, "change file", None, false)
+        .unwrap();
+
+    let err = controller
+        .revert(project, branch_id, commit_id, RevertDestination::Worktree)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!(
+            "reverting commit {commit_id} conflicts with the workspace, revert it as a commit instead"
+        )
+    );
+
+    let new_commit_id = controller
+        .revert(project, branch_id, commit_id, RevertDestination::Commit)
+        .unwrap()
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 2);
+    assert_eq!(branch.commits[0].id, new_commit_id);
+    assert!(branch.commits[0].conflicted);
+
+    let err = controller
+        .revert(
+            project,
+            branch_id,
+            new_commit_id,
+            RevertDestination::Worktree,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Can not revert a conflicted commit"
+    );
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::Validation));
+
+    // the worktree has the automatic resolution, which favors the branch
+    assert!(branch.files.is_empty());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "changed"
+    );
+}
@@ -0,0 +1,280 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::CommitSplit;
+use gitbutler_commit::commit_ext::CommitExt;
+
+use super::*;
+
+#[test]
+fn split_into_two_commits() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    // create commit
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    let commit1_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+    let commit1 = repository.find_commit(commit1_id).unwrap();
+
+    // create commit
+    fs::write(repository.path().join("file3.txt"), "content3").unwrap();
+    let commit2_id = controller
+        .create_commit(project, branch_id, "commit two", None, false)
+        .unwrap();
+    let commit2 = repository.find_commit(commit2_id).unwrap();
+
+    let new_commit_ids = controller
+        .split_commit(
+            project,
+            branch_id,
+            commit1_id,
+            &[
+                CommitSplit {
+                    ownership: "file.txt:1-2".parse().unwrap(),
+                    message: Some("first part".to_string()),
+                },
+                CommitSplit {
+                    ownership: "file2.txt:1-2".parse().unwrap(),
+                    message: None,
+                },
+            ],
+        )
+        .unwrap();
+    assert_eq!(new_commit_ids.len(), 2);
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+
+    assert_eq!(branch.commits.len(), 3);
+    assert!(branch.files.is_empty());
+
+    // the commit above was rebased
+    assert_eq!(&commit2.change_id(), &branch.commits[0].change_id);
+    assert_ne!(&commit2.id(), &branch.commits[0].id);
+    assert_eq!(branch.commits[0].files.len(), 1);
+
+    assert_eq!(branch.commits[1].id, new_commit_ids[1]);
+    assert_eq!(branch.commits[1].description, "commit one");
+    assert_eq!(branch.commits[1].files.len(), 1);
+    assert_eq!(
+        branch.commits[1].files[0].path.display().to_string(),
+        "file2.txt"
+    );
+
+    assert_eq!(branch.commits[2].id, new_commit_ids[0]);
+    assert_eq!(branch.commits[2].description, "first part");
+    assert_eq!(branch.commits[2].files.len(), 1);
+    assert_eq!(
+        branch.commits[2].files[0].path.display().to_string(),
+        "file.txt"
+    );
+
+    // all split commits have their own change-id, but keep the authorship
+    assert_ne!(&commit1.change_id(), &branch.commits[1].change_id);
+    assert_ne!(&commit1.change_id(), &branch.commits[2].change_id);
+    assert_ne!(&branch.commits[1].change_id, &branch.commits[2].change_id);
+    for id in &new_commit_ids {
+        let commit = repository.find_commit(*id).unwrap();
+        assert_eq!(commit.author().email(), commit1.author().email());
+        assert_eq!(commit.author().when(), commit1.author().when());
+    }
+
+    // the tree of the branch didn't change
+    assert_eq!(
+        repository
+            .find_commit(branch.commits[0].id)
+            .unwrap()
+            .tree_id(),
+        commit2.tree_id()
+    );
+}
+
+#[test]
+fn all_hunks_must_be_assigned() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "content").unwrap();
+    fs::write(repository.path().join("file2.txt"), "content2").unwrap();
+    fs::write(repository.path().join("file3.txt"), "content3").unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap();
+
+    let err = controller
+        .split_commit(
+            project,
+            branch_id,
+            commit_id,
+            &[
+                CommitSplit {
+                    ownership: "file.txt:1-2".parse().unwrap(),
+                    message: None,
+                },
+                CommitSplit {
+                    ownership: "file2.txt:1-2".parse().unwrap(),
+                    message: None,
+                },
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "all hunks must be assigned to a group, but some of file3.txt aren't"
+    );
+
+    let err = controller
+        .split_commit(
+            project,
+            branch_id,
+            commit_id,
+            &[
+                CommitSplit {
+                    ownership: "file.txt:1-2\nfile3.txt:1-2".parse().unwrap(),
+                    message: None,
+                },
+                CommitSplit {
+                    ownership: "file2.txt:1-2\nfile3.txt:1-2".parse().unwrap(),
+                    message: None,
+                },
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "hunk file3.txt:1-2 is part of more than one group"
+    );
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    assert_eq!(branch.commits.len(), 1);
+    assert_eq!(branch.commits[0].id, commit_id);
+}
+
+#[test]
+fn split_hunks_of_the_same_file() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    let lines: Vec<_> = (1..=20).map(|i| i.to_string()).collect();
+    fs::write(
+        repository.path().join("file.txt"),
+        format!("{}\n", lines.join("\n")),
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let file_with = |changes: &[(usize, &str)]| {
+        let mut lines = lines.clone();
+        for (idx, line) in changes {
+            lines[*idx] = line.to_string();
+        }
+        format!("{}\n", lines.join("\n"))
+    };
+    fs::write(
+        repository.path().join("file.txt"),
+        file_with(&[(1, "two"), (18, "nineteen")]),
+    )
+    .unwrap();
+    let commit_id = controller
+        .create_commit(project, branch_id, "change file", None, false)
+        .unwrap();
+
+    let branch = controller
+        .list_virtual_branches(project)
+        .unwrap()
+        .0
+        .into_iter()
+        .find(|b| b.id == branch_id)
+        .unwrap();
+    let mut hunks = branch.commits[0].files[0].hunks.clone();
+    hunks.sort_by_key(|hunk| hunk.start);
+    assert_eq!(hunks.len(), 2);
+    let claim = |hunk: &gitbutler_branch_actions::VirtualBranchHunk| {
+        format!("file.txt:{}-{}", hunk.start, hunk.end)
+            .parse()
+            .unwrap()
+    };
+
+    // the lower hunk goes first
+    let new_commit_ids = controller
+        .split_commit(
+            project,
+            branch_id,
+            commit_id,
+            &[
+                CommitSplit {
+                    ownership: claim(&hunks[1]),
+                    message: Some("change the end".to_string()),
+                },
+                CommitSplit {
+                    ownership: claim(&hunks[0]),
+                    message: Some("change the start".to_string()),
+                },
+            ],
+        )
+        .unwrap();
+    assert_eq!(new_commit_ids.len(), 2);
+
+    let git_repo = git2::Repository::open(repository.path()).unwrap();
+    let file_in = |commit_id: git2::Oid| {
+        let tree = repository.find_commit(commit_id).unwrap().tree().unwrap();
+        let blob = git_repo
+            .find_blob(tree.get_path("file.txt".as_ref()).unwrap().id())
+            .unwrap();
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    };
+    assert_eq!(file_in(new_commit_ids[0]), file_with(&[(18, "nineteen")]));
+    assert_eq!(
+        file_in(new_commit_ids[1]),
+        file_with(&[(1, "two"), (18, "nineteen")])
+    );
+}
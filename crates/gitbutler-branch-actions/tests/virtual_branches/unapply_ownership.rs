@@ -1,6 +1,8 @@
 use std::fs;
 
 use gitbutler_branch::{BranchCreateRequest, BranchOwnershipClaims};
+use gitbutler_diff::ChangeType;
+use gitbutler_project::{Project, RenameDetection};
 
 use super::Test;
 
@@ -55,3 +57,94 @@ fn should_unapply_with_commits() {
         .unwrap();
     assert!(branch.files.is_empty());
 }
+
+/// Commit `file.txt`, then move it to `moved.txt` with `content` and return the ownership of the moved file.
+fn move_file(test: &Test, content: &str) -> (Project, BranchOwnershipClaims) {
+    let Test {
+        project,
+        controller,
+        repository,
+        ..
+    } = test;
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "1\n2\n3\n4\n5\n6\n7\n8\n",
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    let mut project = project.clone();
+    project.rename_detection = Some(RenameDetection::default());
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    fs::remove_file(repository.path().join("file.txt")).unwrap();
+    fs::write(repository.path().join("moved.txt"), content).unwrap();
+
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    assert_eq!(branches.len(), 1);
+    let files = &branches[0].files;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].hunks[0].change_type, ChangeType::Renamed);
+    let hunk_ids: Vec<_> = files[0].hunks.iter().map(|hunk| hunk.id.clone()).collect();
+    let ownership = format!("moved.txt:{}", hunk_ids.join(",")).parse().unwrap();
+    (project, ownership)
+}
+
+#[test]
+fn should_unapply_pure_rename() {
+    let test = Test::default();
+    let (project, ownership) = move_file(&test, "1\n2\n3\n4\n5\n6\n7\n8\n");
+
+    test.controller
+        .unapply_ownership(&project, &ownership)
+        .unwrap_or_else(|err| panic!("{err:?}"));
+
+    let path = test.repository.path();
+    assert!(!path.join("moved.txt").exists());
+    assert_eq!(
+        fs::read_to_string(path.join("file.txt")).unwrap(),
+        "1\n2\n3\n4\n5\n6\n7\n8\n"
+    );
+    let (branches, _) = test.controller.list_virtual_branches(&project).unwrap();
+    assert!(branches[0].files.is_empty());
+}
+
+#[test]
+fn should_unapply_rename_with_changes() {
+    let test = Test::default();
+    let (project, ownership) = move_file(&test, "1\n2\n3\n4\n5\n6\n7\neight\n");
+
+    test.controller
+        .unapply_ownership(&project, &ownership)
+        .unwrap_or_else(|err| panic!("{err:?}"));
+
+    let path = test.repository.path();
+    assert!(!path.join("moved.txt").exists());
+    assert_eq!(
+        fs::read_to_string(path.join("file.txt")).unwrap(),
+        "1\n2\n3\n4\n5\n6\n7\n8\n"
+    );
+    let (branches, _) = test.controller.list_virtual_branches(&project).unwrap();
+    assert!(branches[0].files.is_empty());
+}
+
+#[test]
+fn should_reset_rename_with_changes() {
+    let test = Test::default();
+    let (project, _) = move_file(&test, "1\n2\n3\n4\n5\n6\n7\neight\n");
+
+    test.controller
+        .reset_files(&project, &vec!["moved.txt".to_owned()])
+        .unwrap_or_else(|err| panic!("{err:?}"));
+
+    let path = test.repository.path();
+    assert!(!path.join("moved.txt").exists());
+    assert_eq!(
+        fs::read_to_string(path.join("file.txt")).unwrap(),
+        "1\n2\n3\n4\n5\n6\n7\n8\n"
+    );
+}
@@ -1,5 +1,46 @@
+use gitbutler_branch_actions::BaseBranchUpdateAction;
+
 use super::*;
 
+/// The outcome of updating the base branch for each branch, computed without writing anything.
+fn preview(
+    controller: &VirtualBranchActions,
+    project: &Project,
+) -> Vec<(BaseBranchUpdateAction, Vec<PathBuf>)> {
+    let virtual_branches =
+        || fs::read_to_string(project.gb_dir().join("virtual_branches.toml")).unwrap();
+    let loose_objects = || {
+        fs::read_dir(project.path.join(".git/objects"))
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().len() == 2)
+            .map(|entry| fs::read_dir(entry.path()).unwrap().count())
+            .sum::<usize>()
+    };
+    let virtual_branches_before = virtual_branches();
+    let loose_objects_before = loose_objects();
+
+    let branches = controller
+        .preview_update_base_branch(project)
+        .unwrap()
+        .branches
+        .into_iter()
+        .map(|branch| (branch.action, branch.conflicting_paths))
+        .collect();
+
+    assert_eq!(
+        virtual_branches(),
+        virtual_branches_before,
+        "the preview doesn't persist the branches"
+    );
+    assert_eq!(
+        loose_objects(),
+        loose_objects_before,
+        "the preview doesn't write objects"
+    );
+    branches
+}
+
 mod applied_branch {
     use gitbutler_branch::BranchCreateRequest;
 
@@ -37,6 +78,19 @@ mod applied_branch {
             fs::write(repository.path().join("file.txt"), "conflict").unwrap();
         }
 
+        assert_eq!(
+            preview(controller, project),
+            [(
+                BaseBranchUpdateAction::UnapplyConflicted,
+                vec![PathBuf::from("file.txt")]
+            )]
+        );
+        assert_eq!(
+            std::fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+            "conflict",
+            "the preview doesn't change anything"
+        );
+
         let unapplied_branch = {
             // fetch remote
             let unapplied_branches = controller.update_base_branch(project).unwrap();
@@ -477,6 +531,11 @@ mod applied_branch {
                 )
                 .unwrap();
 
+            assert_eq!(
+                preview(controller, project),
+                [(BaseBranchUpdateAction::Merge, vec![])]
+            );
+
             {
                 // fetch remote
                 controller.update_base_branch(project).unwrap();
@@ -538,6 +597,11 @@ mod applied_branch {
             branch_id
         };
 
+        assert_eq!(
+            preview(controller, project),
+            [(BaseBranchUpdateAction::Rebase, vec![])]
+        );
+
         {
             // fetch remote
             controller.update_base_branch(project).unwrap();
@@ -913,6 +977,11 @@ mod applied_branch {
                 .unwrap();
         };
 
+        assert_eq!(
+            preview(controller, project),
+            [(BaseBranchUpdateAction::DeleteIntegrated, vec![])]
+        );
+
         {
             // fetch remote
             controller.update_base_branch(project).unwrap();
@@ -1,5 +1,7 @@
 use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
 use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_config::git::{GbConfig, GitConfig};
+use gitbutler_repo::message_policy::{PolicyViolation, PolicyViolations};
 
 use super::*;
 
@@ -334,3 +336,59 @@ fn empty() {
         "commit message can not be empty"
     );
 }
+
+#[test]
+fn message_policy() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    git2::Repository::open(repository.path())
+        .unwrap()
+        .set_gb_config(GbConfig {
+            commit_message_cleanup: Some(true),
+            commit_message_conventional: Some(true),
+            ..GbConfig::default()
+        })
+        .unwrap();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(repository.path().join("file one.txt"), "").unwrap();
+    let err = controller
+        .create_commit(project, branch_id, "commit one", None, false)
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<PolicyViolations>().unwrap().0,
+        vec![PolicyViolation::NotConventional {
+            subject: "commit one".into()
+        }]
+    );
+
+    let commit_one_oid = controller
+        .create_commit(project, branch_id, "feat: commit one  \n\n", None, false)
+        .unwrap();
+    assert_eq!(
+        repository.find_commit(commit_one_oid).unwrap().message(),
+        Some("feat: commit one")
+    );
+
+    let err = controller
+        .update_commit_message(project, branch_id, commit_one_oid, "commit one updated")
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<PolicyViolations>().unwrap().0,
+        vec![PolicyViolation::NotConventional {
+            subject: "commit one updated".into()
+        }]
+    );
+}
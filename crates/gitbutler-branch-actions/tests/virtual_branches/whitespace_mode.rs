@@ -0,0 +1,267 @@
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_project::WhitespaceMode;
+
+use super::*;
+
+#[test]
+fn whitespace_changes_dont_alter_hunks() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n",
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    controller
+        .set_base_branch(project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(project, &BranchCreateRequest::default())
+        .unwrap();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\n",
+    )
+    .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).unwrap();
+    let hunks = &branches[0].files[0].hunks;
+    assert_eq!(hunks.len(), 1);
+    let hash = hunks[0].hash;
+
+    // Re-indent a line far away from the change.
+    fs::write(
+        repository.path().join("file.txt"),
+        "a\nB\nc\nd\ne\nf\ng\nh\n    i\nj\n",
+    )
+    .unwrap();
+    let (branches, _) = controller.list_virtual_branches(project).unwrap();
+    assert_eq!(
+        branches[0].files[0].hunks.len(),
+        2,
+        "by default, whitespace changes are hunks of their own"
+    );
+
+    let mut project = project.clone();
+    project.whitespace_mode = Some(WhitespaceMode::IgnoreAll);
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    let hunks = &branches[0].files[0].hunks;
+    assert_eq!(hunks.len(), 1);
+    assert_eq!(hunks[0].hash, hash, "the hash is unaffected");
+
+    let commit_id = controller
+        .create_commit(&project, branch_id, "commit", None, false)
+        .unwrap();
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let tree = repo.find_commit(commit_id).unwrap().tree().unwrap();
+    let blob = tree
+        .get_path("file.txt".as_ref())
+        .unwrap()
+        .to_object(&repo)
+        .unwrap()
+        .peel_to_blob()
+        .unwrap();
+    assert_eq!(
+        blob.content(),
+        b"a\nB\nc\nd\ne\nf\ng\nh\ni\nj\n",
+        "ignored whitespace changes aren't committed"
+    );
+}
+
+#[test]
+fn whitespace_changes_next_to_changes_are_not_committed() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(
+        repository.path().join("file.txt"),
+        "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n",
+    )
+    .unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    let mut project = project.clone();
+    project.whitespace_mode = Some(WhitespaceMode::IgnoreAll);
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    let branch_id = controller
+        .create_virtual_branch(&project, &BranchCreateRequest::default())
+        .unwrap();
+
+    // Re-indent the lines right around the change, so they end up as its context.
+    fs::write(
+        repository.path().join("file.txt"),
+        "a\n    b\nC\n    d\ne\nf\ng\nh\ni\nj\n",
+    )
+    .unwrap();
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    let files = &branches[0].files;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].hunks.len(), 1);
+
+    let commit_id = controller
+        .create_commit(&project, branch_id, "commit", None, false)
+        .unwrap();
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let tree = repo.find_commit(commit_id).unwrap().tree().unwrap();
+    let blob = tree
+        .get_path("file.txt".as_ref())
+        .unwrap()
+        .to_object(&repo)
+        .unwrap()
+        .peel_to_blob()
+        .unwrap();
+    assert_eq!(
+        blob.content(),
+        b"a\nb\nC\nd\ne\nf\ng\nh\ni\nj\n",
+        "only the change is committed, without the whitespace changes in its context"
+    );
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "a\n    b\nC\n    d\ne\nf\ng\nh\ni\nj\n",
+        "the whitespace changes stay in the worktree"
+    );
+}
+
+#[test]
+fn files_with_only_whitespace_changes_stay_owned() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    fs::write(repository.path().join("file.txt"), "a\nb\nc\n").unwrap();
+    repository.commit_all("add file");
+    repository.push();
+
+    let mut project = project.clone();
+    project.whitespace_mode = Some(WhitespaceMode::IgnoreAll);
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+
+    fs::write(repository.path().join("file.txt"), "a\n    b\nc\n").unwrap();
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    assert_eq!(branches.len(), 1);
+    let files = &branches[0].files;
+    assert_eq!(files.len(), 1, "the file isn't lost to the branch");
+    assert_eq!(files[0].hunks.len(), 1);
+
+    let refname = controller
+        .convert_to_real_branch(&project, branches[0].id)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "a\nb\nc\n"
+    );
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let tree = repo
+        .find_reference(&refname)
+        .unwrap()
+        .peel_to_tree()
+        .unwrap();
+    let blob = tree
+        .get_path("file.txt".as_ref())
+        .unwrap()
+        .to_object(&repo)
+        .unwrap()
+        .peel_to_blob()
+        .unwrap();
+    assert_eq!(
+        blob.content(),
+        b"a\n    b\nc\n",
+        "the whitespace changes are saved with the branch"
+    );
+}
+
+#[test]
+fn whitespace_changes_next_to_changes_survive_updating_and_unapplying() {
+    let Test {
+        repository,
+        project,
+        controller,
+        ..
+    } = &Test::default();
+
+    // make sure we have an undiscovered commit in the remote branch
+    {
+        fs::write(
+            repository.path().join("file.txt"),
+            "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n",
+        )
+        .unwrap();
+        let first_commit_oid = repository.commit_all("add file");
+        fs::write(repository.path().join("other.txt"), "other").unwrap();
+        repository.commit_all("add other file");
+        repository.push();
+        repository.reset_hard(Some(first_commit_oid));
+    }
+
+    let mut project = project.clone();
+    project.whitespace_mode = Some(WhitespaceMode::IgnoreAll);
+    controller
+        .set_base_branch(&project, &"refs/remotes/origin/master".parse().unwrap())
+        .unwrap();
+    controller
+        .create_virtual_branch(&project, &BranchCreateRequest::default())
+        .unwrap();
+
+    let content = "a\n    b\nC\n    d\ne\nf\ng\nh\ni\nj\n";
+    fs::write(repository.path().join("file.txt"), content).unwrap();
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    assert_eq!(branches[0].files[0].hunks.len(), 1);
+
+    controller.update_base_branch(&project).unwrap();
+    assert!(repository.path().join("other.txt").exists());
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        content,
+        "the whitespace changes survive the update"
+    );
+
+    let (branches, _) = controller.list_virtual_branches(&project).unwrap();
+    assert_eq!(branches.len(), 1);
+    let refname = controller
+        .convert_to_real_branch(&project, branches[0].id)
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(repository.path().join("file.txt")).unwrap(),
+        "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n"
+    );
+
+    let repo = git2::Repository::open(repository.path()).unwrap();
+    let tree = repo
+        .find_reference(&refname)
+        .unwrap()
+        .peel_to_tree()
+        .unwrap();
+    let blob = tree
+        .get_path("file.txt".as_ref())
+        .unwrap()
+        .to_object(&repo)
+        .unwrap()
+        .peel_to_blob()
+        .unwrap();
+    assert_eq!(
+        blob.content(),
+        content.as_bytes(),
+        "the whitespace changes are saved with the branch"
+    );
+}
@@ -67,6 +67,8 @@ pub struct Branch {
     pub in_workspace: bool,
     #[serde(default)]
     pub not_in_workspace_wip_change_id: Option<String>,
+    /// The stack of series on this branch, each represented by the reference to its top-most change.
+    /// It's ordered from the top of the stack, closest to `head`, to the bottom of the stack, closest to the target.
     #[serde(default)]
     pub references: Vec<ChangeReference>,
 }
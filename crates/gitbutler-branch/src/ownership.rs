@@ -1,6 +1,7 @@
-use std::{collections::HashSet, fmt, str::FromStr};
+use std::{collections::HashSet, fmt, path::Path, str::FromStr};
 
 use anyhow::Result;
+use gitbutler_diff::Hunk;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize, Serializer};
 
@@ -89,6 +90,22 @@ impl BranchOwnershipClaims {
 
         taken
     }
+
+    /// If there is a claim on `from`, a file that was renamed to `to`, replace it with a claim on `to` for `hunks`,
+    /// the hunks of the renamed file, so that the file stays owned by this branch.
+    /// Returns `true` if a claim was moved.
+    pub fn follow_rename(&mut self, from: &Path, to: &Path, hunks: Vec<Hunk>) -> bool {
+        let len = self.claims.len();
+        self.claims.retain(|claim| claim.file_path != from);
+        if self.claims.len() == len {
+            return false;
+        }
+        self.put(OwnershipClaim {
+            file_path: to.to_owned(),
+            hunks,
+        });
+        true
+    }
 }
 
 #[derive(Debug, Clone)]
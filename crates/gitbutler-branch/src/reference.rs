@@ -17,4 +17,7 @@ pub struct ChangeReference {
     pub name: ReferenceName,
     /// The change id this reference points to.
     pub change_id: String,
+    /// The commit this reference was last pushed at, or `None` if it wasn't pushed yet.
+    #[serde(with = "gitbutler_serde::oid_opt", default)]
+    pub pushed_commit: Option<git2::Oid>,
 }
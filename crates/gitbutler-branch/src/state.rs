@@ -47,6 +47,11 @@ impl VirtualBranches {
                 .collect()
         })
     }
+
+    /// Returns the virtual branch with `id`, whether it's in the workspace or not.
+    pub fn branch(&self, id: BranchId) -> Option<&Branch> {
+        self.branches.get(&id)
+    }
 }
 
 /// A handle to the state of virtual branches.
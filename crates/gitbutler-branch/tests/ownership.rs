@@ -1,4 +1,7 @@
-use std::{path::PathBuf, vec};
+use std::{
+    path::{Path, PathBuf},
+    vec,
+};
 
 use gitbutler_branch::{reconcile_claims, Branch, BranchId, BranchOwnershipClaims, OwnershipClaim};
 use gitbutler_diff::Hunk;
@@ -257,6 +260,36 @@ fn take_1() {
     );
 }
 
+#[test]
+fn follow_rename() {
+    let mut ownership = "src/main.rs:100-200\nsrc/lib.rs:0-10"
+        .parse::<BranchOwnershipClaims>()
+        .unwrap();
+    let followed = ownership.follow_rename(
+        Path::new("src/main.rs"),
+        Path::new("src/bin.rs"),
+        vec![Hunk::new(0, 0, None).unwrap()],
+    );
+    assert!(followed);
+    assert_eq!(ownership.claims.len(), 2);
+    assert_eq!(
+        ownership.claims[0],
+        "src/bin.rs:0-0".parse::<OwnershipClaim>().unwrap()
+    );
+    assert_eq!(
+        ownership.claims[1],
+        "src/lib.rs:0-10".parse::<OwnershipClaim>().unwrap()
+    );
+
+    let followed = ownership.follow_rename(
+        Path::new("src/other.rs"),
+        Path::new("src/renamed.rs"),
+        vec![Hunk::new(0, 0, None).unwrap()],
+    );
+    assert!(!followed, "unclaimed files aren't followed");
+    assert_eq!(ownership.claims.len(), 2);
+}
+
 #[test]
 fn equal() {
     for (a, b, expected) in vec![
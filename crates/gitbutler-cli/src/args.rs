@@ -9,6 +9,9 @@ pub struct Args {
     /// Run as if gitbutler-cli was started in PATH instead of the current working directory.
     #[clap(short = 'C', long, default_value = ".", value_name = "PATH")]
     pub current_dir: PathBuf,
+    /// Print results as JSON instead of their debug representation, for consumption by other tools.
+    #[clap(short = 'j', long)]
+    pub json: bool,
 
     #[clap(subcommand)]
     pub cmd: Subcommands,
@@ -22,13 +25,15 @@ pub enum Subcommands {
     /// List and manipulate projects.
     #[clap(visible_alias = "projects")]
     Project(project::Platform),
-    /// List and restore snapshots.
+    /// List, inspect and restore snapshots.
     #[clap(visible_alias = "snapshots")]
     Snapshot(snapshot::Platform),
 }
 
 pub mod vbranch {
-    use gitbutler_branch::BranchIdentity;
+    use std::path::PathBuf;
+
+    use gitbutler_branch::{BranchIdentity, OwnershipClaim};
 
     #[derive(Debug, clap::Parser)]
     pub struct Platform {
@@ -60,6 +65,156 @@ pub mod vbranch {
             /// The name of the virtual to commit all staged and unstaged changes to.
             name: String,
         },
+        /// Commit only some of the added or removed lines of a hunk assigned to the named virtual branch.
+        CommitLines {
+            /// The commit message
+            #[clap(short = 'm', long)]
+            message: String,
+            /// The name of the virtual branch the hunk is assigned to.
+            name: String,
+            /// The path of the file containing the hunk, relative to the worktree root.
+            path: PathBuf,
+            /// The id of the hunk, like `4-12`, as shown by `hunks`.
+            hunk: String,
+            /// The indices of the lines to commit within the hunk's diff, with its `@@` header being line 0.
+            #[clap(required = true, value_delimiter = ',')]
+            lines: Vec<u32>,
+        },
+        /// Amend all changes currently assigned to the named virtual branch into one of its commits.
+        Amend {
+            /// The short hash or change-id of the commit to amend.
+            #[clap(short = 'c', long)]
+            commit: String,
+            /// The name of the virtual branch whose changes to amend.
+            name: String,
+        },
+        /// Commit all changes currently assigned to the named virtual branch as a fixup for one of its commits.
+        Fixup {
+            /// The short hash or change-id of the commit to fix up.
+            #[clap(short = 'c', long)]
+            commit: String,
+            /// The name of the virtual branch whose changes to commit.
+            name: String,
+        },
+        /// Fold the `fixup!` and `squash!` commits of the named virtual branch into the commits they target.
+        Autosquash {
+            /// The name of the virtual branch to autosquash.
+            name: String,
+        },
+        /// Amend the changes assigned to the named virtual branch into the commits that last changed their lines.
+        Absorb {
+            /// The name of the virtual branch whose changes to absorb.
+            name: String,
+        },
+        /// Squash a commit of the named virtual branch into its parent.
+        Squash {
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to squash into its parent.
+            commit: String,
+        },
+        /// Move a commit of the named virtual branch up or down within the branch.
+        Reorder {
+            /// Move the commit down by this many commits if positive, or up if negative.
+            #[clap(short = 'o', long, allow_negative_numbers = true)]
+            offset: i32,
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to move.
+            commit: String,
+        },
+        /// Apply a commit from any branch on top of the named virtual branch as a new commit.
+        CherryPick {
+            /// The name of the virtual branch to cherry-pick onto.
+            name: String,
+            /// The revision of the commit to cherry-pick, like a hash or `origin/feature~2`.
+            commit: String,
+        },
+        /// Apply the inverse of a commit from any branch to the named virtual branch.
+        Revert {
+            /// Record the revert as a new commit instead of leaving it as uncommitted changes.
+            #[clap(long)]
+            commit: bool,
+            /// The name of the virtual branch to revert the commit in.
+            name: String,
+            /// The revision of the commit to revert, like a hash or `origin/master~2`.
+            rev: String,
+        },
+        /// Remove a commit from the named virtual branch, keeping its changes in the worktree.
+        Undo {
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to undo.
+            commit: String,
+        },
+        /// Move the top-most commit of a virtual branch onto the named virtual branch.
+        MoveCommit {
+            /// The short hash or change-id of the commit to move, which must be the head of its branch.
+            commit: String,
+            /// The name of the virtual branch to move the commit to.
+            #[clap(short = 't', long)]
+            to: String,
+        },
+        /// Move the changes to the given files from one commit of the named virtual branch to another.
+        MoveFiles {
+            /// The short hash or change-id of the commit to take the file changes from.
+            #[clap(short = 'f', long)]
+            from: String,
+            /// The short hash or change-id of the commit to move the file changes to.
+            #[clap(short = 't', long)]
+            to: String,
+            /// The name of the virtual branch containing both commits.
+            name: String,
+            /// The paths of the files whose changes should be moved, relative to the worktree root.
+            #[clap(required = true)]
+            paths: Vec<PathBuf>,
+        },
+        /// Insert an empty commit next to a commit of the named virtual branch.
+        InsertBlankCommit {
+            /// Insert below the commit if positive, or above it if negative.
+            #[clap(short = 'o', long, allow_negative_numbers = true, default_value_t = -1)]
+            offset: i32,
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to insert the blank commit next to.
+            commit: String,
+        },
+        /// Split a commit of the named virtual branch into one commit per group of its hunks.
+        SplitCommit {
+            /// The hunks of one of the new commits, in order, as `path:start-end[,start-end]`,
+            /// with the claims of multiple files separated by `;`. Each hunk must be part of exactly one group.
+            #[clap(short = 'g', long = "group", required = true, num_args = 1)]
+            groups: Vec<String>,
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to split.
+            commit: String,
+        },
+        /// Change the message of a commit of the named virtual branch.
+        Reword {
+            /// The new commit message.
+            #[clap(short = 'm', long)]
+            message: String,
+            /// The name of the virtual branch containing the commit.
+            name: String,
+            /// The short hash or change-id of the commit to reword.
+            commit: String,
+        },
+        /// List all uncommitted hunks along with the virtual branch they are assigned to.
+        Hunks,
+        /// Assign uncommitted hunks to the named virtual branch.
+        Assign {
+            /// Interactively choose which of the selected hunks to assign, similar to `git add -p`.
+            #[clap(short = 'p', long)]
+            patch: bool,
+            /// The name of the virtual branch to assign the hunks to.
+            name: String,
+            /// The hunks to assign as `path:start-end[,start-end…]`, with `start-end` being a hunk id as shown by `hunks`.
+            ///
+            /// If unset in `--patch` mode, all hunks not yet assigned to the branch are offered.
+            #[clap(required_unless_present = "patch")]
+            hunks: Vec<OwnershipClaim>,
+        },
         /// Create a new virtual branch
         Create {
             /// Also make this branch the default branch, so it is considered the owner of new edits.
@@ -76,7 +231,19 @@ pub mod vbranch {
         /// List all branches that can be relevant.
         ListAll,
         /// After fetching the target (i.e. local tracking branch), recompute our workspace against it.
-        UpdateTarget,
+        UpdateTarget {
+            /// Only show what would happen to each virtual branch, without changing anything.
+            #[clap(short = 'p', long)]
+            preview: bool,
+        },
+        /// Integrate the new commits of the upstream of the named virtual branch into it.
+        IntegrateUpstream {
+            /// Only show the outcome of rebasing and merging, without changing anything.
+            #[clap(short = 'p', long)]
+            preview: bool,
+            /// The name of the virtual branch whose upstream commits to integrate.
+            name: String,
+        },
     }
 }
 
@@ -122,6 +289,10 @@ pub mod project {
 }
 
 pub mod snapshot {
+    use std::path::PathBuf;
+
+    use gitbutler_oplog::entry::{OperationKind, Trailer};
+
     #[derive(Debug, clap::Parser)]
     pub struct Platform {
         #[clap(subcommand)]
@@ -130,6 +301,40 @@ pub mod snapshot {
 
     #[derive(Debug, clap::Subcommand)]
     pub enum SubCommands {
+        /// List snapshots, most recent first.
+        List {
+            /// The maximum amount of snapshots to list.
+            #[clap(short = 'l', long, default_value_t = 100)]
+            limit: usize,
+            /// The amount of (matching) snapshots to skip before listing, most recent first.
+            #[clap(short = 'o', long, default_value_t = 0)]
+            offset: usize,
+            /// Only list snapshots of the given operation kind, like `CreateCommit`. Can be repeated.
+            #[clap(short = 'k', long = "kind")]
+            kinds: Vec<OperationKind>,
+            /// Only list snapshots with the given trailer, like `name: my-branch`. Can be repeated.
+            #[clap(short = 't', long = "trailer")]
+            trailers: Vec<Trailer>,
+            /// Only list snapshots created at or after the given time, in seconds since the Unix epoch.
+            #[clap(long)]
+            since: Option<i64>,
+            /// Only list snapshots created at or before the given time, in seconds since the Unix epoch.
+            #[clap(long)]
+            until: Option<i64>,
+            /// Only list snapshots that changed the given worktree-relative file or directory.
+            #[clap(short = 'p', long)]
+            path: Option<PathBuf>,
+        },
+        /// Show the working directory changes recorded by a snapshot, compared to its predecessor.
+        Diff {
+            /// The snapshot to show the changes of
+            snapshot_id: String,
+        },
+        /// Show the title, body, operation and trailers of a snapshot.
+        Show {
+            /// The snapshot to show
+            snapshot_id: String,
+        },
         /// Restores the state of the working direcory as well as virtual branches to a given snapshot.
         Restore {
             /// The snapshot to restore
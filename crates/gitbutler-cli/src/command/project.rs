@@ -5,10 +5,14 @@ use gitbutler_branch_actions::VirtualBranchActions;
 use gitbutler_project::Project;
 use gitbutler_reference::RemoteRefname;
 
-use crate::command::debug_print;
+use crate::command::print;
 
-pub fn list(ctrl: gitbutler_project::Controller) -> Result<()> {
-    for project in ctrl.list()? {
+pub fn list(ctrl: gitbutler_project::Controller, json: bool) -> Result<()> {
+    let projects = ctrl.list()?;
+    if json {
+        return print(&projects, json);
+    }
+    for project in projects {
         println!(
             "{id} {name} {path}",
             id = project.id,
@@ -23,6 +27,7 @@ pub fn add(
     ctrl: gitbutler_project::Controller,
     path: PathBuf,
     refname: Option<RemoteRefname>,
+    json: bool,
 ) -> Result<()> {
     let path = gix::discover(path)?
         .work_dir()
@@ -33,9 +38,12 @@ pub fn add(
     if let Some(refname) = refname {
         VirtualBranchActions.set_base_branch(&project, &refname)?;
     };
-    debug_print(project)
+    print(&project, json)
 }
 
-pub fn switch_to_workspace(project: Project, refname: RemoteRefname) -> Result<()> {
-    debug_print(VirtualBranchActions.set_base_branch(&project, &refname)?)
+pub fn switch_to_workspace(project: Project, refname: RemoteRefname, json: bool) -> Result<()> {
+    print(
+        &VirtualBranchActions.set_base_branch(&project, &refname)?,
+        json,
+    )
 }
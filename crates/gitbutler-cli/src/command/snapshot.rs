@@ -0,0 +1,94 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::{Context, Result};
+use gitbutler_oplog::{
+    entry::{SnapshotDetails, SnapshotQuery},
+    OplogExt,
+};
+use gitbutler_project::Project;
+
+use crate::command::print;
+
+pub fn list(
+    project: Project,
+    limit: usize,
+    offset: usize,
+    query: &SnapshotQuery,
+    json: bool,
+) -> Result<()> {
+    let snapshots: Vec<_> = project
+        .query_snapshots(query, offset.saturating_add(limit), None)?
+        .into_iter()
+        .skip(offset)
+        .collect();
+    if json {
+        return print(&snapshots, json);
+    }
+    for snapshot in snapshots {
+        let ts = chrono::DateTime::from_timestamp(snapshot.created_at.seconds(), 0)
+            .map(|ts| ts.to_string())
+            .unwrap_or_else(|| snapshot.created_at.seconds().to_string());
+        // Snapshots with details we can't parse are still snapshots, and restorable.
+        let operation = snapshot
+            .details
+            .map(|details| details.operation.to_string())
+            .unwrap_or_else(|| "<unparsable details>".into());
+        println!("{} {} {}", ts, snapshot.commit_id, operation);
+    }
+    Ok(())
+}
+
+pub fn diff(project: Project, snapshot_id: String, json: bool) -> Result<()> {
+    let diff: BTreeMap<_, _> = project
+        .snapshot_diff(snapshot_id.parse()?)?
+        .into_iter()
+        .collect();
+    if json {
+        return print(&diff, json);
+    }
+    for (path, file) in diff {
+        let old_path = file.old_path.as_ref().unwrap_or(&path);
+        println!("--- {}", old_path.display());
+        println!("+++ {}", path.display());
+        if file.skipped {
+            println!("(skipped large file)");
+            continue;
+        }
+        if file.binary {
+            println!("(binary file)");
+            continue;
+        }
+        // The diff of each hunk starts with its header.
+        for hunk in file.hunks {
+            print!("{}", *hunk.diff_lines);
+        }
+    }
+    Ok(())
+}
+
+pub fn show(project: Project, snapshot_id: String, json: bool) -> Result<()> {
+    let repo = git2::Repository::open(&project.path)?;
+    let commit = repo.find_commit(snapshot_id.parse()?)?;
+    let details = commit
+        .message()
+        .context("Snapshot message isn't valid UTF-8")
+        .and_then(SnapshotDetails::from_str)
+        .with_context(|| format!("Could not parse the details of snapshot {}", commit.id()))?;
+    if json {
+        return print(&details, json);
+    }
+    let ts = chrono::DateTime::from_timestamp(commit.time().seconds(), 0);
+    println!("snapshot {}", commit.id());
+    if let Some(ts) = ts {
+        println!("Date: {ts}");
+    }
+    println!();
+    print!("{details}");
+    Ok(())
+}
+
+pub fn restore(project: Project, snapshot_id: String) -> Result<()> {
+    let _guard = project.try_exclusive_access()?;
+    project.restore_snapshot(snapshot_id.parse()?)?;
+    Ok(())
+}
@@ -1,34 +1,70 @@
-use anyhow::{bail, Result};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
 use gitbutler_branch::{
-    Branch, BranchCreateRequest, BranchIdentity, BranchUpdateRequest, VirtualBranchesHandle,
+    Branch, BranchCreateRequest, BranchIdentity, BranchOwnershipClaims, BranchUpdateRequest,
+    OwnershipClaim, VirtualBranchesHandle,
+};
+use gitbutler_branch_actions::{
+    get_branch_listing_details, list_branches, CommitSplit, LineSelection, RevertDestination,
+    VirtualBranchActions, VirtualBranchHunk, VirtualBranches,
 };
-use gitbutler_branch_actions::{get_branch_listing_details, list_branches, VirtualBranchActions};
 use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_diff::Hunk;
 use gitbutler_project::Project;
+use gitbutler_repo::{LogUntil, RepoActionsExt};
+use serde::Serialize;
 
-use crate::command::debug_print;
+use crate::command::print;
 
-pub fn update_target(project: Project) -> Result<()> {
+pub fn update_target(project: Project, preview: bool, json: bool) -> Result<()> {
+    if preview {
+        return print(
+            &VirtualBranchActions.preview_update_base_branch(&project)?,
+            json,
+        );
+    }
     let unapplied = VirtualBranchActions.update_base_branch(&project)?;
-    debug_print(unapplied)
+    print(&unapplied, json)
+}
+
+pub fn integrate_upstream(
+    project: Project,
+    branch_name: String,
+    preview: bool,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    if preview {
+        return print(
+            &VirtualBranchActions.preview_integrate_upstream_commits(&project, branch.id)?,
+            json,
+        );
+    }
+    VirtualBranchActions.integrate_upstream_commits(&project, branch.id)
 }
 
-pub fn list_all(project: Project) -> Result<()> {
+pub fn list_all(project: Project, json: bool) -> Result<()> {
     let ctx = CommandContext::open(&project)?;
-    debug_print(list_branches(&ctx, None, None)?)
+    print(&list_branches(&ctx, None, None)?, json)
 }
 
-pub fn list_local(project: Project) -> Result<()> {
-    debug_print(VirtualBranchActions::list_local_branches(project)?)
+pub fn list_local(project: Project, json: bool) -> Result<()> {
+    print(&VirtualBranchActions::list_local_branches(project)?, json)
 }
 
-pub fn details(project: Project, branch_names: Vec<BranchIdentity>) -> Result<()> {
+pub fn details(project: Project, branch_names: Vec<BranchIdentity>, json: bool) -> Result<()> {
     let ctx = CommandContext::open(&project)?;
-    debug_print(get_branch_listing_details(&ctx, branch_names)?)
+    print(&get_branch_listing_details(&ctx, branch_names)?, json)
 }
 
-pub fn list(project: Project) -> Result<()> {
+pub fn list(project: Project, json: bool) -> Result<()> {
     let branches = VirtualBranchesHandle::new(project.gb_dir()).list_all_branches()?;
+    if json {
+        return print(&branches, json);
+    }
     for vbranch in branches {
         println!(
             "{active} {id} {name} {upstream} {default}",
@@ -44,16 +80,26 @@ pub fn list(project: Project) -> Result<()> {
     Ok(())
 }
 
-pub fn status(project: Project) -> Result<()> {
-    debug_print(VirtualBranchActions.list_virtual_branches(&project)?)
+pub fn status(project: Project, json: bool) -> Result<()> {
+    let (branches, skipped_files) = VirtualBranchActions.list_virtual_branches(&project)?;
+    print(
+        &VirtualBranches {
+            branches,
+            skipped_files,
+        },
+        json,
+    )
 }
 
-pub fn unapply(project: Project, branch_name: String) -> Result<()> {
+pub fn unapply(project: Project, branch_name: String, json: bool) -> Result<()> {
     let branch = branch_by_name(&project, &branch_name)?;
-    debug_print(VirtualBranchActions.convert_to_real_branch(&project, branch.id)?)
+    print(
+        &VirtualBranchActions.convert_to_real_branch(&project, branch.id)?,
+        json,
+    )
 }
 
-pub fn create(project: Project, branch_name: String, set_default: bool) -> Result<()> {
+pub fn create(project: Project, branch_name: String, set_default: bool, json: bool) -> Result<()> {
     let new = VirtualBranchActions.create_virtual_branch(
         &project,
         &BranchCreateRequest {
@@ -65,12 +111,12 @@ pub fn create(project: Project, branch_name: String, set_default: bool) -> Resul
         let new = VirtualBranchesHandle::new(project.gb_dir()).get_branch(new)?;
         set_default_branch(&project, &new)?;
     }
-    debug_print(new)
+    print(&new, json)
 }
 
-pub fn set_default(project: Project, branch_name: String) -> Result<()> {
+pub fn set_default(project: Project, branch_name: String, json: bool) -> Result<()> {
     let branch = branch_by_name(&project, &branch_name)?;
-    set_default_branch(&project, &branch)
+    print(&set_default_branch(&project, &branch)?, json)
 }
 
 fn set_default_branch(project: &Project, branch: &Branch) -> Result<()> {
@@ -89,9 +135,488 @@ fn set_default_branch(project: &Project, branch: &Branch) -> Result<()> {
     )
 }
 
-pub fn commit(project: Project, branch_name: String, message: String) -> Result<()> {
+pub fn commit(project: Project, branch_name: String, message: String, json: bool) -> Result<()> {
     let branch = branch_by_name(&project, &branch_name)?;
-    let (info, skipped) = VirtualBranchActions.list_virtual_branches(&project)?;
+    let ownership = assigned_changes(&project, &branch)?;
+
+    let run_hooks = false;
+    let commit_id = VirtualBranchActions.create_commit(
+        &project,
+        branch.id,
+        &message,
+        Some(&ownership),
+        run_hooks,
+    )?;
+    print(&commit_id.to_string(), json)
+}
+
+pub fn commit_lines(
+    project: Project,
+    branch_name: String,
+    message: String,
+    selection: LineSelection,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let run_hooks = false;
+    let commit_id =
+        VirtualBranchActions.commit_lines(&project, branch.id, &message, &selection, run_hooks)?;
+    print(&commit_id.to_string(), json)
+}
+
+pub fn amend(project: Project, branch_name: String, rev: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    let ownership = assigned_changes(&project, &branch)?;
+    let new_commit_id = VirtualBranchActions.amend(&project, branch.id, commit_id, &ownership)?;
+    print(&new_commit_id.to_string(), json)
+}
+
+pub fn fixup(project: Project, branch_name: String, rev: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    let change_id = CommandContext::open(&project)?
+        .repository()
+        .find_commit(commit_id)?
+        .change_id()
+        .with_context(|| format!("commit {commit_id} has no change-id"))?;
+    let ownership = assigned_changes(&project, &branch)?;
+
+    let run_hooks = false;
+    let commit_id = VirtualBranchActions.create_fixup_commit(
+        &project,
+        branch.id,
+        &change_id,
+        Some(&ownership),
+        run_hooks,
+    )?;
+    print(&commit_id.to_string(), json)
+}
+
+pub fn autosquash(project: Project, branch_name: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    print(&VirtualBranchActions.autosquash(&project, branch.id)?, json)
+}
+
+pub fn absorb(project: Project, branch_name: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    print(&VirtualBranchActions.absorb(&project, branch.id)?, json)
+}
+
+pub fn squash(project: Project, branch_name: String, rev: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    print(
+        &VirtualBranchActions.squash(&project, branch.id, commit_id)?,
+        json,
+    )
+}
+
+pub fn reorder(
+    project: Project,
+    branch_name: String,
+    rev: String,
+    offset: i32,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    print(
+        &VirtualBranchActions.reorder_commit(&project, branch.id, commit_id, offset)?,
+        json,
+    )
+}
+
+pub fn undo(project: Project, branch_name: String, rev: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    print(
+        &VirtualBranchActions.undo_commit(&project, branch.id, commit_id)?,
+        json,
+    )
+}
+
+pub fn cherry_pick(project: Project, branch_name: String, rev: String, json: bool) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_revspec(&project, &rev)?;
+    let commit_id = VirtualBranchActions.cherry_pick(&project, branch.id, commit_id)?;
+    print(&commit_id.to_string(), json)
+}
+
+pub fn revert(
+    project: Project,
+    branch_name: String,
+    rev: String,
+    as_commit: bool,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_revspec(&project, &rev)?;
+    let destination = if as_commit {
+        RevertDestination::Commit
+    } else {
+        RevertDestination::Worktree
+    };
+    let commit_id = VirtualBranchActions.revert(&project, branch.id, commit_id, destination)?;
+    print(&commit_id.map(|id| id.to_string()), json)
+}
+
+pub fn move_commit(
+    project: Project,
+    rev: String,
+    target_branch_name: String,
+    json: bool,
+) -> Result<()> {
+    let target_branch = branch_by_name(&project, &target_branch_name)?;
+    let mut found = Vec::new();
+    for branch in VirtualBranchesHandle::new(project.gb_dir()).list_branches_in_workspace()? {
+        if let Ok(commit_id) = commit_by_rev(&project, &branch, &rev) {
+            found.push((branch, commit_id));
+        }
+    }
+    let (source_branch, commit_id) = match found.len() {
+        0 => bail!("No commit '{rev}' in any applied virtual branch"),
+        1 => found.pop().expect("present"),
+        _ => bail!("Commit '{rev}' is ambiguous as it was found in more than one virtual branch"),
+    };
+    if source_branch.head != commit_id {
+        bail!(
+            "Commit '{rev}' must be the top-most commit of branch '{name}' to be moved",
+            name = source_branch.name
+        );
+    }
+    print(
+        &VirtualBranchActions.move_commit(&project, target_branch.id, commit_id)?,
+        json,
+    )
+}
+
+pub fn move_files(
+    project: Project,
+    branch_name: String,
+    from_rev: String,
+    to_rev: String,
+    paths: Vec<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let from_commit_id = commit_by_rev(&project, &branch, &from_rev)?;
+    let to_commit_id = commit_by_rev(&project, &branch, &to_rev)?;
+
+    let ctx = CommandContext::open(&project)?;
+    let repo = ctx.repository();
+    let from_commit = repo.find_commit(from_commit_id)?;
+    let from_parent = from_commit.parent(0).context("failed to find parent")?;
+    let diff = gitbutler_diff::trees(
+        repo,
+        &from_parent.tree()?,
+        &from_commit.tree()?,
+        Default::default(),
+    )?;
+    let claims = paths
+        .into_iter()
+        .map(|path| {
+            let file = diff.get(&path).with_context(|| {
+                format!("'{}' isn't changed in commit '{from_rev}'", path.display())
+            })?;
+            Ok(OwnershipClaim {
+                hunks: file.hunks.iter().map(Into::into).collect(),
+                file_path: path,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let new_commit_id = VirtualBranchActions.move_commit_file(
+        &project,
+        branch.id,
+        from_commit_id,
+        to_commit_id,
+        &BranchOwnershipClaims { claims },
+    )?;
+    print(&new_commit_id.to_string(), json)
+}
+
+pub fn insert_blank_commit(
+    project: Project,
+    branch_name: String,
+    rev: String,
+    offset: i32,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    print(
+        &VirtualBranchActions.insert_blank_commit(&project, branch.id, commit_id, offset)?,
+        json,
+    )
+}
+
+pub fn split_commit(
+    project: Project,
+    branch_name: String,
+    rev: String,
+    groups: Vec<String>,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    let splits = groups
+        .iter()
+        .map(|group| {
+            Ok(CommitSplit {
+                ownership: group.replace(';', "\n").parse()?,
+                message: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let new_commit_ids =
+        VirtualBranchActions.split_commit(&project, branch.id, commit_id, &splits)?;
+    print(
+        &new_commit_ids
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        json,
+    )
+}
+
+pub fn reword(
+    project: Project,
+    branch_name: String,
+    rev: String,
+    message: String,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let commit_id = commit_by_rev(&project, &branch, &rev)?;
+    print(
+        &VirtualBranchActions.update_commit_message(&project, branch.id, commit_id, &message)?,
+        json,
+    )
+}
+
+pub fn hunks(project: Project, json: bool) -> Result<()> {
+    let (branches, _) = VirtualBranchActions.list_virtual_branches(&project)?;
+    let assignments: Vec<_> = branches
+        .iter()
+        .flat_map(|branch| {
+            branch.files.iter().flat_map(move |file| {
+                file.hunks.iter().map(move |hunk| HunkAssignment {
+                    branch_name: &branch.name,
+                    hunk,
+                })
+            })
+        })
+        .collect();
+    if json {
+        return print(&assignments, json);
+    }
+    for HunkAssignment { branch_name, hunk } in assignments {
+        println!(
+            "{path}:{id} {branch_name}{locks}{poisoned}",
+            path = hunk.file_path.display(),
+            id = hunk.id,
+            locks = hunk
+                .locked_to
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|lock| format!(
+                    " 🔒{branch}@{commit:.7}",
+                    branch = branches
+                        .iter()
+                        .find(|b| b.id == lock.branch_id)
+                        .map_or("<unknown>", |b| b.name.as_str()),
+                    commit = lock.commit_id.to_string(),
+                ))
+                .collect::<String>(),
+            poisoned = if hunk.poisoned { " ☠️" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+pub fn assign(
+    project: Project,
+    branch_name: String,
+    selection: Vec<OwnershipClaim>,
+    patch: bool,
+    json: bool,
+) -> Result<()> {
+    let branch = branch_by_name(&project, &branch_name)?;
+    let (branches, _) = VirtualBranchActions.list_virtual_branches(&project)?;
+    let target = branches
+        .iter()
+        .find(|b| b.id == branch.id)
+        .with_context(|| format!("Branch '{branch_name}' must be applied to assign hunks to it"))?;
+
+    let candidates: Vec<_> = branches
+        .iter()
+        .filter(|b| b.id != branch.id)
+        .flat_map(|b| b.files.iter().flat_map(|file| &file.hunks))
+        .filter(|hunk| selection.is_empty() || selection.iter().any(|claim| selects(claim, hunk)))
+        .collect();
+
+    for claim in &selection {
+        for range in &claim.hunks {
+            let is_known = branches
+                .iter()
+                .flat_map(|b| b.files.iter().flat_map(|file| &file.hunks))
+                .any(|hunk| {
+                    hunk.file_path == claim.file_path
+                        && hunk.start == range.start
+                        && hunk.end == range.end
+                });
+            if !is_known {
+                bail!(
+                    "No uncommitted hunk '{path}:{start}-{end}'",
+                    path = claim.file_path.display(),
+                    start = range.start,
+                    end = range.end
+                );
+            }
+        }
+    }
+
+    let selected = if patch {
+        select_hunks_interactively(candidates, &branch_name)?
+    } else {
+        candidates
+    };
+    if selected.is_empty() {
+        bail!("No hunks to assign to branch '{branch_name}'");
+    }
+
+    let mut ownership = target.ownership.clone();
+    for hunk in selected {
+        let locked_to = hunk.locked_to.as_deref().unwrap_or_default();
+        if locked_to
+            .iter()
+            .any(|lock| lock.branch_id != locked_to[0].branch_id)
+        {
+            bail!(
+                "Hunk '{path}:{id}' depends on more than one branch and can't be moved",
+                path = hunk.file_path.display(),
+                id = hunk.id
+            );
+        }
+        if hunk
+            .locked_to
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .any(|lock| lock.branch_id != branch.id)
+        {
+            bail!(
+                "Hunk '{path}:{id}' is locked to a commit in another branch and can't be moved",
+                path = hunk.file_path.display(),
+                id = hunk.id
+            );
+        }
+        if let Some(dependency) = hunk
+            .depends_on
+            .iter()
+            .find(|dependency| dependency.branch_id != branch.id)
+        {
+            bail!(
+                "Hunk '{path}:{id}' depends on hunk '{path}:{other_id}' in another branch and can't be moved without it",
+                path = hunk.file_path.display(),
+                id = hunk.id,
+                other_id = dependency.hunk_id
+            );
+        }
+        ownership.put(OwnershipClaim {
+            file_path: hunk.file_path.clone(),
+            hunks: vec![Hunk::new(hunk.start, hunk.end, Some(hunk.hash))?],
+        });
+    }
+
+    VirtualBranchActions.update_virtual_branch(
+        &project,
+        BranchUpdateRequest {
+            id: branch.id,
+            ownership: Some(ownership.clone()),
+            ..Default::default()
+        },
+    )?;
+    print(&ownership, json)
+}
+
+/// A hunk along with the name of the virtual branch it is assigned to.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HunkAssignment<'a> {
+    branch_name: &'a str,
+    hunk: &'a VirtualBranchHunk,
+}
+
+/// Return `true` if `hunk` is one of the hunks in `claim`, matched by its id.
+fn selects(claim: &OwnershipClaim, hunk: &VirtualBranchHunk) -> bool {
+    claim.file_path == hunk.file_path
+        && claim
+            .hunks
+            .iter()
+            .any(|range| range.start == hunk.start && range.end == hunk.end)
+}
+
+/// Show each of `hunks` and ask on standard input whether it should be assigned to `branch_name`,
+/// returning the hunks that were chosen.
+fn select_hunks_interactively<'a>(
+    hunks: Vec<&'a VirtualBranchHunk>,
+    branch_name: &str,
+) -> Result<Vec<&'a VirtualBranchHunk>> {
+    let mut selected = Vec::new();
+    let mut skip_file = None;
+    let mut take_file = None;
+    let mut stdin = std::io::stdin().lock();
+    for hunk in hunks {
+        if skip_file.as_ref() == Some(&hunk.file_path) {
+            continue;
+        }
+        if take_file.as_ref() == Some(&hunk.file_path) {
+            selected.push(hunk);
+            continue;
+        }
+        println!("{}:{}", hunk.file_path.display(), hunk.id);
+        println!("{}", *hunk.diff);
+        loop {
+            print!("Assign this hunk to '{branch_name}' [y,n,q,a,d,?]? ");
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            if stdin.read_line(&mut answer)? == 0 {
+                return Ok(selected);
+            }
+            match answer.trim() {
+                "y" => selected.push(hunk),
+                "n" => {}
+                "q" => return Ok(selected),
+                "a" => {
+                    selected.push(hunk);
+                    take_file = Some(hunk.file_path.clone());
+                }
+                "d" => skip_file = Some(hunk.file_path.clone()),
+                _ => {
+                    println!(
+                        "y - assign this hunk\n\
+                         n - do not assign this hunk\n\
+                         q - quit; do not assign this hunk or any of the remaining ones\n\
+                         a - assign this hunk and all later hunks in the file\n\
+                         d - do not assign this hunk or any of the later hunks in the file\n\
+                         ? - print help"
+                    );
+                    continue;
+                }
+            }
+            break;
+        }
+    }
+    Ok(selected)
+}
+
+/// Return the ownership of all uncommitted changes that are currently assigned to `branch`,
+/// or fail if there are none.
+fn assigned_changes(project: &Project, branch: &Branch) -> Result<BranchOwnershipClaims> {
+    let (info, skipped) = VirtualBranchActions.list_virtual_branches(project)?;
 
     if !skipped.is_empty() {
         eprintln!(
@@ -107,6 +632,7 @@ pub fn commit(project: Project, branch_name: String, message: String) -> Result<
     if populated_branch.ownership.claims.is_empty() {
         bail!(
             "Branch '{branch_name}' has no change to commit{hint}",
+            branch_name = branch.name,
             hint = {
                 let candidate_names = info
                     .iter()
@@ -127,15 +653,50 @@ pub fn commit(project: Project, branch_name: String, message: String) -> Result<
             }
         )
     }
+    Ok(populated_branch.ownership.clone())
+}
 
-    let run_hooks = false;
-    debug_print(VirtualBranchActions.create_commit(
-        &project,
-        branch.id,
-        &message,
-        Some(&populated_branch.ownership),
-        run_hooks,
-    )?)
+/// Find the commit `rev` points to, which can be any revision git understands.
+fn commit_by_revspec(project: &Project, rev: &str) -> Result<git2::Oid> {
+    Ok(CommandContext::open(project)?
+        .repository()
+        .revparse_single(rev)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("Couldn't find commit '{rev}'"))?
+        .id())
+}
+
+/// Find the commit identified by `rev` among the commits of `branch` that aren't yet in the target.
+/// `rev` is either a prefix of the commit hash, or a prefix of its change-id.
+pub fn commit_by_rev(project: &Project, branch: &Branch, rev: &str) -> Result<git2::Oid> {
+    if rev.is_empty() {
+        bail!("A commit must be specified by its hash or change-id");
+    }
+    let ctx = CommandContext::open(project)?;
+    let target = VirtualBranchesHandle::new(project.gb_dir()).get_default_target()?;
+    let mut found: Vec<_> = ctx
+        .log(branch.head, LogUntil::Commit(target.sha))?
+        .into_iter()
+        .filter(|commit| {
+            commit.id().to_string().starts_with(rev)
+                || commit
+                    .change_id()
+                    .map_or(false, |change_id| change_id.starts_with(rev))
+        })
+        .map(|commit| commit.id())
+        .collect();
+    if found.is_empty() {
+        bail!(
+            "No commit '{rev}' in virtual branch '{name}'",
+            name = branch.name
+        );
+    } else if found.len() > 1 {
+        bail!(
+            "Found more than one commit matching '{rev}' in virtual branch '{name}'",
+            name = branch.name
+        );
+    }
+    Ok(found.pop().expect("present"))
 }
 
 pub fn branch_by_name(project: &Project, name: &str) -> Result<Branch> {
@@ -1,4 +1,6 @@
 use anyhow::Result;
+use gitbutler_branch_actions::LineSelection;
+use gitbutler_oplog::entry::SnapshotQuery;
 
 mod args;
 use args::Args;
@@ -20,28 +22,111 @@ fn main() -> Result<()> {
         args::Subcommands::Branch(vbranch::Platform { cmd }) => {
             let project = command::prepare::project_from_path(args.current_dir)?;
             match cmd {
-                Some(vbranch::SubCommands::ListLocal) => command::vbranch::list_local(project),
-                Some(vbranch::SubCommands::Status) => command::vbranch::status(project),
+                Some(vbranch::SubCommands::ListLocal) => {
+                    command::vbranch::list_local(project, args.json)
+                }
+                Some(vbranch::SubCommands::Status) => command::vbranch::status(project, args.json),
                 Some(vbranch::SubCommands::Unapply { name }) => {
-                    command::vbranch::unapply(project, name)
+                    command::vbranch::unapply(project, name, args.json)
                 }
                 Some(vbranch::SubCommands::SetDefault { name }) => {
-                    command::vbranch::set_default(project, name)
+                    command::vbranch::set_default(project, name, args.json)
                 }
                 Some(vbranch::SubCommands::Commit { message, name }) => {
-                    command::vbranch::commit(project, name, message)
+                    command::vbranch::commit(project, name, message, args.json)
+                }
+                Some(vbranch::SubCommands::CommitLines {
+                    message,
+                    name,
+                    path,
+                    hunk,
+                    lines,
+                }) => command::vbranch::commit_lines(
+                    project,
+                    name,
+                    message,
+                    LineSelection {
+                        file_path: path,
+                        hunk_id: hunk,
+                        lines,
+                    },
+                    args.json,
+                ),
+                Some(vbranch::SubCommands::Amend { commit, name }) => {
+                    command::vbranch::amend(project, name, commit, args.json)
+                }
+                Some(vbranch::SubCommands::Fixup { commit, name }) => {
+                    command::vbranch::fixup(project, name, commit, args.json)
+                }
+                Some(vbranch::SubCommands::Autosquash { name }) => {
+                    command::vbranch::autosquash(project, name, args.json)
+                }
+                Some(vbranch::SubCommands::Absorb { name }) => {
+                    command::vbranch::absorb(project, name, args.json)
+                }
+                Some(vbranch::SubCommands::Squash { name, commit }) => {
+                    command::vbranch::squash(project, name, commit, args.json)
+                }
+                Some(vbranch::SubCommands::Reorder {
+                    offset,
+                    name,
+                    commit,
+                }) => command::vbranch::reorder(project, name, commit, offset, args.json),
+                Some(vbranch::SubCommands::CherryPick { name, commit }) => {
+                    command::vbranch::cherry_pick(project, name, commit, args.json)
+                }
+                Some(vbranch::SubCommands::Revert { commit, name, rev }) => {
+                    command::vbranch::revert(project, name, rev, commit, args.json)
+                }
+                Some(vbranch::SubCommands::Undo { name, commit }) => {
+                    command::vbranch::undo(project, name, commit, args.json)
+                }
+                Some(vbranch::SubCommands::MoveCommit { commit, to }) => {
+                    command::vbranch::move_commit(project, commit, to, args.json)
+                }
+                Some(vbranch::SubCommands::MoveFiles {
+                    from,
+                    to,
+                    name,
+                    paths,
+                }) => command::vbranch::move_files(project, name, from, to, paths, args.json),
+                Some(vbranch::SubCommands::InsertBlankCommit {
+                    offset,
+                    name,
+                    commit,
+                }) => {
+                    command::vbranch::insert_blank_commit(project, name, commit, offset, args.json)
+                }
+                Some(vbranch::SubCommands::SplitCommit {
+                    groups,
+                    name,
+                    commit,
+                }) => command::vbranch::split_commit(project, name, commit, groups, args.json),
+                Some(vbranch::SubCommands::Reword {
+                    message,
+                    name,
+                    commit,
+                }) => command::vbranch::reword(project, name, commit, message, args.json),
+                Some(vbranch::SubCommands::Hunks) => command::vbranch::hunks(project, args.json),
+                Some(vbranch::SubCommands::Assign { patch, name, hunks }) => {
+                    command::vbranch::assign(project, name, hunks, patch, args.json)
                 }
                 Some(vbranch::SubCommands::Create { set_default, name }) => {
-                    command::vbranch::create(project, name, set_default)
+                    command::vbranch::create(project, name, set_default, args.json)
                 }
                 Some(vbranch::SubCommands::Details { names }) => {
-                    command::vbranch::details(project, names)
+                    command::vbranch::details(project, names, args.json)
+                }
+                Some(vbranch::SubCommands::ListAll) => {
+                    command::vbranch::list_all(project, args.json)
+                }
+                Some(vbranch::SubCommands::UpdateTarget { preview }) => {
+                    command::vbranch::update_target(project, preview, args.json)
                 }
-                Some(vbranch::SubCommands::ListAll) => command::vbranch::list_all(project),
-                Some(vbranch::SubCommands::UpdateTarget) => {
-                    command::vbranch::update_target(project)
+                Some(vbranch::SubCommands::IntegrateUpstream { preview, name }) => {
+                    command::vbranch::integrate_upstream(project, name, preview, args.json)
                 }
-                None => command::vbranch::list(project),
+                None => command::vbranch::list(project, args.json),
             }
         }
         args::Subcommands::Project(project::Platform {
@@ -51,27 +136,53 @@ fn main() -> Result<()> {
         }) => match cmd {
             Some(project::SubCommands::SwitchToWorkspace { remote_ref_name }) => {
                 let project = command::prepare::project_from_path(args.current_dir)?;
-                command::project::switch_to_workspace(project, remote_ref_name)
+                command::project::switch_to_workspace(project, remote_ref_name, args.json)
             }
             Some(project::SubCommands::Add {
                 switch_to_workspace,
                 path,
             }) => {
                 let ctrl = command::prepare::project_controller(app_suffix, app_data_dir)?;
-                command::project::add(ctrl, path, switch_to_workspace)
+                command::project::add(ctrl, path, switch_to_workspace, args.json)
             }
             None => {
                 let ctrl = command::prepare::project_controller(app_suffix, app_data_dir)?;
-                command::project::list(ctrl)
+                command::project::list(ctrl, args.json)
             }
         },
         args::Subcommands::Snapshot(snapshot::Platform { cmd }) => {
             let project = command::prepare::project_from_path(args.current_dir)?;
             match cmd {
+                Some(snapshot::SubCommands::List {
+                    limit,
+                    offset,
+                    kinds,
+                    trailers,
+                    since,
+                    until,
+                    path,
+                }) => {
+                    let query = SnapshotQuery {
+                        operations: kinds,
+                        trailers,
+                        since,
+                        until,
+                        path,
+                    };
+                    command::snapshot::list(project, limit, offset, &query, args.json)
+                }
+                Some(snapshot::SubCommands::Diff { snapshot_id }) => {
+                    command::snapshot::diff(project, snapshot_id, args.json)
+                }
+                Some(snapshot::SubCommands::Show { snapshot_id }) => {
+                    command::snapshot::show(project, snapshot_id, args.json)
+                }
                 Some(snapshot::SubCommands::Restore { snapshot_id }) => {
                     command::snapshot::restore(project, snapshot_id)
                 }
-                None => command::snapshot::list(project),
+                None => {
+                    command::snapshot::list(project, 100, 0, &SnapshotQuery::default(), args.json)
+                }
             }
         }
     }
@@ -0,0 +1,122 @@
+use anyhow::Result;
+
+#[test]
+fn amend_by_hash() -> Result<()> {
+    let ctx = project_ctx("amend-by-hash")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["three", "two", "one"]);
+    assert_eq!(
+        blob_at(&ctx, &commits[1], "two")?,
+        "two\namended\n",
+        "the worktree change was amended into the middle commit"
+    );
+    Ok(())
+}
+
+#[test]
+fn squash_by_change_id() -> Result<()> {
+    let ctx = project_ctx("squash-by-change-id")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["two\nthree", "one"]);
+    Ok(())
+}
+
+#[test]
+fn reorder_down() -> Result<()> {
+    let ctx = project_ctx("reorder-down")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["two", "three", "one"]);
+    Ok(())
+}
+
+#[test]
+fn undo_middle() -> Result<()> {
+    let ctx = project_ctx("undo-middle")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["three", "one"]);
+    assert!(
+        ctx.project().path.join("two").is_file(),
+        "the changes of the undone commit remain in the worktree"
+    );
+    Ok(())
+}
+
+#[test]
+fn move_commit() -> Result<()> {
+    let ctx = project_ctx("move-commit")?;
+    assert_eq!(messages(&branch_commits(&ctx, "virtual")?), ["two", "one"]);
+    assert_eq!(messages(&branch_commits(&ctx, "other")?), ["three"]);
+    Ok(())
+}
+
+#[test]
+fn move_files() -> Result<()> {
+    let ctx = project_ctx("move-files")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["three", "two", "one"]);
+    assert_eq!(
+        blob_at(&ctx, &commits[2], "three")?,
+        "three\n",
+        "the file was moved into the bottom-most commit"
+    );
+    Ok(())
+}
+
+#[test]
+fn insert_blank_commit() -> Result<()> {
+    let ctx = project_ctx("insert-blank-commit")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["", "three", "two", "one"]);
+    Ok(())
+}
+
+#[test]
+fn reword() -> Result<()> {
+    let ctx = project_ctx("reword")?;
+    let commits = branch_commits(&ctx, "virtual")?;
+    assert_eq!(messages(&commits), ["three", "two, reworded", "one"]);
+    Ok(())
+}
+
+mod util {
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use gitbutler_branch::VirtualBranchesHandle;
+    use gitbutler_command_context::CommandContext;
+    use gitbutler_repo::{LogUntil, RepoActionsExt};
+
+    pub fn project_ctx(name: &str) -> Result<CommandContext> {
+        gitbutler_testsupport::read_only::fixture("for-commit-workflow.sh", name)
+    }
+
+    /// Return all commits of the virtual branch named `name`, from its head down to the target.
+    pub fn branch_commits<'repo>(
+        ctx: &'repo CommandContext,
+        name: &str,
+    ) -> Result<Vec<git2::Commit<'repo>>> {
+        let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
+        let branch = vb_state
+            .list_all_branches()?
+            .into_iter()
+            .find(|b| b.name == name)
+            .with_context(|| format!("no virtual branch named '{name}'"))?;
+        let target = vb_state.get_default_target()?;
+        ctx.log(branch.head, LogUntil::Commit(target.sha))
+    }
+
+    pub fn messages(commits: &[git2::Commit<'_>]) -> Vec<String> {
+        commits
+            .iter()
+            .map(|c| c.message().expect("valid UTF-8").to_owned())
+            .collect()
+    }
+
+    /// Return the content of the file at `path` in the tree of `commit`.
+    pub fn blob_at(ctx: &CommandContext, commit: &git2::Commit<'_>, path: &str) -> Result<String> {
+        let entry = commit.tree()?.get_path(Path::new(path))?;
+        let blob = ctx.repository().find_blob(entry.id())?;
+        Ok(std::str::from_utf8(blob.content())?.to_owned())
+    }
+}
+use util::{blob_at, branch_commits, messages, project_ctx};
@@ -0,0 +1,90 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+CLI=${1:?The first argument is the GitButler CLI}
+
+function tick () {
+  if test -z "${tick+set}"; then
+    tick=1675176957
+  else
+    tick=$(($tick + 60))
+  fi
+  GIT_COMMITTER_DATE="$tick +0100"
+  GIT_AUTHOR_DATE="$tick +0100"
+  export GIT_COMMITTER_DATE GIT_AUTHOR_DATE
+}
+tick
+
+# Print the GitButler change-id of the given revision.
+function change_id () {
+  git cat-file commit "${1:?revision}" | sed -n 's/^gitbutler-change-id //p'
+}
+
+# Set up a workspace with a virtual branch named `virtual` that has three commits,
+# each adding one file named after the commit.
+function three_commits () {
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name @{u})"
+  $CLI branch create virtual
+  for name in one two three; do
+    echo $name > $name
+    tick
+    $CLI branch commit virtual -m "$name"
+  done
+}
+
+git init remote
+(cd remote
+  echo first > file
+  git add . && git commit -m "init"
+)
+
+export GITBUTLER_CLI_DATA_DIR=../user/gitbutler/app-data
+git clone remote amend-by-hash
+(cd amend-by-hash
+  three_commits
+  echo amended >> two
+  $CLI branch amend --commit "$(git rev-parse --short refs/gitbutler/virtual~1)" virtual
+)
+
+git clone remote squash-by-change-id
+(cd squash-by-change-id
+  three_commits
+  $CLI branch squash virtual "$(change_id refs/gitbutler/virtual)"
+)
+
+git clone remote reorder-down
+(cd reorder-down
+  three_commits
+  $CLI branch reorder --offset 1 virtual "$(git rev-parse --short refs/gitbutler/virtual)"
+)
+
+git clone remote undo-middle
+(cd undo-middle
+  three_commits
+  $CLI branch undo virtual "$(git rev-parse --short refs/gitbutler/virtual~1)"
+)
+
+git clone remote move-commit
+(cd move-commit
+  three_commits
+  $CLI branch create other
+  $CLI branch move-commit --to other "$(change_id refs/gitbutler/virtual)"
+)
+
+git clone remote move-files
+(cd move-files
+  three_commits
+  $CLI branch move-files --from "$(git rev-parse --short refs/gitbutler/virtual)" \
+    --to "$(git rev-parse --short refs/gitbutler/virtual~2)" virtual three
+)
+
+git clone remote insert-blank-commit
+(cd insert-blank-commit
+  three_commits
+  $CLI branch insert-blank-commit virtual "$(git rev-parse --short refs/gitbutler/virtual)"
+)
+
+git clone remote reword
+(cd reword
+  three_commits
+  $CLI branch reword -m "two, reworded" virtual "$(change_id refs/gitbutler/virtual~1)"
+)
@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+CLI=${1:?The first argument is the GitButler CLI}
+
+git init remote
+(cd remote
+  seq 20 > file
+  git add . && git commit -m "init"
+)
+
+# Set up a workspace with the default virtual branch `virtual` and another one named `other`,
+# with two uncommitted hunks in `file` that are both assigned to `virtual`.
+function two_hunks () {
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name @{u})"
+  $CLI branch create --set-default virtual
+  $CLI branch create other
+  { seq 1; echo two; seq 3 17; echo eighteen; seq 19 20; } > file
+  $CLI branch status >/dev/null
+}
+
+export GITBUTLER_CLI_DATA_DIR=../user/gitbutler/app-data
+git clone remote assign-by-id
+(cd assign-by-id
+  two_hunks
+  $CLI branch assign other file:15-21
+)
+
+git clone remote assign-interactively
+(cd assign-interactively
+  two_hunks
+  printf 'n\ny\n' | $CLI branch assign --patch other
+)
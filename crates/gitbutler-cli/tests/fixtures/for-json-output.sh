@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+CLI=${1:?The first argument is the GitButler CLI}
+
+git init remote
+(cd remote
+  echo first > file
+  git add . && git commit -m "init"
+)
+
+export GITBUTLER_CLI_DATA_DIR=../user/gitbutler/app-data
+# Make `other` the default branch and commit to it, and write the JSON output
+# of each command next to the project, named after the command.
+git clone remote json-output
+(cd json-output
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name @{u})"
+  $CLI branch create virtual
+  $CLI branch create other
+  $CLI --json branch set-default other > ../set-default.json
+  echo one > one
+  $CLI --json branch commit other -m "one" > ../commit.json
+  $CLI --json branch list > ../list.json
+)
@@ -0,0 +1,43 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+CLI=${1:?The first argument is the GitButler CLI}
+
+function tick () {
+  if test -z "${tick+set}"; then
+    tick=1675176957
+  else
+    tick=$(($tick + 60))
+  fi
+  GIT_COMMITTER_DATE="$tick +0100"
+  GIT_AUTHOR_DATE="$tick +0100"
+  export GIT_COMMITTER_DATE GIT_AUTHOR_DATE
+}
+tick
+
+git init remote
+(cd remote
+  echo first > file
+  git add . && git commit -m "init"
+)
+
+export GITBUTLER_CLI_DATA_DIR=../user/gitbutler/app-data
+# Snapshot a virtual branch named `virtual` before each of its two commits, and write
+# the output of the snapshot subcommands next to the project, named after the subcommand.
+git clone remote snapshots
+(cd snapshots
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name @{u})"
+  $CLI branch create virtual
+  for name in one two; do
+    echo $name > $name
+    $CLI branch status >/dev/null
+    tick
+    $CLI branch commit virtual -m "$name"
+  done
+
+  $CLI snapshot list > ../list
+  $CLI snapshot list --kind CreateCommit > ../list-commits
+  # the second to last field of a listed snapshot is its id
+  snapshot_id=$($CLI snapshot list --kind CreateCommit --limit 1 | awk '{ print $(NF-1) }')
+  $CLI snapshot diff "$snapshot_id" > ../diff
+  $CLI snapshot show "$snapshot_id" > ../show
+)
@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+#[test]
+fn assign_by_id() -> Result<()> {
+    let ctx = project_ctx("assign-by-id")?;
+    assert_eq!(hunk_ids(&ctx, "virtual")?, ["file:1-6"]);
+    assert_eq!(
+        hunk_ids(&ctx, "other")?,
+        ["file:15-21"],
+        "the selected hunk was moved"
+    );
+    Ok(())
+}
+
+#[test]
+fn assign_interactively() -> Result<()> {
+    let ctx = project_ctx("assign-interactively")?;
+    assert_eq!(hunk_ids(&ctx, "virtual")?, ["file:1-6"]);
+    assert_eq!(
+        hunk_ids(&ctx, "other")?,
+        ["file:15-21"],
+        "only the second hunk was accepted"
+    );
+    Ok(())
+}
+
+mod util {
+    use anyhow::{Context, Result};
+    use gitbutler_branch::VirtualBranchesHandle;
+    use gitbutler_command_context::CommandContext;
+
+    pub fn project_ctx(name: &str) -> Result<CommandContext> {
+        gitbutler_testsupport::read_only::fixture("for-hunk-assignment.sh", name)
+    }
+
+    /// Return the hunks owned by the virtual branch named `name` as `path:start-end`.
+    pub fn hunk_ids(ctx: &CommandContext, name: &str) -> Result<Vec<String>> {
+        let branch = VirtualBranchesHandle::new(ctx.project().gb_dir())
+            .list_all_branches()?
+            .into_iter()
+            .find(|b| b.name == name)
+            .with_context(|| format!("no virtual branch named '{name}'"))?;
+        Ok(branch
+            .ownership
+            .claims
+            .iter()
+            .flat_map(|claim| {
+                claim.hunks.iter().map(|hunk| {
+                    format!("{}:{}-{}", claim.file_path.display(), hunk.start, hunk.end)
+                })
+            })
+            .collect())
+    }
+}
+use util::{hunk_ids, project_ctx};
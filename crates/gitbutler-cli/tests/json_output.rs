@@ -0,0 +1,72 @@
+use anyhow::Result;
+use serde_json::Value;
+
+#[test]
+fn branch_list() -> Result<()> {
+    let project = project("json-output")?;
+    let branches = output(&project, "list.json")?;
+    let branches = branches.as_array().expect("branches are listed as array");
+    let mut names = names(branches);
+    names.sort();
+    assert_eq!(names, ["other", "virtual"]);
+
+    let other = branches
+        .iter()
+        .find(|branch| branch["name"] == "other")
+        .expect("just checked");
+    assert_eq!(
+        other["head"],
+        output(&project, "commit.json")?,
+        "the id of the new commit is printed as string"
+    );
+    Ok(())
+}
+
+#[test]
+fn branch_set_default() -> Result<()> {
+    let project = project("json-output")?;
+    assert_eq!(
+        output(&project, "set-default.json")?,
+        Value::Null,
+        "there is no result to print, but it's still valid JSON"
+    );
+
+    let branches = output(&project, "list.json")?;
+    let default = branches
+        .as_array()
+        .expect("branches are listed as array")
+        .iter()
+        .max_by_key(|branch| branch["selected_for_changes"].as_i64())
+        .expect("there are branches");
+    assert_eq!(default["name"], "other");
+    Ok(())
+}
+
+mod util {
+    use anyhow::{Context, Result};
+    use gitbutler_project::Project;
+    use serde_json::Value;
+
+    pub fn project(name: &str) -> Result<Project> {
+        gitbutler_testsupport::read_only::fixture_project("for-json-output.sh", name)
+    }
+
+    /// Parse the JSON that the fixture wrote to the file `name` next to `project`.
+    pub fn output(project: &Project, name: &str) -> Result<Value> {
+        let path = project
+            .path
+            .parent()
+            .context("projects are in the fixture directory")?
+            .join(name);
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Return the names of all `branches`, in order.
+    pub fn names(branches: &[Value]) -> Vec<&str> {
+        branches
+            .iter()
+            .map(|branch| branch["name"].as_str().expect("branches have names"))
+            .collect()
+    }
+}
+use util::{names, output, project};
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use gitbutler_oplog::OplogExt;
+
+#[test]
+fn list() -> Result<()> {
+    let project = project("snapshots")?;
+    let snapshot_ids: Vec<_> = project
+        .list_snapshots(100, None)?
+        .into_iter()
+        .map(|snapshot| snapshot.commit_id.to_string())
+        .collect();
+    assert!(snapshot_ids.len() > 2);
+
+    let lines = output_lines(&project, "list")?;
+    assert_eq!(
+        lines
+            .iter()
+            .map(|line| snapshot_id(line))
+            .collect::<Vec<_>>(),
+        snapshot_ids,
+        "all snapshots are listed, most recent first"
+    );
+    assert!(lines[0].ends_with(" CreateCommit"));
+
+    let lines = output_lines(&project, "list-commits")?;
+    assert_eq!(
+        lines.len(),
+        2,
+        "only the snapshots of both commits are listed"
+    );
+    assert!(lines.iter().all(|line| line.ends_with(" CreateCommit")));
+    Ok(())
+}
+
+#[test]
+fn diff() -> Result<()> {
+    let project = project("snapshots")?;
+    let lines = output_lines(&project, "diff")?;
+    assert_eq!(
+        lines.len(),
+        4,
+        "only the file added since the previous snapshot"
+    );
+    assert_eq!(lines[..2], ["--- two", "+++ two"]);
+    assert!(
+        lines[2].starts_with("@@ -0,0 +1"),
+        "each hunk has one header"
+    );
+    assert_eq!(lines[3], "+two");
+    Ok(())
+}
+
+#[test]
+fn show() -> Result<()> {
+    let project = project("snapshots")?;
+    let snapshot_id = snapshot_id(&output_lines(&project, "list-commits")?[0]).to_owned();
+
+    let lines = output_lines(&project, "show")?;
+    assert_eq!(lines[0], format!("snapshot {snapshot_id}"));
+    assert!(lines[1].starts_with("Date: "));
+    for expected in ["CreateCommit", "Operation: CreateCommit", "message: two"] {
+        assert!(
+            lines.iter().any(|line| line == expected),
+            "{expected:?} is part of the details"
+        );
+    }
+    Ok(())
+}
+
+mod util {
+    use anyhow::{Context, Result};
+    use gitbutler_project::Project;
+
+    pub fn project(name: &str) -> Result<Project> {
+        gitbutler_testsupport::read_only::fixture_project("for-snapshots.sh", name)
+    }
+
+    /// Return the lines that the fixture wrote to the file `name` next to `project`.
+    pub fn output_lines(project: &Project, name: &str) -> Result<Vec<String>> {
+        let path = project
+            .path
+            .parent()
+            .context("projects are in the fixture directory")?
+            .join(name);
+        Ok(std::fs::read_to_string(path)?
+            .lines()
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    /// Return the id of the snapshot that `line` of `snapshot list` describes.
+    pub fn snapshot_id(line: &str) -> &str {
+        line.rsplit(' ')
+            .nth(1)
+            .expect("id and operation are listed")
+    }
+}
+use util::{output_lines, project, snapshot_id};
@@ -22,6 +22,7 @@ const V2_HEADERS_VERSION: &str = "2";
 
 const V2_CHANGE_ID_HEADER: &str = "gitbutler-change-id";
 const V2_CONFLICTED_HEADER: &str = "gitbutler-conflicted";
+const V2_FIXUP_OF_HEADER: &str = "gitbutler-fixup-of";
 #[derive(Debug, Clone)]
 pub struct CommitHeadersV2 {
     /// A property we can use to determine if two different commits are
@@ -32,6 +33,9 @@ pub struct CommitHeadersV2 {
     /// commit. This is only written if the property is present. Conflicted
     /// commits should never make it into the main trunk.
     pub conflicted: Option<u64>,
+    /// The change-id of the commit that this commit is a fixup for. Autosquashing
+    /// folds it into that commit. This is only written if the property is present.
+    pub fixup_of: Option<String>,
 }
 
 impl Default for CommitHeadersV2 {
@@ -40,6 +44,7 @@ impl Default for CommitHeadersV2 {
             // Change ID using base16 encoding
             change_id: Uuid::new_v4().to_string(),
             conflicted: None,
+            fixup_of: None,
         }
     }
 }
@@ -49,6 +54,7 @@ impl From<CommitHeadersV1> for CommitHeadersV2 {
         CommitHeadersV2 {
             change_id: commit_headers_v1.change_id,
             conflicted: None,
+            fixup_of: None,
         }
     }
 }
@@ -77,9 +83,15 @@ impl HasCommitHeaders for git2::Commit<'_> {
                     Err(_) => None,
                 };
 
+                let fixup_of = self
+                    .header_field_bytes(V2_FIXUP_OF_HEADER)
+                    .ok()
+                    .and_then(|value| value.as_str().map(ToOwned::to_owned));
+
                 Some(CommitHeadersV2 {
                     change_id,
                     conflicted,
+                    fixup_of,
                 })
             } else {
                 // Must be for a version we don't recognise
@@ -118,5 +130,9 @@ impl CommitHeadersV2 {
         if let Some(conflicted) = self.conflicted {
             commit_buffer.set_header(V2_CONFLICTED_HEADER, &conflicted.to_string())
         }
+
+        if let Some(fixup_of) = &self.fixup_of {
+            commit_buffer.set_header(V2_FIXUP_OF_HEADER, fixup_of)
+        }
     }
 }
@@ -10,12 +10,24 @@ pub struct GbConfig {
     pub signing_format: Option<String>,
     pub gpg_program: Option<String>,
     pub gpg_ssh_program: Option<String>,
+    /// Strip trailing whitespace and surplus blank lines from commit messages before they are written.
+    pub commit_message_cleanup: Option<bool>,
+    /// The maximum amount of characters allowed in the subject line of a commit message.
+    pub commit_message_max_subject_length: Option<u32>,
+    /// Require the subject line of commit messages to follow the conventional commits format.
+    pub commit_message_conventional: Option<bool>,
+    /// The trailer key, like `Ticket`, that every commit message must have.
+    pub commit_message_required_trailer: Option<String>,
 }
 const SIGN_COMMITS: &str = "gitbutler.signCommits";
 const SIGNING_KEY: &str = "user.signingKey";
 const SIGNING_FORMAT: &str = "gpg.format";
 const GPG_PROGRAM: &str = "gpg.program";
 const GPG_SSH_PROGRAM: &str = "gpg.ssh.program";
+const COMMIT_MESSAGE_CLEANUP: &str = "gitbutler.commitMessage.cleanup";
+const COMMIT_MESSAGE_MAX_SUBJECT_LENGTH: &str = "gitbutler.commitMessage.maxSubjectLength";
+const COMMIT_MESSAGE_CONVENTIONAL: &str = "gitbutler.commitMessage.conventional";
+const COMMIT_MESSAGE_REQUIRED_TRAILER: &str = "gitbutler.commitMessage.requiredTrailer";
 
 pub trait GitConfig {
     fn gb_config(&self) -> Result<GbConfig>;
@@ -29,12 +41,21 @@ impl GitConfig for git2::Repository {
         let signing_format = get_string(self, SIGNING_FORMAT)?;
         let gpg_program = get_string(self, GPG_PROGRAM)?;
         let gpg_ssh_program = get_string(self, GPG_SSH_PROGRAM)?;
+        let commit_message_cleanup = get_bool(self, COMMIT_MESSAGE_CLEANUP)?;
+        let commit_message_max_subject_length =
+            get_i32(self, COMMIT_MESSAGE_MAX_SUBJECT_LENGTH)?.map(|length| length.max(0) as u32);
+        let commit_message_conventional = get_bool(self, COMMIT_MESSAGE_CONVENTIONAL)?;
+        let commit_message_required_trailer = get_string(self, COMMIT_MESSAGE_REQUIRED_TRAILER)?;
         Ok(GbConfig {
             sign_commits,
             signing_key,
             signing_format,
             gpg_program,
             gpg_ssh_program,
+            commit_message_cleanup,
+            commit_message_max_subject_length,
+            commit_message_conventional,
+            commit_message_required_trailer,
         })
     }
     fn set_gb_config(&self, config: GbConfig) -> Result<()> {
@@ -53,6 +74,22 @@ impl GitConfig for git2::Repository {
         if let Some(gpg_ssh_program) = config.gpg_ssh_program {
             set_local_string(self, GPG_SSH_PROGRAM, &gpg_ssh_program)?;
         }
+        if let Some(cleanup) = config.commit_message_cleanup {
+            set_local_bool(self, COMMIT_MESSAGE_CLEANUP, cleanup)?;
+        }
+        if let Some(max_subject_length) = config.commit_message_max_subject_length {
+            set_local_i32(
+                self,
+                COMMIT_MESSAGE_MAX_SUBJECT_LENGTH,
+                max_subject_length.try_into()?,
+            )?;
+        }
+        if let Some(conventional) = config.commit_message_conventional {
+            set_local_bool(self, COMMIT_MESSAGE_CONVENTIONAL, conventional)?;
+        }
+        if let Some(required_trailer) = config.commit_message_required_trailer {
+            set_local_string(self, COMMIT_MESSAGE_REQUIRED_TRAILER, &required_trailer)?;
+        }
         Ok(())
     }
 }
@@ -68,6 +105,17 @@ fn get_bool(repo: &git2::Repository, key: &str) -> Result<Option<bool>> {
     }
 }
 
+fn get_i32(repo: &git2::Repository, key: &str) -> Result<Option<i32>> {
+    let config = repo.config()?;
+    match config.get_i32(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) => match err.code() {
+            git2::ErrorCode::NotFound => Ok(None),
+            _ => Err(err.into()),
+        },
+    }
+}
+
 fn get_string(repo: &git2::Repository, key: &str) -> Result<Option<String>> {
     let config = repo.config()?;
     match config.get_string(key) {
@@ -87,6 +135,14 @@ fn set_local_bool(repo: &git2::Repository, key: &str, val: bool) -> Result<()> {
     }
 }
 
+fn set_local_i32(repo: &git2::Repository, key: &str, val: i32) -> Result<()> {
+    let config = repo.config()?;
+    match config.open_level(ConfigLevel::Local) {
+        Ok(mut local) => local.set_i32(key, val).map_err(Into::into),
+        Err(err) => Err(err.into()),
+    }
+}
+
 fn set_local_string(repo: &git2::Repository, key: &str, val: &str) -> Result<()> {
     let config = repo.config()?;
     match config.open_level(ConfigLevel::Local) {
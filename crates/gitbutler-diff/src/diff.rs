@@ -1,6 +1,13 @@
-use std::{borrow::Cow, collections::HashMap, path::PathBuf, str};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    str,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bstr::{BStr, BString, ByteSlice, ByteVec};
 use gitbutler_cherry_pick::RepositoryExt;
 use gitbutler_command_context::RepositoryExtLite;
@@ -8,8 +15,13 @@ use gitbutler_serde::BStringForFrontend;
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
+use crate::{intraline::TokenRange, lfs::LfsPointer};
+
 pub type DiffByPathMap = HashMap<PathBuf, FileDiff>;
 
+/// The amount of unchanged lines shown around the changes of each hunk.
+const CONTEXT_LINES: u32 = 3;
+
 /// The type of change
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -20,6 +32,10 @@ pub enum ChangeType {
     Deleted,
     /// Entry content changed between old and new
     Modified,
+    /// Entry was moved from another path, possibly with changes to its content
+    Renamed,
+    /// Entry is a copy of another entry, possibly with changes to its content
+    Copied,
 }
 impl From<git2::Delta> for ChangeType {
     fn from(v: git2::Delta) -> Self {
@@ -27,12 +43,9 @@ impl From<git2::Delta> for ChangeType {
         use ChangeType as C;
         match v {
             D::Untracked | D::Added => C::Added,
-            D::Modified
-            | D::Unmodified
-            | D::Renamed
-            | D::Copied
-            | D::Typechange
-            | D::Conflicted => C::Modified,
+            D::Modified | D::Unmodified | D::Typechange | D::Conflicted => C::Modified,
+            D::Renamed => C::Renamed,
+            D::Copied => C::Copied,
             D::Ignored | D::Unreadable | D::Deleted => C::Deleted,
         }
     }
@@ -51,13 +64,99 @@ pub struct GitHunk {
     pub diff_lines: BStringForFrontend,
     pub binary: bool,
     pub change_type: ChangeType,
+    /// The path the file had before it was renamed or copied, if `change_type` is [`ChangeType::Renamed`]
+    /// or [`ChangeType::Copied`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<PathBuf>,
+    /// The words or characters that changed within the lines of this hunk, if they were
+    /// [computed](crate::intraline::annotate).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_tokens: Option<Vec<TokenRange>>,
+    /// What is known about the content of binary files and LFS pointers, whose changes can't be shown line by line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_info: Option<ContentInfo>,
+}
+
+/// The sizes and type of the content of a file whose changes can't be shown line by line.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentInfo {
+    /// `true` if the file is a Git LFS pointer, in which case the sizes are the ones of the LFS objects.
+    pub lfs: bool,
+    /// The size of the content before the change, or `None` if the file was added.
+    pub old_size_bytes: Option<u64>,
+    /// The size of the content after the change, or `None` if the file was deleted.
+    pub new_size_bytes: Option<u64>,
+    /// The MIME type of binary content, if it could be determined.
+    pub mime_type: Option<String>,
+    /// A description of the change for display, like `LFS object changed (1.2 KiB → 3.4 KiB)`.
+    pub summary: String,
+}
+
+impl ContentInfo {
+    /// Describe a change from the `old` to the `new` LFS pointer.
+    pub fn lfs(old: Option<&LfsPointer>, new: Option<&LfsPointer>) -> Self {
+        Self::new(true, old.map(|p| p.size), new.map(|p| p.size), None)
+    }
+
+    /// Describe a change of binary content from `old_size_bytes` to `new_size_bytes`.
+    pub fn binary(
+        old_size_bytes: Option<u64>,
+        new_size_bytes: Option<u64>,
+        mime_type: Option<String>,
+    ) -> Self {
+        Self::new(false, old_size_bytes, new_size_bytes, mime_type)
+    }
+
+    fn new(
+        lfs: bool,
+        old_size_bytes: Option<u64>,
+        new_size_bytes: Option<u64>,
+        mime_type: Option<String>,
+    ) -> Self {
+        let kind = if lfs { "LFS object" } else { "Binary file" };
+        let (action, sizes) = match (old_size_bytes, new_size_bytes) {
+            (Some(old), Some(new)) => (
+                "changed",
+                format!("{} → {}", human_size(old), human_size(new)),
+            ),
+            (None, Some(new)) => ("added", human_size(new)),
+            (Some(old), None) => ("removed", human_size(old)),
+            (None, None) => ("changed", "unknown size".into()),
+        };
+        let summary = match &mime_type {
+            Some(mime_type) => format!("{kind} {action} ({mime_type}, {sizes})"),
+            None => format!("{kind} {action} ({sizes})"),
+        };
+        ContentInfo {
+            lfs,
+            old_size_bytes,
+            new_size_bytes,
+            mime_type,
+            summary,
+        }
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
 }
 
 /// Lifecycle
 impl GitHunk {
     /// A special hunk that signals a binary file whose complete content is a blob under `hex_id` in Git.
     /// `changetype` is tells us what happened with the file.
-    fn binary_marker(hex_id: String, change_type: ChangeType) -> Self {
+    fn binary_marker(hex_id: String, change_type: ChangeType, old_path: Option<PathBuf>) -> Self {
         GitHunk {
             old_start: 0,
             old_lines: 0,
@@ -66,6 +165,9 @@ impl GitHunk {
             diff_lines: hex_id.into(),
             binary: true,
             change_type,
+            old_path,
+            changed_tokens: None,
+            content_info: None,
         }
     }
 
@@ -79,6 +181,18 @@ impl GitHunk {
             diff_lines: Default::default(),
             binary: false,
             change_type: ChangeType::Modified,
+            old_path: None,
+            changed_tokens: None,
+            content_info: None,
+        }
+    }
+
+    /// Return a hunk that represents a file which was renamed or copied from `old_path` without changes to its content.
+    fn generic_moved_file(change_type: ChangeType, old_path: PathBuf) -> Self {
+        Self {
+            change_type,
+            old_path: Some(old_path),
+            ..Self::generic_new_file()
         }
     }
 }
@@ -105,6 +219,136 @@ impl GitHunk {
         unapplied_hunk.old_start <= workspace_new_end
             && workspace_hunk.new_start <= unapplied_old_end
     }
+
+    /// Returns `true` if `self` and `other`, both hunks of the same diff of a file, are so close to each other
+    /// that their context lines would overlap if one of them had twice as many, so they can't be told apart
+    /// by the lines around them. Committing one without the other then changes the context the other is shown in.
+    pub fn is_adjacent_to(&self, other: &GitHunk) -> bool {
+        let self_old_end = self.old_start + self.old_lines;
+        let other_old_end = other.old_start + other.old_lines;
+
+        self.old_start <= other_old_end + CONTEXT_LINES
+            && other.old_start <= self_old_end + CONTEXT_LINES
+    }
+}
+
+/// Selection
+impl GitHunk {
+    /// Return a hunk with only the added or removed `lines` of this hunk, identified by their 0-based index
+    /// in `diff_lines` with the `@@` header being the first line, which applies to the same old lines.
+    ///
+    /// Removed lines that aren't selected are kept as context, while added lines that aren't selected are dropped.
+    /// Fails if any of `lines` isn't an added or removed line, or if this is a binary hunk.
+    pub fn select_lines(&self, lines: &[u32]) -> Result<GitHunk> {
+        if self.binary {
+            bail!("lines of binary hunks can't be selected");
+        }
+        let diff_lines: Vec<&[u8]> = self.diff_lines.lines_with_terminator().collect();
+        if let Some(line) = lines.iter().find(|line| {
+            !diff_lines.get(**line as usize).map_or(false, |diff_line| {
+                diff_line.starts_with(b"+") || diff_line.starts_with(b"-")
+            }) || **line == 0
+        }) {
+            bail!("line {line} isn't an added or removed line of the hunk");
+        }
+        if lines.is_empty() {
+            bail!("at least one line must be selected");
+        }
+
+        // Each line of the hunk with its index, along with the `\ No newline at end of file` marker that may follow it.
+        let mut entries: Vec<(u32, &[u8], &[u8])> = Vec::new();
+        for (idx, line) in diff_lines.iter().enumerate().skip(1) {
+            match entries.last_mut() {
+                Some(last) if line.starts_with(b"\\") => last.2 = *line,
+                _ => entries.push((idx as u32, *line, &b""[..])),
+            }
+        }
+        let is_selected = |entry: &&(u32, &[u8], &[u8])| lines.contains(&entry.0);
+
+        let mut body = BString::default();
+        let (mut old_lines, mut new_lines) = (0, 0);
+        let mut idx = 0;
+        while idx < entries.len() {
+            let (_, line, marker) = entries[idx];
+            if !line.starts_with(b"-") && !line.starts_with(b"+") {
+                if line.starts_with(b" ") {
+                    old_lines += 1;
+                    new_lines += 1;
+                }
+                body.push_str(line);
+                body.push_str(marker);
+                idx += 1;
+                continue;
+            }
+
+            // A block of removed lines followed by the lines that were added in their place.
+            let removed_end = idx
+                + entries[idx..]
+                    .iter()
+                    .take_while(|(_, line, _)| line.starts_with(b"-"))
+                    .count();
+            let added_end = removed_end
+                + entries[removed_end..]
+                    .iter()
+                    .take_while(|(_, line, _)| line.starts_with(b"+"))
+                    .count();
+            let removed = &entries[idx..removed_end];
+            let added: Vec<_> = entries[removed_end..added_end]
+                .iter()
+                .filter(is_selected)
+                .collect();
+            // Removed lines that aren't selected stay, so the added lines go right after the last removed
+            // line that is selected to keep their position.
+            let insert_at = removed
+                .iter()
+                .rposition(|entry| is_selected(&entry))
+                .map_or(removed.len(), |pos| pos + 1);
+            for pos in 0..=removed.len() {
+                if pos == insert_at {
+                    for (_, line, marker) in &added {
+                        new_lines += 1;
+                        body.push_str(line);
+                        body.push_str(marker);
+                    }
+                }
+                let Some(entry) = removed.get(pos) else {
+                    continue;
+                };
+                let (_, line, marker) = entry;
+                old_lines += 1;
+                if is_selected(&entry) {
+                    body.push_str(line);
+                } else {
+                    new_lines += 1;
+                    body.push(b' ');
+                    body.push_str(&line[1..]);
+                }
+                body.push_str(marker);
+            }
+            idx = added_end;
+        }
+
+        let header = diff_lines.first().copied().unwrap_or_default();
+        let section = header
+            .strip_prefix(b"@@ ")
+            .and_then(|rest| rest.find(b" @@").map(|pos| &rest[pos + 3..]))
+            .unwrap_or(&b"\n"[..]);
+        let mut diff = BString::from(format!(
+            "@@ -{},{old_lines} +{},{new_lines} @@",
+            self.old_start, self.new_start
+        ));
+        diff.push_str(section);
+        diff.push_str(body);
+
+        Ok(GitHunk {
+            old_lines,
+            new_lines,
+            diff_lines: diff.into(),
+            changed_tokens: None,
+            content_info: None,
+            ..self.clone()
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Default)]
@@ -123,21 +367,77 @@ pub struct FileDiff {
     pub new_size_bytes: u64,
 }
 
+/// Determines how files that were moved or copied are detected, instead of showing them as a deletion and an addition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenameOptions {
+    /// How similar, in percent, a new file has to be to a removed one to be considered a rename of it.
+    pub threshold: u16,
+    /// If `true`, also detect new files which are copies of existing ones.
+    pub copies: bool,
+}
+
+/// The whitespace changes that are ignored when diffing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreWhitespace {
+    /// Ignore all changes to whitespace, like `git diff --ignore-all-space`.
+    All,
+    /// Ignore changes to whitespace at the end of lines, like `git diff --ignore-space-at-eol`.
+    AtEol,
+    /// Ignore added or removed blank lines, like `git diff --ignore-blank-lines`.
+    BlankLines,
+}
+
+/// The settings that affect how [`workdir()`] and [`trees()`] diff files.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiffSettings {
+    /// If set, renamed and copied files are detected as such.
+    pub renames: Option<RenameOptions>,
+    /// If set, the given kind of whitespace changes doesn't produce or change hunks, unless a file has no other changes.
+    pub whitespace: Option<IgnoreWhitespace>,
+}
+
+impl DiffSettings {
+    fn apply_whitespace(&self, opts: &mut git2::DiffOptions) {
+        match self.whitespace {
+            None => {}
+            Some(IgnoreWhitespace::All) => {
+                opts.ignore_whitespace(true);
+            }
+            Some(IgnoreWhitespace::AtEol) => {
+                opts.ignore_whitespace_eol(true);
+            }
+            Some(IgnoreWhitespace::BlankLines) => {
+                opts.ignore_blank_lines(true);
+            }
+        }
+    }
+}
+
+/// Diff the tree of `commit_oid` against the worktree, including untracked files, as configured by `settings`.
 #[instrument(level = tracing::Level::DEBUG, skip(repo))]
-pub fn workdir(repo: &git2::Repository, commit_oid: git2::Oid) -> Result<DiffByPathMap> {
+pub fn workdir(
+    repo: &git2::Repository,
+    commit_oid: git2::Oid,
+    settings: DiffSettings,
+) -> Result<DiffByPathMap> {
     let commit = repo
         .find_commit(commit_oid)
         .context("failed to find commit")?;
     let old_tree = repo.find_real_tree(&commit, Default::default())?;
 
-    let mut diff_opts = git2::DiffOptions::new();
-    diff_opts
-        .recurse_untracked_dirs(true)
-        .include_untracked(true)
-        .show_binary(true)
-        .show_untracked_content(true)
-        .ignore_submodules(true)
-        .context_lines(3);
+    let diff_opts = || {
+        let mut diff_opts = git2::DiffOptions::new();
+        diff_opts
+            .recurse_untracked_dirs(true)
+            .include_untracked(true)
+            .show_binary(true)
+            .show_untracked_content(true)
+            .ignore_submodules(true)
+            .context_lines(CONTEXT_LINES);
+        diff_opts
+    };
+    let mut diff_opts_with_settings = diff_opts();
+    settings.apply_whitespace(&mut diff_opts_with_settings);
 
     let mut index = repo.index()?;
     // Just a hack to resolve conflicts, which don't get diffed.
@@ -158,26 +458,217 @@ pub fn workdir(repo: &git2::Repository, commit_oid: git2::Oid) -> Result<DiffByP
         index.add_path(conflict_path_to_resolve.as_ref())?;
     }
     repo.ignore_large_files_in_diffs(50_000_000)?;
-    let diff = repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts))?;
-    hunks_by_filepath(Some(repo), &diff)
+    let mut diff =
+        repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts_with_settings))?;
+    if let Some(renames) = settings.renames {
+        find_renames(&mut diff, renames)?;
+    }
+    let mut files = hunks_by_filepath(Some(repo), &diff)?;
+    if settings.whitespace.is_some() {
+        restore_old_context_lines(repo, &diff, &mut files)?;
+        let paths = files_without_hunks(&diff)?;
+        if !paths.is_empty() {
+            let mut unignored_diff_opts = diff_opts();
+            restrict_to_paths(&mut unignored_diff_opts, &paths);
+            let diff = repo
+                .diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut unignored_diff_opts))?;
+            files.extend(hunks_by_filepath(Some(repo), &diff)?);
+        }
+    }
+    describe_binary_files(repo, &diff, &mut files)?;
+    Ok(files)
 }
 
-pub fn trees(
+/// Diff the files at `paths` in the tree of `commit_oid` against the worktree without ignoring any whitespace,
+/// like [`workdir()`] does for files that only have whitespace changes.
+///
+/// These hunks keep whitespace changes that [`workdir()`] ignores next to other changes, so they can be saved with
+/// a branch, even though they aren't committed.
+pub fn workdir_with_whitespace(
     repo: &git2::Repository,
-    old_tree: &git2::Tree,
-    new_tree: &git2::Tree,
+    commit_oid: git2::Oid,
+    paths: &[PathBuf],
 ) -> Result<DiffByPathMap> {
+    if paths.is_empty() {
+        return Ok(DiffByPathMap::default());
+    }
+    let commit = repo
+        .find_commit(commit_oid)
+        .context("failed to find commit")?;
+    let old_tree = repo.find_real_tree(&commit, Default::default())?;
+
     let mut diff_opts = git2::DiffOptions::new();
     diff_opts
         .recurse_untracked_dirs(true)
         .include_untracked(true)
         .show_binary(true)
+        .show_untracked_content(true)
         .ignore_submodules(true)
-        .context_lines(3)
-        .show_untracked_content(true);
+        .context_lines(CONTEXT_LINES);
+    restrict_to_paths(&mut diff_opts, paths);
+    let diff = repo.diff_tree_to_workdir_with_index(Some(&old_tree), Some(&mut diff_opts))?;
+    hunks_by_filepath(Some(repo), &diff)
+}
+
+/// Diff `old_tree` against `new_tree` as configured by `settings`.
+pub fn trees(
+    repo: &git2::Repository,
+    old_tree: &git2::Tree,
+    new_tree: &git2::Tree,
+    settings: DiffSettings,
+) -> Result<DiffByPathMap> {
+    let diff_opts = || {
+        let mut diff_opts = git2::DiffOptions::new();
+        diff_opts
+            .recurse_untracked_dirs(true)
+            .include_untracked(true)
+            .show_binary(true)
+            .ignore_submodules(true)
+            .context_lines(CONTEXT_LINES)
+            .show_untracked_content(true);
+        diff_opts
+    };
+    let mut diff_opts_with_settings = diff_opts();
+    settings.apply_whitespace(&mut diff_opts_with_settings);
+
+    let mut diff = repo.diff_tree_to_tree(
+        Some(old_tree),
+        Some(new_tree),
+        Some(&mut diff_opts_with_settings),
+    )?;
+    if let Some(renames) = settings.renames {
+        find_renames(&mut diff, renames)?;
+    }
+    let mut files = hunks_by_filepath(None, &diff)?;
+    if settings.whitespace.is_some() {
+        restore_old_context_lines(repo, &diff, &mut files)?;
+        let paths = files_without_hunks(&diff)?;
+        if !paths.is_empty() {
+            let mut unignored_diff_opts = diff_opts();
+            restrict_to_paths(&mut unignored_diff_opts, &paths);
+            let diff = repo.diff_tree_to_tree(
+                Some(old_tree),
+                Some(new_tree),
+                Some(&mut unignored_diff_opts),
+            )?;
+            files.extend(hunks_by_filepath(None, &diff)?);
+        }
+    }
+    describe_binary_files(repo, &diff, &mut files)?;
+    Ok(files)
+}
+
+/// Return the paths of all modified files in `diff` whose changes were entirely ignored, like whitespace changes.
+///
+/// These are diffed again without ignoring anything, so they keep their hunks and stay owned by a branch. Otherwise
+/// their changes would be lost whenever the worktree is checked out from the branches.
+fn files_without_hunks(diff: &git2::Diff) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for (idx, delta) in diff.deltas().enumerate() {
+        if delta.status() != git2::Delta::Modified
+            || delta.old_file().mode() != delta.new_file().mode()
+            || delta.new_file().is_binary()
+        {
+            continue;
+        }
+        let Some(path) = delta.new_file().path() else {
+            continue;
+        };
+        let has_hunks =
+            git2::Patch::from_diff(diff, idx)?.map_or(true, |patch| patch.num_hunks() > 0);
+        if !has_hunks {
+            paths.push(path.to_owned());
+        }
+    }
+    Ok(paths)
+}
+
+/// Make `opts` diff nothing but the files at `paths`.
+fn restrict_to_paths(opts: &mut git2::DiffOptions, paths: &[PathBuf]) {
+    opts.disable_pathspec_match(true);
+    for path in paths {
+        opts.pathspec(path);
+    }
+}
+
+/// Replace the context lines of all hunks in `files` with the lines of the old file they stand for.
+///
+/// When whitespace is ignored, context lines may have whitespace changes and are printed as they are in
+/// the new file, so the hunks wouldn't apply to the old file anymore.
+fn restore_old_context_lines(
+    repo: &git2::Repository,
+    diff: &git2::Diff,
+    files: &mut DiffByPathMap,
+) -> Result<()> {
+    const NO_NEWLINE_AT_EOF: &[u8] = b"\\ No newline at end of file\n";
+    for delta in diff.deltas() {
+        if !matches!(
+            delta.status(),
+            git2::Delta::Modified | git2::Delta::Renamed | git2::Delta::Copied
+        ) || delta.old_file().is_binary()
+            || delta.new_file().is_binary()
+        {
+            continue;
+        }
+        let Some(file) = delta.new_file().path().and_then(|path| files.get_mut(path)) else {
+            continue;
+        };
+        let old_blob = repo
+            .find_blob(delta.old_file().id())
+            .context("failed to find the blob of the old file")?;
+        let old_lines: Vec<_> = old_blob.content().lines_with_terminator().collect();
+
+        for hunk in file.hunks.iter_mut().filter(|hunk| !hunk.binary) {
+            let mut old_line_idx = (hunk.old_start as usize).saturating_sub(1);
+            let mut skip_no_newline_marker = false;
+            let mut diff_lines = BString::default();
+            for line in hunk.diff_lines.lines_with_terminator() {
+                match line.first() {
+                    Some(b'\\') if skip_no_newline_marker => {}
+                    Some(b' ') => {
+                        let old_line = old_lines.get(old_line_idx).with_context(|| {
+                            format!(
+                                "context line {} is out of bounds of the old file",
+                                old_line_idx + 1
+                            )
+                        })?;
+                        old_line_idx += 1;
+                        diff_lines.push_byte(b' ');
+                        diff_lines.push_str(old_line);
+                        if !old_line.ends_with(b"\n") {
+                            diff_lines.push_byte(b'\n');
+                            diff_lines.push_str(NO_NEWLINE_AT_EOF);
+                        }
+                        skip_no_newline_marker = true;
+                        continue;
+                    }
+                    Some(b'-') => {
+                        old_line_idx += 1;
+                        diff_lines.push_str(line);
+                    }
+                    _ => diff_lines.push_str(line),
+                }
+                skip_no_newline_marker = false;
+            }
+            hunk.diff_lines = diff_lines.into();
+        }
+    }
+    Ok(())
+}
 
-    let diff = repo.diff_tree_to_tree(Some(old_tree), Some(new_tree), Some(&mut diff_opts))?;
-    hunks_by_filepath(None, &diff)
+/// Turn deletions and additions of similar files in `diff` into renames, and possibly copies, as configured in `renames`.
+fn find_renames(diff: &mut git2::Diff, renames: RenameOptions) -> Result<()> {
+    let threshold = renames.threshold.min(100);
+    let mut find_opts = git2::DiffFindOptions::new();
+    find_opts
+        .renames(true)
+        .rename_threshold(threshold)
+        .copies(renames.copies)
+        .copy_threshold(threshold)
+        .for_untracked(true);
+    diff.find_similar(Some(&mut find_opts))
+        .context("failed to detect renames")?;
+    Ok(())
 }
 
 /// Transform `diff` into a mapping of `worktree-relative path -> FileDiff`, where `FileDiff` is
@@ -197,12 +688,17 @@ pub fn hunks_by_filepath(
     }
     // find all the hunks
     let mut diff_files = HashMap::new();
+    // new path -> (change type, old path) of all renamed or copied files
+    let mut moved_files = HashMap::new();
     let mut err = None;
 
     diff.print(
         git2::DiffFormat::Patch,
         |delta, hunk, line: git2::DiffLine<'_>| {
             let change_type: ChangeType = delta.status().into();
+            let old_path = matches!(change_type, ChangeType::Renamed | ChangeType::Copied)
+                .then(|| delta.old_file().path().map(ToOwned::to_owned))
+                .flatten();
             let file_path = delta.new_file().path().unwrap_or_else(|| {
                 delta
                     .old_file()
@@ -247,6 +743,9 @@ pub fn hunks_by_filepath(
 
             match line {
                 None => {
+                    if let Some(old_path) = &old_path {
+                        moved_files.insert(file_path.to_path_buf(), (change_type, old_path.clone()));
+                    }
                     let existing = diff_files
                         .insert(file_path.to_path_buf(),
                             FileDiff {
@@ -277,7 +776,7 @@ pub fn hunks_by_filepath(
                                 hunk.diff_lines.push_str(line.as_ref());
                             }
                             LineOrHexHash::HexHashOfBinaryBlob(id) => {
-                                let marker =  GitHunk::binary_marker(id, hunk.change_type) ;
+                                let marker =  GitHunk::binary_marker(id, hunk.change_type, hunk.old_path.take()) ;
                                 *hunk = marker;
                             }
                         },
@@ -292,10 +791,13 @@ pub fn hunks_by_filepath(
                                         diff_lines: line.into_owned().into(),
                                         binary: false,
                                         change_type,
+                                        old_path,
+                                        changed_tokens: None,
+                                        content_info: None,
                                     }
                                 }
                                 LineOrHexHash::HexHashOfBinaryBlob(id) => {
-                                    GitHunk::binary_marker(id, change_type)
+                                    GitHunk::binary_marker(id, change_type, old_path)
                                 }
                             };
                             hunks.push(new_hunk);
@@ -309,24 +811,112 @@ pub fn hunks_by_filepath(
     .with_context(|| format!("failed to print diff: {err:?}"))?;
 
     for file in diff_files.values_mut() {
-        if let Some(binary_hunk) = file
+        if let Some(mut binary_hunk) = file
             .hunks
             .iter()
             .find_map(|hunk| hunk.binary.then(|| hunk.clone()))
         {
-            if file.hunks.len() > 1 {
-                // TODO(ST): needs tests, this code isn't executed yet.
-                // if there are multiple hunks with binary among them, we replace it with a single marker.
-                file.hunks = vec![binary_hunk];
-            }
+            binary_hunk.content_info = Some(ContentInfo::binary(
+                (binary_hunk.change_type != ChangeType::Added).then_some(file.old_size_bytes),
+                (binary_hunk.change_type != ChangeType::Deleted).then_some(file.new_size_bytes),
+                None,
+            ));
+            // if there are multiple hunks with binary among them, we replace it with a single marker.
+            file.hunks = vec![binary_hunk];
+        } else if let [hunk] = file.hunks.as_mut_slice() {
+            hunk.content_info = lfs_pointer_change(hunk);
         } else if file.hunks.is_empty() {
-            file.hunks = vec![GitHunk::generic_new_file()];
+            // A rename or copy without changes to the content has no hunks, but its origin must be kept.
+            let moved_from = file
+                .new_path
+                .as_ref()
+                .and_then(|new_path| moved_files.remove(new_path));
+            file.hunks = vec![match moved_from {
+                Some((change_type, old_path)) => GitHunk::generic_moved_file(change_type, old_path),
+                None => GitHunk::generic_new_file(),
+            }];
         }
     }
 
     Ok(diff_files)
 }
 
+/// Return the change of LFS pointers in `hunk`, if it is a hunk that spans an entire pointer file.
+fn lfs_pointer_change(hunk: &GitHunk) -> Option<ContentInfo> {
+    if hunk.old_start > 1 || hunk.new_start > 1 {
+        return None;
+    }
+    let (mut old, mut new) = (BString::default(), BString::default());
+    for line in hunk.diff_lines.lines_with_terminator().skip(1) {
+        match line.first() {
+            Some(b' ') => {
+                old.push_str(&line[1..]);
+                new.push_str(&line[1..]);
+            }
+            Some(b'-') => old.push_str(&line[1..]),
+            Some(b'+') => new.push_str(&line[1..]),
+            _ => {}
+        }
+    }
+    let old = LfsPointer::from_bytes(&old);
+    let new = LfsPointer::from_bytes(&new);
+    (old.is_some() || new.is_some()).then(|| ContentInfo::lfs(old.as_ref(), new.as_ref()))
+}
+
+/// Describe the binary files in `files` by the MIME type of their content, which is looked up in `repo`
+/// or in its worktree, as `diff` indicates.
+///
+/// Binary content that replaced an LFS pointer is the LFS object the pointer was smudged into,
+/// so it's described as change of the LFS object.
+pub fn describe_binary_files(
+    repo: &git2::Repository,
+    diff: &git2::Diff,
+    files: &mut DiffByPathMap,
+) -> Result<()> {
+    /// Enough to identify all formats by their magic bytes.
+    const SNIFF_LEN: usize = 8192;
+    for delta in diff.deltas() {
+        let (side, path) = match (delta.new_file().path(), delta.old_file().path()) {
+            (Some(path), _) if delta.status() != git2::Delta::Deleted => (delta.new_file(), path),
+            (_, Some(path)) => (delta.old_file(), path),
+            _ => continue,
+        };
+        let Some(info) = files
+            .get_mut(path)
+            .and_then(|file| file.hunks.iter_mut().find(|hunk| hunk.binary))
+            .and_then(|hunk| hunk.content_info.as_mut())
+        else {
+            continue;
+        };
+        let head = match repo.find_blob(side.id()) {
+            Ok(blob) => Some(blob.content()[..blob.size().min(SNIFF_LEN)].to_vec()),
+            Err(_) => worktree_file_head(repo, path, SNIFF_LEN),
+        };
+        let mime_type = head
+            .as_deref()
+            .and_then(infer::get)
+            .map(|kind| kind.mime_type().to_owned());
+        let old_pointer = (delta.status() == git2::Delta::Modified)
+            .then(|| repo.find_blob(delta.old_file().id()))
+            .and_then(Result::ok)
+            .and_then(|blob| LfsPointer::from_bytes(blob.content()));
+        *info = ContentInfo::new(
+            old_pointer.is_some(),
+            old_pointer.map_or(info.old_size_bytes, |pointer| Some(pointer.size)),
+            info.new_size_bytes,
+            mime_type,
+        );
+    }
+    Ok(())
+}
+
+fn worktree_file_head(repo: &git2::Repository, path: &Path, len: usize) -> Option<Vec<u8>> {
+    let file = fs::File::open(repo.workdir()?.join(path)).ok()?;
+    let mut head = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut head).ok()?;
+    Some(head)
+}
+
 // returns None if it cannot reverse the patch header
 fn reverse_patch_header(header: &BStr) -> Option<BString> {
     let mut parts = header.split(|b| b.is_ascii_whitespace());
@@ -398,6 +988,16 @@ pub fn reverse_hunk(hunk: &GitHunk) -> Option<GitHunk> {
             diff_lines: diff.into(),
             binary: hunk.binary,
             change_type: hunk.change_type,
+            old_path: hunk.old_path.clone(),
+            changed_tokens: None,
+            content_info: hunk.content_info.as_ref().map(|info| {
+                ContentInfo::new(
+                    info.lfs,
+                    info.new_size_bytes,
+                    info.old_size_bytes,
+                    info.mime_type.clone(),
+                )
+            }),
         })
     }
 }
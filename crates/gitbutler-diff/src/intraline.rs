@@ -0,0 +1,186 @@
+//! Find the words or characters that changed within the lines of a hunk, so they can be highlighted.
+//!
+//! Each block of removed lines that is directly followed by a block of added lines is split into tokens,
+//! which are then diffed against each other with `gix`'s blob diff. Lines that were only added or only
+//! removed have no counterpart and thus no changed tokens.
+use std::ops::Range;
+
+use bstr::{BStr, ByteSlice};
+use gix::diff::blob::{diff, intern::TokenSource, Algorithm, InternedInput};
+use serde::Serialize;
+
+use crate::DiffByPathMap;
+
+/// The granularity at which the changes within modified lines are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// Changed words, along with changed whitespace and punctuation.
+    Word,
+    /// Changed characters.
+    Char,
+}
+
+/// A range of bytes in a line of a hunk's diff that differs from its counterpart on the other side.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRange {
+    /// The 0-based index of the line in the hunk's diff, with the `@@` header being the first line.
+    pub line: u32,
+    /// The offset of the first changed byte, not counting the `+` or `-` prefix of the line.
+    pub start: u32,
+    /// The offset one past the last changed byte.
+    pub end: u32,
+}
+
+/// Compute the changed tokens of all non-binary hunks in `files` and store them in
+/// [`GitHunk::changed_tokens`](crate::GitHunk::changed_tokens).
+pub fn annotate(files: &mut DiffByPathMap, granularity: Granularity) {
+    for hunk in files
+        .values_mut()
+        .flat_map(|file| file.hunks.iter_mut())
+        .filter(|hunk| !hunk.binary)
+    {
+        hunk.changed_tokens = Some(changed_tokens(hunk.diff_lines.as_ref(), granularity));
+    }
+}
+
+/// Return the ranges of all tokens in `diff`, the lines of a hunk, that were changed at the given `granularity`,
+/// ordered by line and offset.
+pub fn changed_tokens(diff: &BStr, granularity: Granularity) -> Vec<TokenRange> {
+    let lines: Vec<&[u8]> = diff.lines().collect();
+    let mut ranges = Vec::new();
+    let mut idx = 0;
+    while idx < lines.len() {
+        let removed_start = idx;
+        while idx < lines.len() && lines[idx].starts_with(b"-") {
+            idx += 1;
+        }
+        let added_start = idx;
+        while idx < lines.len() && lines[idx].starts_with(b"+") {
+            idx += 1;
+        }
+        if removed_start < added_start && added_start < idx {
+            let removed = Tokens::new(&lines, removed_start..added_start, granularity);
+            let added = Tokens::new(&lines, added_start..idx, granularity);
+            let input = InternedInput::new(removed.source(), added.source());
+            diff(
+                Algorithm::Histogram,
+                &input,
+                |before: Range<u32>, after: Range<u32>| {
+                    removed.mark(before, &mut ranges);
+                    added.mark(after, &mut ranges);
+                },
+            );
+        }
+        if idx == removed_start {
+            idx += 1;
+        }
+    }
+    ranges.sort_by_key(|range| (range.line, range.start));
+    ranges
+}
+
+/// The tokens of a block of lines, along with the line and offset each of them starts at.
+struct Tokens<'a> {
+    tokens: Vec<&'a [u8]>,
+    /// `None` for the tokens separating lines, which are never part of a [`TokenRange`].
+    positions: Vec<Option<(u32, u32)>>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(lines: &[&'a [u8]], block: Range<usize>, granularity: Granularity) -> Self {
+        let mut tokens = Tokens {
+            tokens: Vec::new(),
+            positions: Vec::new(),
+        };
+        for line_idx in block {
+            // Skip the `+` or `-` prefix.
+            let content = &lines[line_idx][1..];
+            let mut offset = 0;
+            let mut push = |token: &'a [u8]| {
+                tokens.tokens.push(token);
+                tokens.positions.push(Some((line_idx as u32, offset)));
+                offset += token.len() as u32;
+            };
+            match granularity {
+                Granularity::Word => words(content).for_each(&mut push),
+                Granularity::Char => content
+                    .char_indices()
+                    .for_each(|(start, end, _)| push(&content[start..end])),
+            }
+            tokens.tokens.push(b"\n");
+            tokens.positions.push(None);
+        }
+        tokens
+    }
+
+    fn source(&self) -> TokenSlice<'_, 'a> {
+        TokenSlice(&self.tokens)
+    }
+
+    /// Add the tokens in `changed` to `ranges`, extending the last range if it ends where a token starts.
+    fn mark(&self, changed: Range<u32>, ranges: &mut Vec<TokenRange>) {
+        for idx in changed {
+            let idx = idx as usize;
+            let Some((line, start)) = self.positions[idx] else {
+                continue;
+            };
+            let end = start + self.tokens[idx].len() as u32;
+            match ranges.last_mut() {
+                Some(last) if last.line == line && last.end == start => last.end = end,
+                _ => ranges.push(TokenRange { line, start, end }),
+            }
+        }
+    }
+}
+
+/// Split `line` into runs of alphanumeric characters, runs of whitespace, and single other characters.
+fn words(line: &[u8]) -> impl Iterator<Item = &[u8]> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    fn class(byte: u8) -> Class {
+        // Bytes of multi-byte characters are considered part of words.
+        if byte.is_ascii_alphanumeric() || byte == b'_' || !byte.is_ascii() {
+            Class::Word
+        } else if byte.is_ascii_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    }
+
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        let first = class(*rest.first()?);
+        let len = if first == Class::Other {
+            1
+        } else {
+            rest.iter()
+                .position(|byte| class(*byte) != first)
+                .unwrap_or(rest.len())
+        };
+        let (token, tail) = rest.split_at(len);
+        rest = tail;
+        Some(token)
+    })
+}
+
+#[derive(Clone, Copy)]
+struct TokenSlice<'t, 'a>(&'t [&'a [u8]]);
+
+impl<'t, 'a> TokenSource for TokenSlice<'t, 'a> {
+    type Token = &'a [u8];
+    type Tokenizer = std::iter::Copied<std::slice::Iter<'t, &'a [u8]>>;
+
+    fn tokenize(&self) -> Self::Tokenizer {
+        self.0.iter().copied()
+    }
+
+    fn estimate_tokens(&self) -> u32 {
+        self.0.len() as u32
+    }
+}
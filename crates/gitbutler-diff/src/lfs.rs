@@ -0,0 +1,103 @@
+//! Support for files stored in [Git LFS](https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md).
+//!
+//! Repositories only contain small pointer files for these, while the actual content lives in the LFS store
+//! at `.git/lfs/objects`, and is put into the worktree by the `lfs` smudge filter which `git2` doesn't run.
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use bstr::ByteSlice;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Pointer files are never larger than this, which allows to skip reading larger files entirely.
+pub const MAX_POINTER_SIZE: u64 = 1024;
+
+const VERSION: &str = "https://git-lfs.github.com/spec/v1";
+/// The version used by pre-release versions of Git LFS, which is still valid.
+const LEGACY_VERSION: &str = "https://hawser.github.com/spec/v1";
+
+/// The content of an LFS pointer file.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LfsPointer {
+    /// The hex-encoded SHA-256 of the object.
+    pub oid: String,
+    /// The size of the object in bytes.
+    pub size: u64,
+}
+
+impl LfsPointer {
+    /// Parse `content` as pointer file, or return `None` if it isn't one.
+    pub fn from_bytes(content: &[u8]) -> Option<Self> {
+        if content.len() as u64 > MAX_POINTER_SIZE {
+            return None;
+        }
+        let mut lines = content.lines();
+        let version = lines.next()?.strip_prefix(b"version ")?;
+        if version != VERSION.as_bytes() && version != LEGACY_VERSION.as_bytes() {
+            return None;
+        }
+        let (mut oid, mut size) = (None, None);
+        for line in lines {
+            let (key, value) = line.split_once_str(" ")?;
+            match key {
+                b"oid" => {
+                    let hex = value.strip_prefix(b"sha256:")?;
+                    if hex.len() != 64 || !hex.iter().all(u8::is_ascii_hexdigit) {
+                        return None;
+                    }
+                    oid = Some(hex.to_str().ok()?.to_owned());
+                }
+                b"size" => size = Some(value.to_str().ok()?.parse().ok()?),
+                // Extensions and keys of later versions.
+                _ => {}
+            }
+        }
+        Some(LfsPointer {
+            oid: oid?,
+            size: size?,
+        })
+    }
+
+    /// Return the pointer for an object with `content`.
+    pub fn for_content(content: &[u8]) -> Self {
+        LfsPointer {
+            oid: hex::encode(Sha256::digest(content)),
+            size: content.len() as u64,
+        }
+    }
+
+    /// Return the content of the pointer file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        format!(
+            "version {VERSION}\noid sha256:{}\nsize {}\n",
+            self.oid, self.size
+        )
+        .into_bytes()
+    }
+
+    /// Return the path at which the object is kept in the LFS store of the repository at `git_dir`.
+    pub fn object_path(&self, git_dir: &Path) -> PathBuf {
+        git_dir
+            .join("lfs")
+            .join("objects")
+            .join(&self.oid[..2])
+            .join(&self.oid[2..4])
+            .join(&self.oid)
+    }
+}
+
+/// Return `true` if the worktree-relative `path` is configured to be stored in LFS by `.gitattributes`.
+pub fn is_tracked(repo: &git2::Repository, path: &Path) -> Result<bool> {
+    Ok(repo.get_attr(path, "filter", git2::AttrCheckFlags::FILE_THEN_INDEX)? == Some("lfs"))
+}
+
+/// Put `content` into the LFS store of `repo`, like the `lfs` clean filter would, and return its pointer.
+pub fn store(repo: &git2::Repository, content: &[u8]) -> Result<LfsPointer> {
+    let pointer = LfsPointer::for_content(content);
+    let object_path = pointer.object_path(repo.path());
+    if !object_path.exists() {
+        gitbutler_fs::create_dirs_then_write(&object_path, content)?;
+    }
+    Ok(pointer)
+}
@@ -1,8 +1,11 @@
 mod diff;
 mod hunk;
+pub mod intraline;
+pub mod lfs;
 pub mod write;
 pub use diff::{
-    diff_files_into_hunks, hunks_by_filepath, reverse_hunk, trees, workdir, ChangeType,
-    DiffByPathMap, FileDiff, GitHunk,
+    describe_binary_files, diff_files_into_hunks, hunks_by_filepath, reverse_hunk, trees, workdir,
+    workdir_with_whitespace, ChangeType, ContentInfo, DiffByPathMap, DiffSettings, FileDiff,
+    GitHunk, IgnoreWhitespace, RenameOptions,
 };
 pub use hunk::{Hunk, HunkHash};
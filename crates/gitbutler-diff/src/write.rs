@@ -1,6 +1,9 @@
 #[cfg(target_family = "unix")]
 use std::os::unix::prelude::PermissionsExt;
-use std::{borrow::Borrow, path::PathBuf};
+use std::{
+    borrow::Borrow,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Context, Result};
 use bstr::{BString, ByteSlice, ByteVec};
@@ -8,7 +11,7 @@ use diffy::{apply_bytes as diffy_apply, Line, Patch};
 use gitbutler_command_context::CommandContext;
 use hex::ToHex;
 
-use crate::GitHunk;
+use crate::{ChangeType, GitHunk};
 
 // this function takes a list of file ownership,
 // constructs a tree from those changes on top of the target
@@ -49,13 +52,42 @@ pub fn hunks_onto_tree<T>(
 where
     T: Into<GitHunk> + Clone,
 {
-    let git_repository = ctx.repository();
+    hunks_onto_tree_in(
+        ctx.repository(),
+        &ctx.project().worktree_path(),
+        base_tree,
+        files,
+    )
+}
+
+/// Like [`hunks_onto_tree()`], but write all objects to `git_repository`, which may keep them in memory,
+/// and read files that aren't fully described by their hunks from `worktree_path`.
+pub fn hunks_onto_tree_in<T>(
+    git_repository: &git2::Repository,
+    worktree_path: &Path,
+    base_tree: &git2::Tree,
+    files: impl IntoIterator<Item = (impl Borrow<PathBuf>, impl Borrow<Vec<T>>)>,
+) -> Result<git2::Oid>
+where
+    T: Into<GitHunk> + Clone,
+{
     let mut builder = git2::build::TreeUpdateBuilder::new();
     // now update the index with content in the working directory for each file
     for (rel_path, hunks) in files {
         let rel_path = rel_path.borrow();
         let hunks: Vec<GitHunk> = hunks.borrow().iter().map(|h| h.clone().into()).collect();
-        let full_path = ctx.project().worktree_path().join(rel_path);
+        let full_path = worktree_path.join(rel_path);
+
+        // A renamed file replaces the file it was moved from.
+        if let Some(old_path) = hunks
+            .iter()
+            .filter(|hunk| hunk.change_type == ChangeType::Renamed)
+            .find_map(|hunk| hunk.old_path.as_ref())
+        {
+            if base_tree.get_path(old_path).is_ok() && !worktree_path.join(old_path).exists() {
+                builder.remove(old_path);
+            }
+        }
 
         let is_submodule = full_path.is_dir()
             && hunks.len() == 1
@@ -102,7 +134,7 @@ where
 
                 // if the link target is inside the project repository, make it relative
                 let link_target = link_target
-                    .strip_prefix(ctx.project().worktree_path())
+                    .strip_prefix(worktree_path)
                     .unwrap_or(&link_target);
 
                 let blob_oid = git_repository.blob(
@@ -178,6 +210,28 @@ where
                 let new_blob_oid = git_repository.blob(blob_contents.as_bytes())?;
                 // upsert into the builder
                 builder.upsert(rel_path, new_blob_oid, filemode);
+            } else if let Some(base_contents) =
+                content_before_hunks(git_repository, base_tree, &hunks)?
+            {
+                // Hunks of added or renamed files may not have all of their lines, like when only
+                // some were selected, so the file on disk can't be used.
+                let mut hunks = hunks.iter().collect::<Vec<_>>();
+                hunks.sort_by_key(|hunk| hunk.new_start);
+                let mut all_diffs = BString::default();
+                for hunk in hunks {
+                    all_diffs.push_str(&hunk.diff_lines);
+                }
+                let patch = Patch::from_bytes(&all_diffs)?;
+                let blob_contents = apply(&base_contents, &patch).with_context(|| {
+                    format!(
+                        "failed to apply the hunks of {}:\n{}",
+                        rel_path.display(),
+                        all_diffs.as_bstr()
+                    )
+                })?;
+
+                let new_blob_oid = git_repository.blob(blob_contents.as_bytes())?;
+                builder.upsert(rel_path, new_blob_oid, filemode);
             } else {
                 // create a git blob from a file on disk
                 let blob_oid = git_repository
@@ -193,12 +247,45 @@ where
 
     // now write out the tree
     let tree_oid = builder
-        .create_updated(ctx.repository(), base_tree)
+        .create_updated(git_repository, base_tree)
         .context("failed to write updated tree")?;
 
     Ok(tree_oid)
 }
 
+/// Return the content that `hunks` of a file that isn't in `base_tree` apply to, which is nothing for added files
+/// and the file it was moved from for renamed ones, or `None` if the hunks can't be applied.
+fn content_before_hunks(
+    repo: &git2::Repository,
+    base_tree: &git2::Tree,
+    hunks: &[GitHunk],
+) -> Result<Option<Vec<u8>>> {
+    let Some(first_hunk) = hunks.first() else {
+        return Ok(None);
+    };
+    if hunks
+        .iter()
+        .any(|hunk| hunk.binary || hunk.diff_lines.is_empty())
+    {
+        return Ok(None);
+    }
+    Ok(match (first_hunk.change_type, &first_hunk.old_path) {
+        (ChangeType::Added, _) => Some(Vec::new()),
+        (ChangeType::Renamed, Some(old_path)) => match base_tree.get_path(old_path) {
+            Ok(entry) => Some(
+                entry
+                    .to_object(repo)?
+                    .peel_to_blob()
+                    .context("failed to get blob")?
+                    .content()
+                    .to_vec(),
+            ),
+            Err(_) => None,
+        },
+        _ => None,
+    })
+}
+
 /// Just like [`diffy::apply()`], but on error it will attach hashes of the input `base_image` and `patch`.
 pub fn apply<S: AsRef<[u8]>>(base_image: S, patch: &Patch<'_, [u8]>) -> Result<BString> {
     fn md5_hash_hex(b: impl AsRef<[u8]>) -> String {
@@ -1,4 +1,4 @@
-use gitbutler_diff::Hunk;
+use gitbutler_diff::{ChangeType, GitHunk, Hunk};
 
 #[test]
 fn to_from_string() {
@@ -123,3 +123,74 @@ fn eq() {
         assert_eq!(a == b, expected, "comparing {} and {}", a, b);
     }
 }
+
+#[test]
+fn git_hunk_adjacency() {
+    let hunk = |old_start, old_lines| GitHunk {
+        old_start,
+        old_lines,
+        new_start: old_start,
+        new_lines: old_lines,
+        diff_lines: Default::default(),
+        binary: false,
+        change_type: ChangeType::Modified,
+        old_path: None,
+        changed_tokens: None,
+        content_info: None,
+    };
+    assert!(hunk(1, 7).is_adjacent_to(&hunk(5, 7)), "overlapping");
+    assert!(
+        hunk(1, 5).is_adjacent_to(&hunk(9, 7)),
+        "context lines separated by three unchanged lines"
+    );
+    assert!(hunk(9, 7).is_adjacent_to(&hunk(1, 5)), "it's symmetric");
+    assert!(
+        !hunk(1, 5).is_adjacent_to(&hunk(10, 7)),
+        "context lines separated by four unchanged lines"
+    );
+}
+
+mod select_lines {
+    use gitbutler_diff::{ChangeType, GitHunk};
+
+    fn hunk() -> GitHunk {
+        GitHunk {
+            old_start: 1,
+            old_lines: 3,
+            new_start: 1,
+            new_lines: 3,
+            diff_lines: "@@ -1,3 +1,3 @@\n a\n-b\n-c\n+B\n+C\n".into(),
+            binary: false,
+            change_type: ChangeType::Modified,
+            old_path: None,
+            changed_tokens: None,
+            content_info: None,
+        }
+    }
+
+    #[test]
+    fn keeps_unselected_removals_as_context() {
+        let selected = hunk().select_lines(&[2, 4]).unwrap();
+        assert_eq!(selected.diff_lines, "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+        assert_eq!((selected.old_lines, selected.new_lines), (3, 3));
+    }
+
+    #[test]
+    fn drops_unselected_additions() {
+        let selected = hunk().select_lines(&[2, 3]).unwrap();
+        assert_eq!(selected.diff_lines, "@@ -1,3 +1,1 @@\n a\n-b\n-c\n");
+        assert_eq!((selected.old_lines, selected.new_lines), (3, 1));
+
+        let selected = hunk().select_lines(&[5]).unwrap();
+        assert_eq!(selected.diff_lines, "@@ -1,3 +1,4 @@\n a\n b\n c\n+C\n");
+        assert_eq!((selected.old_lines, selected.new_lines), (3, 4));
+    }
+
+    #[test]
+    fn rejects_context_and_header() {
+        hunk().select_lines(&[1]).unwrap_err();
+        hunk().select_lines(&[0]).unwrap_err();
+        hunk().select_lines(&[7]).unwrap_err();
+        hunk().select_lines(&[]).unwrap_err();
+    }
+}
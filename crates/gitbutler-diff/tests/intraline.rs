@@ -0,0 +1,44 @@
+use bstr::BStr;
+use gitbutler_diff::intraline::{changed_tokens, Granularity, TokenRange};
+
+fn ranges(diff: &str, granularity: Granularity) -> Vec<(u32, String)> {
+    let lines: Vec<&str> = diff.lines().collect();
+    changed_tokens(BStr::new(diff), granularity)
+        .into_iter()
+        .map(|TokenRange { line, start, end }| {
+            let content = &lines[line as usize][1..];
+            (line, content[start as usize..end as usize].to_owned())
+        })
+        .collect()
+}
+
+#[test]
+fn changed_words() {
+    let diff =
+        "@@ -1,2 +1,2 @@\n context\n-let value = compute(a);\n+let result = compute(a, b);\n";
+    assert_eq!(
+        ranges(diff, Granularity::Word),
+        [(2, "value".into()), (3, "result".into()), (3, ", b".into())]
+    );
+}
+
+#[test]
+fn changed_chars() {
+    let diff = "@@ -1 +1 @@\n-colour\n+color\n";
+    assert_eq!(ranges(diff, Granularity::Char), [(1, "u".into())]);
+}
+
+#[test]
+fn only_lines_with_counterparts_have_changed_tokens() {
+    let diff = "@@ -1,2 +1,2 @@\n+added\n context\n-removed\n";
+    assert!(changed_tokens(BStr::new(diff), Granularity::Word).is_empty());
+}
+
+#[test]
+fn blocks_of_lines_are_diffed_as_a_whole() {
+    let diff = "@@ -1,2 +1,2 @@\n-one two\n-three four\n+one 2\n+three four\n";
+    assert_eq!(
+        ranges(diff, Granularity::Word),
+        [(1, "two".into()), (3, "2".into())]
+    );
+}
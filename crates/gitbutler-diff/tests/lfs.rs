@@ -0,0 +1,68 @@
+use gitbutler_diff::{lfs::LfsPointer, ContentInfo};
+
+const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+#[test]
+fn parse_pointer() {
+    let content =
+        format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize 12345\n");
+    assert_eq!(
+        LfsPointer::from_bytes(content.as_bytes()),
+        Some(LfsPointer {
+            oid: OID.into(),
+            size: 12345
+        })
+    );
+}
+
+#[test]
+fn parse_invalid_pointer() {
+    for content in [
+        "just some text\n".to_string(),
+        format!("oid sha256:{OID}\nsize 12345\n"),
+        format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\n"),
+        "version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 1\n".into(),
+        format!("version https://git-lfs.github.com/spec/v1\noid sha256:{OID}\nsize many\n"),
+    ] {
+        assert_eq!(
+            LfsPointer::from_bytes(content.as_bytes()),
+            None,
+            "{content}"
+        );
+    }
+}
+
+#[test]
+fn pointer_for_content_round_trips() {
+    let pointer = LfsPointer::for_content(b"hello world");
+    assert_eq!(
+        pointer.oid,
+        "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+    );
+    assert_eq!(pointer.size, 11);
+    assert_eq!(LfsPointer::from_bytes(&pointer.to_bytes()), Some(pointer));
+}
+
+#[test]
+fn summaries() {
+    let old = LfsPointer {
+        oid: OID.into(),
+        size: 12,
+    };
+    let new = LfsPointer {
+        oid: OID.into(),
+        size: 3 * 1024 * 1024 + 512 * 1024,
+    };
+    assert_eq!(
+        ContentInfo::lfs(Some(&old), Some(&new)).summary,
+        "LFS object changed (12 B → 3.5 MiB)"
+    );
+    assert_eq!(
+        ContentInfo::lfs(None, Some(&new)).summary,
+        "LFS object added (3.5 MiB)"
+    );
+    assert_eq!(
+        ContentInfo::binary(Some(2048), None, Some("image/png".into())).summary,
+        "Binary file removed (image/png, 2.0 KiB)"
+    );
+}
@@ -1 +1,3 @@
 pub mod hunk;
+mod intraline;
+mod lfs;
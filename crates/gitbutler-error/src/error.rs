@@ -132,6 +132,10 @@ pub enum Code {
     DefaultTargetNotFound,
     CommitSigningFailed,
     CommitHookFailed,
+    /// A git hook exited unsuccessfully, with its output being part of the error message.
+    HookRejected,
+    /// A commit message violates the message policy configured for the repository.
+    CommitMessagePolicy,
     CommitMergeConflictFailure,
     ProjectMissing,
     AuthorMissing,
@@ -146,6 +150,8 @@ impl std::fmt::Display for Code {
             Code::DefaultTargetNotFound => "errors.projects.default_target.not_found",
             Code::CommitSigningFailed => "errors.commit.signing_failed",
             Code::CommitHookFailed => "errors.commit.hook_failed",
+            Code::HookRejected => "errors.git.hook_rejected",
+            Code::CommitMessagePolicy => "errors.commit.message_policy",
             Code::CommitMergeConflictFailure => "errors.commit.merge_conflict_failure",
             Code::AuthorMissing => "errors.git.author_missing",
             Code::ProjectMissing => "errors.projects.missing",
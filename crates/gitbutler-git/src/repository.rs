@@ -336,12 +336,17 @@ where
 /// Any prompts for the user are passed to the asynchronous callback `on_prompt`,
 /// which should return the user's response or `None` if the operation should be
 /// aborted, in which case an `Err` value is returned from this function.
+///
+/// If `force_with_lease` is set to the object the destination of `refspec` is expected
+/// to point to on the remote, the push may rewrite it, but fails if it points elsewhere.
+#[allow(clippy::too_many_arguments)]
 pub async fn push<P, F, Fut, E, Extra>(
     repo_path: P,
     executor: E,
     remote: &str,
-    refspec: RefSpec,
+    mut refspec: RefSpec,
     force: bool,
+    force_with_lease: Option<String>,
     on_prompt: F,
     extra: Extra,
 ) -> Result<(), crate::Error<Error<E>>>
@@ -354,12 +359,23 @@ where
 {
     let mut args = vec!["push", "--quiet"];
 
+    let lease = match force_with_lease {
+        Some(expected) => {
+            let destination = refspec.destination.as_deref().unwrap_or_default();
+            // A forced refspec would ignore the lease.
+            refspec.update_non_fastforward = false;
+            Some(format!("--force-with-lease={destination}:{expected}"))
+        }
+        None => None,
+    };
     let refspec = refspec.to_string();
 
     args.push(remote);
     args.push(&refspec);
 
-    if force {
+    if let Some(lease) = &lease {
+        args.push(lease);
+    } else if force {
         args.push("--force");
     }
 
@@ -0,0 +1,280 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use gitbutler_branch::VirtualBranchesHandle;
+use gitbutler_project::{
+    access::{WorktreeReadPermission, WorktreeWritePermission},
+    Project,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entry::{OperationKind, SnapshotDetails},
+    oplog::reparent_commit,
+    reflog::set_reference_to_oplog,
+    state::OplogHandle,
+};
+
+/// The only reference in an oplog bundle, pointing to its most recent snapshot.
+const BUNDLE_REF: &str = "refs/gitbutler/oplog-bundle";
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Describes the snapshots in a bundle written by [`OplogExt::export_snapshots()`](crate::OplogExt::export_snapshots()).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OplogBundleManifest {
+    /// The version of the bundle format.
+    pub version: u32,
+    /// The most recent snapshot in the bundle, which is what its only reference points to.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub head: git2::Oid,
+    /// The snapshots in the bundle, most recent first.
+    pub snapshots: Vec<OplogBundleEntry>,
+}
+
+/// A snapshot in an oplog bundle.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OplogBundleEntry {
+    /// The id of the snapshot commit in the bundle.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub id: git2::Oid,
+    /// The id of the snapshot commit in the exporting repository, which differs from `id` if it had to be rewritten.
+    #[serde(with = "gitbutler_serde::oid")]
+    pub original_id: git2::Oid,
+    /// The operation that was performed just before the snapshot was created.
+    pub operation: OperationKind,
+    /// The title of the snapshot.
+    pub title: String,
+    /// Snapshot creation time in seconds since the Unix epoch.
+    pub created_at: i64,
+}
+
+impl OplogBundleManifest {
+    /// Returns the path of the manifest that belongs to the bundle at `bundle_path`.
+    ///
+    /// The suffix is appended to the whole file name, so each bundle has its own manifest, whatever its extension.
+    pub fn path_for(bundle_path: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.manifest.toml", bundle_path.display()))
+    }
+}
+
+pub(crate) fn export_snapshots(
+    ctx: &Project,
+    bundle_path: &Path,
+    head: Option<git2::Oid>,
+    oldest: Option<git2::Oid>,
+    _shared_access: &WorktreeReadPermission,
+) -> Result<OplogBundleManifest> {
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let head_id = match head {
+        Some(id) => id,
+        None => OplogHandle::new(&ctx.gb_dir())
+            .oplog_head()?
+            .context("There are no snapshots to export")?,
+    };
+
+    // The snapshots to export, most recent first.
+    let mut chain = Vec::new();
+    let mut next = Some(repo.find_commit(head_id)?);
+    while let Some(commit) = next.take() {
+        if commit.parent_count() > 1 {
+            bail!(
+                "Snapshot {} has more than one parent and can't be exported",
+                commit.id()
+            );
+        }
+        let is_oldest = oldest == Some(commit.id());
+        next = commit.parent(0).ok().filter(|_| !is_oldest);
+        chain.push(commit);
+    }
+    if let Some(oldest) = oldest {
+        if chain.last().map(|commit| commit.id()) != Some(oldest) {
+            bail!("Snapshot {oldest} isn't reachable from {head_id}");
+        }
+    }
+
+    // Rewrite the chain unless it starts at a root, to make the bundle self-contained.
+    let mut ids = Vec::with_capacity(chain.len());
+    let mut parent_id = None;
+    for commit in chain.iter().rev() {
+        let id = if commit.parent_id(0).ok() == parent_id {
+            commit.id()
+        } else {
+            reparent_commit(&repo, commit, parent_id)?
+        };
+        ids.push(id);
+        parent_id = Some(id);
+    }
+    ids.reverse();
+
+    let snapshots = chain
+        .iter()
+        .zip(ids.iter())
+        .map(|(commit, id)| {
+            let details = snapshot_details(commit)?;
+            Ok(OplogBundleEntry {
+                id: *id,
+                original_id: commit.id(),
+                operation: details.operation,
+                title: details.title,
+                created_at: commit.time().seconds(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let manifest = OplogBundleManifest {
+        version: BUNDLE_FORMAT_VERSION,
+        head: ids[0],
+        snapshots,
+    };
+
+    let bundle_path = absolute_path(bundle_path)?;
+    let mut bundle_ref = repo.reference(BUNDLE_REF, manifest.head, true, "oplog export")?;
+    let res = git(
+        &ctx.path,
+        [
+            OsStr::new("bundle"),
+            OsStr::new("create"),
+            bundle_path.as_os_str(),
+            OsStr::new(BUNDLE_REF),
+        ],
+    );
+    bundle_ref.delete()?;
+    res?;
+
+    gitbutler_fs::write(
+        OplogBundleManifest::path_for(&bundle_path),
+        toml::to_string(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+pub(crate) fn import_snapshots(
+    ctx: &Project,
+    bundle_path: &Path,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Vec<git2::Oid>> {
+    let manifest_path = OplogBundleManifest::path_for(bundle_path);
+    let manifest: OplogBundleManifest = toml::from_str(
+        &std::fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Could not read manifest at {}", manifest_path.display()))?,
+    )?;
+    if manifest.version != BUNDLE_FORMAT_VERSION {
+        bail!(
+            "Oplog bundle format version {} isn't supported, expected {BUNDLE_FORMAT_VERSION}",
+            manifest.version
+        );
+    }
+
+    let bundle_path = absolute_path(bundle_path)?;
+    let unbundled = git(
+        &ctx.path,
+        [
+            OsStr::new("bundle"),
+            OsStr::new("unbundle"),
+            bundle_path.as_os_str(),
+        ],
+    )?;
+    let bundle_head = unbundled
+        .lines()
+        .find_map(|line| line.strip_suffix(BUNDLE_REF))
+        .map(|id| git2::Oid::from_str(id.trim()))
+        .transpose()?
+        .context("The bundle doesn't contain oplog snapshots")?;
+    if bundle_head != manifest.head {
+        bail!(
+            "The bundle points to {bundle_head}, but its manifest expects {}",
+            manifest.head
+        );
+    }
+
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let mut chain = Vec::new();
+    let mut next = Some(repo.find_commit(bundle_head)?);
+    while let Some(commit) = next.take() {
+        let expected = manifest.snapshots.get(chain.len()).with_context(|| {
+            format!(
+                "The bundle contains more snapshots than the {} in its manifest",
+                manifest.snapshots.len()
+            )
+        })?;
+        if commit.id() != expected.id || commit.parent_count() > 1 {
+            bail!(
+                "Bundle commit {} doesn't match snapshot {} in the manifest",
+                commit.id(),
+                expected.id
+            );
+        }
+        if commit.tree()?.get_name("virtual_branches.toml").is_none() {
+            bail!("Bundle commit {} isn't a snapshot", commit.id());
+        }
+        snapshot_details(&commit)?;
+        next = commit.parent(0).ok();
+        chain.push(commit);
+    }
+    if chain.len() != manifest.snapshots.len() {
+        bail!(
+            "The bundle contains {} snapshots, but its manifest lists {}",
+            chain.len(),
+            manifest.snapshots.len()
+        );
+    }
+
+    let oplog_state = OplogHandle::new(&ctx.gb_dir());
+    let mut parent_id = oplog_state.oplog_head()?;
+    let mut imported = Vec::with_capacity(chain.len());
+    for commit in chain.iter().rev() {
+        let id = reparent_commit(&repo, commit, parent_id)?;
+        imported.push(id);
+        parent_id = Some(id);
+    }
+    imported.reverse();
+
+    let new_oplog_head_id = imported[0];
+    oplog_state.set_oplog_head(new_oplog_head_id)?;
+    let vb_state = VirtualBranchesHandle::new(ctx.gb_dir());
+    let target_commit_id = vb_state.get_default_target()?.sha;
+    set_reference_to_oplog(&ctx.path, target_commit_id, new_oplog_head_id)?;
+
+    Ok(imported)
+}
+
+fn snapshot_details(commit: &git2::Commit<'_>) -> Result<SnapshotDetails> {
+    commit
+        .message()
+        .context("Snapshot message isn't valid UTF-8")
+        .and_then(SnapshotDetails::from_str)
+        .with_context(|| format!("Commit {} doesn't have valid snapshot details", commit.id()))
+}
+
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    Ok(if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir()?.join(path)
+    })
+}
+
+/// Run `git` with `args` in `worktree_dir` and return its standard output.
+fn git<'a>(worktree_dir: &Path, args: impl IntoIterator<Item = &'a OsStr>) -> Result<String> {
+    let mut cmd = std::process::Command::new(gix::path::env::exe_invocation());
+    cmd.current_dir(worktree_dir).args(args);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = cmd.output().context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
@@ -31,6 +31,57 @@ pub struct Snapshot {
     pub details: Option<SnapshotDetails>,
 }
 
+/// Criteria for selecting snapshots with [`OplogExt::query_snapshots()`](crate::OplogExt::query_snapshots()).
+///
+/// All criteria that are set have to match for a snapshot to be selected, so the default query matches all snapshots.
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapshotQuery {
+    /// If not empty, the snapshot operation has to be one of these.
+    pub operations: Vec<OperationKind>,
+    /// Each of these trailers has to be present on the snapshot, with the same key and value.
+    pub trailers: Vec<Trailer>,
+    /// If set, the snapshot must have been created at or after this time, in seconds since the Unix epoch.
+    pub since: Option<i64>,
+    /// If set, the snapshot must have been created at or before this time, in seconds since the Unix epoch.
+    pub until: Option<i64>,
+    /// If set, the snapshot must have changed this worktree-relative path, or a path inside of it if it's a directory.
+    pub path: Option<PathBuf>,
+}
+
+impl SnapshotQuery {
+    /// Return `true` if `details` match the operation and trailer criteria.
+    /// Snapshots with unparsable details only match if no such criteria are set.
+    pub fn matches_details(&self, details: Option<&SnapshotDetails>) -> bool {
+        if self.operations.is_empty() && self.trailers.is_empty() {
+            return true;
+        }
+        let Some(details) = details else {
+            return false;
+        };
+        (self.operations.is_empty() || self.operations.contains(&details.operation))
+            && self
+                .trailers
+                .iter()
+                .all(|wanted| details.trailers.contains(wanted))
+    }
+
+    /// Return `true` if `time`, in seconds since the Unix epoch, is within the time range criteria.
+    pub fn matches_time(&self, time: i64) -> bool {
+        self.since.map_or(true, |since| time >= since)
+            && self.until.map_or(true, |until| time <= until)
+    }
+
+    /// Return `true` if `files_changed` satisfy the path criteria.
+    pub fn matches_files(&self, files_changed: &[PathBuf]) -> bool {
+        self.path.as_ref().map_or(true, |path| {
+            files_changed
+                .iter()
+                .any(|changed| changed.starts_with(path))
+        })
+    }
+}
+
 /// The payload of a snapshot commit
 ///
 /// This is persisted as a commit message in the title, body and trailers format (<https://git-scm.com/docs/git-interpret-trailers>)
@@ -144,9 +195,11 @@ pub enum OperationKind {
     DiscardHunk,
     DiscardFile,
     AmendCommit,
+    Absorb,
     UndoCommit,
     UnapplyBranch,
     CherryPick,
+    RevertCommit,
     SquashCommit,
     UpdateCommitMessage,
     MoveCommit,
@@ -154,6 +207,8 @@ pub enum OperationKind {
     ReorderCommit,
     InsertBlankCommit,
     MoveCommitFile,
+    SplitCommit,
+    Autosquash,
     FileChanges,
     EnterEditMode,
     #[default]
@@ -196,7 +251,7 @@ impl FromStr for Version {
 
 /// Represents a key value pair stored in a snapshot, like `key: value\n`
 /// Using the git trailer format (<https://git-scm.com/docs/git-interpret-trailers>)
-#[derive(Debug, PartialEq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Trailer {
     /// Trailer key
@@ -1,7 +1,10 @@
+mod bundle;
+pub use bundle::{OplogBundleEntry, OplogBundleManifest};
 pub mod entry;
 mod oplog;
 pub use oplog::OplogExt;
 mod reflog;
+mod retention;
 mod snapshot;
 pub use snapshot::SnapshotExt;
 mod state;
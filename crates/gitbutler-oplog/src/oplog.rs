@@ -1,26 +1,35 @@
 use std::{
     collections::{hash_map::Entry, HashMap},
     fs,
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
     str::{from_utf8, FromStr},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, bail, Context, Result};
 use git2::{DiffOptions, FileMode};
-use gitbutler_branch::{Branch, SignaturePurpose, VirtualBranchesHandle, VirtualBranchesState};
+use gitbutler_branch::{
+    Branch, BranchId, SignaturePurpose, VirtualBranchesHandle, VirtualBranchesState,
+};
 use gitbutler_command_context::RepositoryExtLite;
-use gitbutler_diff::{hunks_by_filepath, FileDiff};
+use gitbutler_diff::{
+    describe_binary_files, hunks_by_filepath, intraline,
+    lfs::{self, LfsPointer},
+    FileDiff,
+};
 use gitbutler_project::{
     access::{WorktreeReadPermission, WorktreeWritePermission},
-    Project,
+    IntralineDiff, OplogRetention, Project,
 };
 use gitbutler_repo::RepositoryExt;
+use itertools::Itertools;
 use tracing::instrument;
 
 use super::{
-    entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
+    bundle::{self, OplogBundleManifest},
+    entry::{OperationKind, Snapshot, SnapshotDetails, SnapshotQuery, Trailer},
     reflog::set_reference_to_oplog,
+    retention::snapshots_to_keep,
     state::OplogHandle,
 };
 
@@ -96,6 +105,21 @@ pub trait OplogExt {
         oplog_commit_id: Option<git2::Oid>,
     ) -> Result<Vec<Snapshot>>;
 
+    /// Like [`list_snapshots`](Self::list_snapshots), but only returns up to `limit` snapshots that match `query`,
+    /// with the most recent snapshot first.
+    ///
+    /// Use `oplog_commit_id` as traversal root to continue where a previous query left off. Unlike with
+    /// [`list_snapshots`](Self::list_snapshots), it is only returned if it matches `query`.
+    ///
+    /// Snapshots are assumed to be ordered by creation time, so the traversal stops at the first
+    /// snapshot created before [`SnapshotQuery::since`].
+    fn query_snapshots(
+        &self,
+        query: &SnapshotQuery,
+        limit: usize,
+        oplog_commit_id: Option<git2::Oid>,
+    ) -> Result<Vec<Snapshot>>;
+
     /// Reverts to a previous state of the working directory, virtual branches and commits.
     /// The provided `snapshot_commit_id` must refer to a valid snapshot commit, as returned by [`create_snapshot`](Self::create_snapshot).
     /// Upon success, a new snapshot is created representing the state right before this call.
@@ -109,6 +133,31 @@ pub trait OplogExt {
     /// Returns the sha of the created revert snapshot commit or None if snapshots are disabled.
     fn restore_snapshot(&self, snapshot_commit_id: git2::Oid) -> Result<Option<git2::Oid>>;
 
+    /// Like [`restore_snapshot`](Self::restore_snapshot), but only restores the worktree-relative `paths`
+    /// to their state in the snapshot, leaving everything else as is. Directories are restored recursively,
+    /// and paths that didn't exist in the snapshot are removed.
+    ///
+    /// Returns the sha of the created revert snapshot commit or None if snapshots are disabled.
+    fn restore_paths_from_snapshot(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        paths: &[PathBuf],
+    ) -> Result<Option<git2::Oid>>;
+
+    /// Like [`restore_snapshot`](Self::restore_snapshot), but only restores the virtual branch with `branch_id` from
+    /// the blob `virtual_branches.toml` in the snapshot, along with its commits. All other branches remain unchanged.
+    /// If the restored branch is in the workspace, the files it changed compared to its current state are restored
+    /// in the working directory as well.
+    /// The `refs/gitbutler/` reference of the branch is set to its restored head, but the workspace commit is left
+    /// to the caller to update.
+    ///
+    /// Returns the sha of the created revert snapshot commit or None if snapshots are disabled.
+    fn restore_branch_from_snapshot(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        branch_id: BranchId,
+    ) -> Result<Option<git2::Oid>>;
+
     /// Determines if a new snapshot should be created due to file changes being created since the last snapshot.
     /// The needs for the automatic snapshotting are:
     ///  - It needs to facilitate backup of work in progress code
@@ -132,6 +181,42 @@ pub trait OplogExt {
 
     /// Gets the sha of the last snapshot commit if present.
     fn oplog_head(&self) -> Result<Option<git2::Oid>>;
+
+    /// Drops snapshots according to the [`OplogRetention`] policy of the project, if there is one.
+    /// The oplog chain is rewritten to only contain the snapshots that are kept, and the reflog is updated
+    /// to refer to it, so the dropped snapshots and everything only they refer to become unreachable.
+    ///
+    /// Note that all snapshots that are more recent than the oldest dropped snapshot receive a new id, and
+    /// that the changes of dropped snapshots are attributed to the next more recent snapshot that is kept.
+    ///
+    /// Returns the number of dropped snapshots.
+    fn prune_snapshots(&self, perm: &mut WorktreeWritePermission) -> Result<usize>;
+
+    /// Returns `true` if the project has an [`OplogRetention`] policy and the snapshots weren't pruned
+    /// within `check_if_last_pruned_older_than`, as pruning visits the whole oplog and shouldn't run
+    /// after every snapshot.
+    fn should_prune_snapshots(&self, check_if_last_pruned_older_than: Duration) -> Result<bool>;
+
+    /// Exports the snapshots from `head` (or the oplog head if `None`) back to and including `oldest` (or the very first
+    /// snapshot if `None`) as self-contained git bundle at `bundle_path`.
+    /// A manifest describing the bundle is written next to it, at [`OplogBundleManifest::path_for()`].
+    ///
+    /// If `oldest` has a parent, the exported snapshots are rewritten to start a new history, and receive new ids.
+    ///
+    /// Returns the manifest that was written.
+    fn export_snapshots(
+        &self,
+        bundle_path: &Path,
+        head: Option<git2::Oid>,
+        oldest: Option<git2::Oid>,
+    ) -> Result<OplogBundleManifest>;
+
+    /// Imports the snapshots from a bundle previously written by [`export_snapshots`](Self::export_snapshots),
+    /// and grafts them onto the oplog head, if present, so they become the most recent snapshots.
+    /// Each commit is validated to be a snapshot as described by the manifest next to `bundle_path`.
+    ///
+    /// Returns the ids of the imported snapshots in this repository, most recent first.
+    fn import_snapshots(&self, bundle_path: &Path) -> Result<Vec<git2::Oid>>;
 }
 
 impl OplogExt for Project {
@@ -164,97 +249,17 @@ impl OplogExt for Project {
         limit: usize,
         oplog_commit_id: Option<git2::Oid>,
     ) -> Result<Vec<Snapshot>> {
-        let repo_path = self.path.as_path();
-        let repo = git2::Repository::open(repo_path)?;
-
-        let traversal_root_id = match oplog_commit_id {
-            Some(id) => id,
-            None => {
-                let oplog_state = OplogHandle::new(&self.gb_dir());
-                if let Some(id) = oplog_state.oplog_head()? {
-                    id
-                } else {
-                    return Ok(vec![]);
-                }
-            }
-        };
-
-        let oplog_head_commit = repo.find_commit(traversal_root_id)?;
-
-        let mut revwalk = repo.revwalk()?;
-        revwalk.push(oplog_head_commit.id())?;
-
-        let mut snapshots = Vec::new();
-
-        let mut wd_trees_cache: HashMap<git2::Oid, git2::Oid> = HashMap::new();
-
-        for commit_id in revwalk {
-            if snapshots.len() == limit {
-                break;
-            }
-            let commit_id = commit_id?;
-            let commit = repo.find_commit(commit_id)?;
-
-            if commit.parent_count() > 1 {
-                break;
-            }
-
-            let tree = commit.tree()?;
-            if tree.get_name("virtual_branches.toml").is_none() {
-                // We reached a tree that is not a snapshot
-                tracing::warn!("Commit {commit_id} didn't seem to be an oplog commit - skipping");
-                continue;
-            }
-
-            // Get tree id from cache or calculate it
-            let wd_tree = get_workdir_tree(&mut wd_trees_cache, commit_id, &repo)?;
-
-            let details = commit
-                .message()
-                .and_then(|msg| SnapshotDetails::from_str(msg).ok());
-
-            if let Ok(parent) = commit.parent(0) {
-                // Get tree id from cache or calculate it
-                let parent_tree = get_workdir_tree(&mut wd_trees_cache, parent.id(), &repo)?;
-
-                let mut opts = DiffOptions::new();
-                opts.include_untracked(true);
-                opts.ignore_submodules(true);
-                let diff =
-                    repo.diff_tree_to_tree(Some(&parent_tree), Some(&wd_tree), Some(&mut opts))?;
-
-                let mut files_changed = Vec::new();
-                diff.print(git2::DiffFormat::NameOnly, |delta, _, _| {
-                    if let Some(path) = delta.new_file().path() {
-                        files_changed.push(path.to_path_buf());
-                    }
-                    true
-                })?;
-
-                let stats = diff.stats()?;
-                snapshots.push(Snapshot {
-                    commit_id,
-                    details,
-                    lines_added: stats.insertions(),
-                    lines_removed: stats.deletions(),
-                    files_changed,
-                    created_at: commit.time(),
-                });
-            } else {
-                // this is the very first snapshot
-                snapshots.push(Snapshot {
-                    commit_id,
-                    details,
-                    lines_added: 0,
-                    lines_removed: 0,
-                    files_changed: Vec::new(),
-                    created_at: commit.time(),
-                });
-                break;
-            }
-        }
+        list_snapshots(self, &SnapshotQuery::default(), limit, oplog_commit_id)
+    }
 
-        Ok(snapshots)
+    #[instrument(skip(self), err(Debug))]
+    fn query_snapshots(
+        &self,
+        query: &SnapshotQuery,
+        limit: usize,
+        oplog_commit_id: Option<git2::Oid>,
+    ) -> Result<Vec<Snapshot>> {
+        list_snapshots(self, query, limit, oplog_commit_id)
     }
 
     fn restore_snapshot(&self, snapshot_commit_id: git2::Oid) -> Result<Option<git2::Oid>> {
@@ -262,6 +267,29 @@ impl OplogExt for Project {
         restore_snapshot(self, snapshot_commit_id, guard.write_permission())
     }
 
+    fn restore_paths_from_snapshot(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        paths: &[PathBuf],
+    ) -> Result<Option<git2::Oid>> {
+        let mut guard = self.exclusive_worktree_access();
+        restore_paths_from_snapshot(self, snapshot_commit_id, paths, guard.write_permission())
+    }
+
+    fn restore_branch_from_snapshot(
+        &self,
+        snapshot_commit_id: git2::Oid,
+        branch_id: BranchId,
+    ) -> Result<Option<git2::Oid>> {
+        let mut guard = self.exclusive_worktree_access();
+        restore_branch_from_snapshot(
+            self,
+            snapshot_commit_id,
+            branch_id,
+            guard.write_permission(),
+        )
+    }
+
     #[instrument(level = tracing::Level::DEBUG, skip(self), err(Debug))]
     fn should_auto_snapshot(&self, check_if_last_snapshot_older_than: Duration) -> Result<bool> {
         let last_snapshot_time = OplogHandle::new(&self.gb_dir()).modified_at()?;
@@ -300,7 +328,15 @@ impl OplogExt for Project {
         let diff =
             repo.diff_tree_to_tree(Some(&old_wd_tree), Some(&wd_tree), Some(&mut diff_opts))?;
 
-        let hunks = hunks_by_filepath(None, &diff)?;
+        let mut hunks = hunks_by_filepath(None, &diff)?;
+        describe_binary_files(&repo, &diff, &mut hunks)?;
+        if let Some(granularity) = self.intraline_diff {
+            let granularity = match granularity {
+                IntralineDiff::Word => intraline::Granularity::Word,
+                IntralineDiff::Char => intraline::Granularity::Char,
+            };
+            intraline::annotate(&mut hunks, granularity);
+        }
         Ok(hunks)
     }
 
@@ -309,6 +345,40 @@ impl OplogExt for Project {
         let oplog_state = OplogHandle::new(&self.gb_dir());
         oplog_state.oplog_head()
     }
+
+    #[instrument(skip(self), err(Debug))]
+    fn export_snapshots(
+        &self,
+        bundle_path: &Path,
+        head: Option<git2::Oid>,
+        oldest: Option<git2::Oid>,
+    ) -> Result<OplogBundleManifest> {
+        let guard = self.shared_worktree_access();
+        bundle::export_snapshots(self, bundle_path, head, oldest, guard.read_permission())
+    }
+
+    #[instrument(skip(self), err(Debug))]
+    fn import_snapshots(&self, bundle_path: &Path) -> Result<Vec<git2::Oid>> {
+        let mut guard = self.exclusive_worktree_access();
+        bundle::import_snapshots(self, bundle_path, guard.write_permission())
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn prune_snapshots(&self, perm: &mut WorktreeWritePermission) -> Result<usize> {
+        let Some(retention) = self.oplog_retention else {
+            return Ok(0);
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        prune_snapshots(self, &retention, now.try_into()?, perm)
+    }
+
+    fn should_prune_snapshots(&self, check_if_last_pruned_older_than: Duration) -> Result<bool> {
+        if self.oplog_retention.is_none() {
+            return Ok(false);
+        }
+        let last_pruned_time = OplogHandle::new(&self.gb_dir()).pruned_at()?;
+        Ok(last_pruned_time.elapsed()? > check_if_last_pruned_older_than)
+    }
 }
 
 /// Get a tree of the working dir (applied branches merged)
@@ -329,6 +399,206 @@ fn get_workdir_tree<'a>(
     Ok(wd_tree)
 }
 
+/// Walk the oplog starting at `oplog_commit_id` or the oplog head, and collect up to `limit` snapshots matching `query`.
+fn list_snapshots(
+    ctx: &Project,
+    query: &SnapshotQuery,
+    limit: usize,
+    oplog_commit_id: Option<git2::Oid>,
+) -> Result<Vec<Snapshot>> {
+    let repo_path = ctx.path.as_path();
+    let repo = git2::Repository::open(repo_path)?;
+
+    let traversal_root_id = match oplog_commit_id {
+        Some(id) => id,
+        None => {
+            let oplog_state = OplogHandle::new(&ctx.gb_dir());
+            if let Some(id) = oplog_state.oplog_head()? {
+                id
+            } else {
+                return Ok(vec![]);
+            }
+        }
+    };
+
+    let oplog_head_commit = repo.find_commit(traversal_root_id)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(oplog_head_commit.id())?;
+
+    let mut snapshots = Vec::new();
+
+    let mut wd_trees_cache: HashMap<git2::Oid, git2::Oid> = HashMap::new();
+
+    for commit_id in revwalk {
+        if snapshots.len() == limit {
+            break;
+        }
+        let commit_id = commit_id?;
+        let commit = repo.find_commit(commit_id)?;
+
+        if commit.parent_count() > 1 {
+            break;
+        }
+
+        let tree = commit.tree()?;
+        if tree.get_name("virtual_branches.toml").is_none() {
+            // We reached a tree that is not a snapshot
+            tracing::warn!("Commit {commit_id} didn't seem to be an oplog commit - skipping");
+            continue;
+        }
+
+        let created_at = commit.time();
+        if query
+            .since
+            .is_some_and(|since| created_at.seconds() < since)
+        {
+            // All snapshots further down are older.
+            break;
+        }
+
+        let details = commit
+            .message()
+            .and_then(|msg| SnapshotDetails::from_str(msg).ok());
+        if !query.matches_time(created_at.seconds()) || !query.matches_details(details.as_ref()) {
+            continue;
+        }
+
+        // Get tree id from cache or calculate it
+        let wd_tree = get_workdir_tree(&mut wd_trees_cache, commit_id, &repo)?;
+
+        if let Ok(parent) = commit.parent(0) {
+            // Get tree id from cache or calculate it
+            let parent_tree = get_workdir_tree(&mut wd_trees_cache, parent.id(), &repo)?;
+
+            let mut opts = DiffOptions::new();
+            opts.include_untracked(true);
+            opts.ignore_submodules(true);
+            let diff =
+                repo.diff_tree_to_tree(Some(&parent_tree), Some(&wd_tree), Some(&mut opts))?;
+
+            let mut files_changed = Vec::new();
+            diff.print(git2::DiffFormat::NameOnly, |delta, _, _| {
+                if let Some(path) = delta.new_file().path() {
+                    files_changed.push(path.to_path_buf());
+                }
+                true
+            })?;
+            if !query.matches_files(&files_changed) {
+                continue;
+            }
+
+            let stats = diff.stats()?;
+            snapshots.push(Snapshot {
+                commit_id,
+                details,
+                lines_added: stats.insertions(),
+                lines_removed: stats.deletions(),
+                files_changed,
+                created_at,
+            });
+        } else {
+            // this is the very first snapshot
+            if query.matches_files(&[]) {
+                snapshots.push(Snapshot {
+                    commit_id,
+                    details,
+                    lines_added: 0,
+                    lines_removed: 0,
+                    files_changed: Vec::new(),
+                    created_at,
+                });
+            }
+            break;
+        }
+    }
+
+    Ok(snapshots)
+}
+
+fn prune_snapshots(
+    ctx: &Project,
+    retention: &OplogRetention,
+    now: i64,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<usize> {
+    let repo = git2::Repository::open(ctx.path.as_path())?;
+    let oplog_state = OplogHandle::new(&ctx.gb_dir());
+    let Some(oplog_head_id) = oplog_state.oplog_head()? else {
+        return Ok(0);
+    };
+    oplog_state.set_pruned_now()?;
+
+    // Collect the linear chain of oplog commits, most recent first. Whatever is behind a merge is left untouched.
+    let mut chain = Vec::new();
+    let mut base_id = None;
+    let mut next = Some(repo.find_commit(oplog_head_id)?);
+    while let Some(commit) = next.take() {
+        if commit.parent_count() > 1 {
+            base_id = Some(commit.id());
+            break;
+        }
+        next = commit.parent(0).ok();
+        chain.push(commit);
+    }
+
+    let is_snapshot = |commit: &git2::Commit<'_>| {
+        commit.tree().map_or(false, |tree| {
+            tree.get_name("virtual_branches.toml").is_some()
+        })
+    };
+    let snapshots: Vec<_> = chain
+        .iter()
+        .filter(|commit| is_snapshot(commit))
+        .map(|commit| (commit.id(), commit.time().seconds()))
+        .collect();
+    let keep = snapshots_to_keep(&snapshots, retention, now);
+    let dropped = snapshots.len() - keep.len();
+    if dropped == 0 {
+        return Ok(0);
+    }
+
+    // Re-create the chain from the oldest commit, reusing commits as long as their parent didn't change.
+    let mut parent_id = base_id;
+    for commit in chain.iter().rev() {
+        if is_snapshot(commit) && !keep.contains(&commit.id()) {
+            continue;
+        }
+        if commit.parent_id(0).ok() == parent_id {
+            parent_id = Some(commit.id());
+            continue;
+        }
+        parent_id = Some(reparent_commit(&repo, commit, parent_id)?);
+    }
+
+    let new_oplog_head_id = parent_id.context("BUG: the most recent snapshot is always kept")?;
+    // No snapshot was created, so the next automatic snapshot isn't delayed.
+    oplog_state.replace_oplog_head(new_oplog_head_id)?;
+
+    let vb_state = VirtualBranchesHandle::new(ctx.gb_dir());
+    let target_commit_id = vb_state.get_default_target()?.sha;
+    set_reference_to_oplog(&ctx.path, target_commit_id, new_oplog_head_id)?;
+
+    Ok(dropped)
+}
+
+/// Write a copy of `commit` that has `parent_id` as its only parent, or no parent if `None`, and return its id.
+pub(crate) fn reparent_commit(
+    repo: &git2::Repository,
+    commit: &git2::Commit<'_>,
+    parent_id: Option<git2::Oid>,
+) -> Result<git2::Oid> {
+    let parent = parent_id.map(|id| repo.find_commit(id)).transpose()?;
+    Ok(repo.commit(
+        None,
+        &commit.author(),
+        &commit.committer(),
+        &String::from_utf8_lossy(commit.message_raw_bytes()),
+        &commit.tree()?,
+        parent.iter().collect::<Vec<_>>().as_slice(),
+    )?)
+}
+
 fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> Result<git2::Oid> {
     let worktree_dir = ctx.path.as_path();
     let repo = git2::Repository::open(worktree_dir)?;
@@ -338,11 +608,7 @@ fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> R
     // grab the target commit
     let default_target_commit = repo.find_commit(vb_state.get_default_target()?.sha)?;
     let target_tree_id = default_target_commit.tree_id();
-
-    // Create a blob out of `.git/gitbutler/virtual_branches.toml`
-    let vb_path = repo.path().join("gitbutler").join("virtual_branches.toml");
-    let vb_content = fs::read(vb_path)?;
-    let vb_blob_id = repo.blob(&vb_content)?;
+    let target_tree = default_target_commit.tree()?;
 
     // Create a tree out of the conflicts state if present
     let conflicts_tree_id = write_conflicts_tree(worktree_dir, &repo)?;
@@ -356,19 +622,24 @@ fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> R
     tree_builder.insert("index", index_tree_oid, FileMode::Tree.into())?;
     tree_builder.insert("target_tree", target_tree_id, FileMode::Tree.into())?;
     tree_builder.insert("conflicts", conflicts_tree_id, FileMode::Tree.into())?;
-    tree_builder.insert("virtual_branches.toml", vb_blob_id, FileMode::Blob.into())?;
 
     // go through all virtual branches and create a subtree for each with the tree and any commits encoded
     let mut branches_tree_builder = repo.treebuilder(None)?;
     let mut head_tree_ids = Vec::new();
+    let mut trees_with_lfs_pointers = Vec::new();
 
     for branch in vb_state.list_branches_in_workspace()? {
         head_tree_ids.push(branch.tree);
 
+        let branch_tree_id = tree_with_lfs_pointers(&repo, &target_tree, branch.tree)?;
+        if branch_tree_id != branch.tree {
+            trees_with_lfs_pointers.push((branch.id, branch_tree_id));
+        }
+
         // commits in virtual branches (tree and commit data)
         // calculate all the commits between branch.head and the target and codify them
         let mut branch_tree_builder = repo.treebuilder(None)?;
-        branch_tree_builder.insert("tree", branch.tree, FileMode::Tree.into())?;
+        branch_tree_builder.insert("tree", branch_tree_id, FileMode::Tree.into())?;
 
         // let's get all the commits between the branch head and the target
         let mut revwalk = repo.revwalk()?;
@@ -440,6 +711,25 @@ fn prepare_snapshot(ctx: &Project, _shared_access: &WorktreeReadPermission) -> R
     let branch_tree_id = branches_tree_builder.write()?;
     tree_builder.insert("virtual_branches", branch_tree_id, FileMode::Tree.into())?;
 
+    // Create a blob out of `.git/gitbutler/virtual_branches.toml`, with the branch trees as stored in the snapshot
+    let vb_path = repo.path().join("gitbutler").join("virtual_branches.toml");
+    let mut vb_content = fs::read(vb_path)?;
+    if !trees_with_lfs_pointers.is_empty() {
+        let mut vb_toml: toml::Table = toml::from_str(from_utf8(&vb_content)?)?;
+        for (branch_id, tree_id) in trees_with_lfs_pointers {
+            if let Some(branch) = vb_toml
+                .get_mut("branches")
+                .and_then(|branches| branches.get_mut(branch_id.to_string().as_str()))
+                .and_then(toml::Value::as_table_mut)
+            {
+                branch.insert("tree".into(), tree_id.to_string().into());
+            }
+        }
+        vb_content = toml::to_string(&vb_toml)?.into_bytes();
+    }
+    let vb_blob_id = repo.blob(&vb_content)?;
+    tree_builder.insert("virtual_branches.toml", vb_blob_id, FileMode::Blob.into())?;
+
     let tree_id = tree_builder.write()?;
     Ok(tree_id)
 }
@@ -535,15 +825,7 @@ fn restore_snapshot(
         for commit_entry in commits_tree.iter() {
             // for each commit, recreate the commit from the commit data if it doesn't exist
             if let Some(commit_id) = commit_entry.name() {
-                // check for the oid in the repo
-                let commit_oid = git2::Oid::from_str(commit_id)?;
-                if repo.find_commit(commit_oid).is_err() {
-                    // commit is not in the repo, let's build it from our data
-                    let new_commit_oid = deserialize_commit(&repo, &commit_entry)?;
-                    if new_commit_oid != commit_oid {
-                        bail!("commit id mismatch: failed to recreate a commit from its parts");
-                    }
-                }
+                let commit_oid = ensure_commit_exists(&repo, commit_id, &commit_entry)?;
 
                 // if branch_name is 'workspace', we need to create or update the gitbutler/workspace branch
                 if branch_name == Some("workspace") {
@@ -584,6 +866,18 @@ fn restore_snapshot(
     checkout_builder.force();
     // Checkout the tree
     repo.checkout_tree(workdir_tree.as_object(), Some(&mut checkout_builder))?;
+    let target_tree = repo.find_tree(
+        snapshot_tree
+            .get_name("target_tree")
+            .context("failed to get target tree entry")?
+            .id(),
+    )?;
+    let changed_paths: Vec<_> = repo
+        .diff_tree_to_tree(Some(&target_tree), Some(&workdir_tree), None)?
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().map(ToOwned::to_owned))
+        .collect();
+    smudge_lfs_pointers(&repo, &workdir_tree, &changed_paths)?;
 
     // Update virtual_branches.toml with the state from the snapshot
     fs::write(
@@ -601,34 +895,50 @@ fn restore_snapshot(
     let mut index = repo.index()?;
     index.read_tree(&index_tree)?;
 
-    let restored_operation = snapshot_commit
-        .message()
-        .and_then(|msg| SnapshotDetails::from_str(msg).ok())
-        .map(|d| d.operation.to_string())
-        .unwrap_or_default();
-
     // create new snapshot
     let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
-    let restored_date_ms = snapshot_commit.time().seconds() * 1000;
     let details = SnapshotDetails {
         version: Default::default(),
         operation: OperationKind::RestoreFromSnapshot,
         title: "Restored from snapshot".to_string(),
         body: None,
-        trailers: vec![
-            Trailer {
-                key: "restored_from".to_string(),
-                value: snapshot_commit_id.to_string(),
-            },
-            Trailer {
-                key: "restored_operation".to_string(),
-                value: restored_operation,
-            },
-            Trailer {
-                key: "restored_date".to_string(),
-                value: restored_date_ms.to_string(),
-            },
-        ],
+        trailers: restored_from_trailers(&snapshot_commit),
+    };
+    commit_snapshot(
+        ctx,
+        before_restore_snapshot_tree_id,
+        details,
+        exclusive_access,
+    )
+}
+
+fn restore_paths_from_snapshot(
+    ctx: &Project,
+    snapshot_commit_id: git2::Oid,
+    paths: &[PathBuf],
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Option<git2::Oid>> {
+    let worktree_dir = ctx.path.as_path();
+    let repo = git2::Repository::open(worktree_dir)?;
+
+    let before_restore_snapshot_result = prepare_snapshot(ctx, exclusive_access.read_permission());
+    let snapshot_commit = repo.find_commit(snapshot_commit_id)?;
+
+    let workdir_tree_id = tree_from_applied_vbranches(&repo, snapshot_commit_id)?;
+    checkout_paths(&repo, workdir_tree_id, paths)?;
+
+    let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
+    let mut trailers = restored_from_trailers(&snapshot_commit);
+    trailers.push(Trailer {
+        key: "restored_paths".to_string(),
+        value: paths.iter().map(|path| path.display()).join("\n"),
+    });
+    let details = SnapshotDetails {
+        version: Default::default(),
+        operation: OperationKind::RestoreFromSnapshot,
+        title: "Restored paths from snapshot".to_string(),
+        body: None,
+        trailers,
     };
     commit_snapshot(
         ctx,
@@ -638,6 +948,257 @@ fn restore_snapshot(
     )
 }
 
+fn restore_branch_from_snapshot(
+    ctx: &Project,
+    snapshot_commit_id: git2::Oid,
+    branch_id: BranchId,
+    exclusive_access: &mut WorktreeWritePermission,
+) -> Result<Option<git2::Oid>> {
+    let worktree_dir = ctx.path.as_path();
+    let repo = git2::Repository::open(worktree_dir)?;
+
+    let before_restore_snapshot_result = prepare_snapshot(ctx, exclusive_access.read_permission());
+    let snapshot_commit = repo.find_commit(snapshot_commit_id)?;
+    let snapshot_tree = snapshot_commit.tree()?;
+
+    let vb_toml_entry = snapshot_tree
+        .get_name("virtual_branches.toml")
+        .context("failed to get virtual_branches.toml blob")?;
+    let vb_toml_blob = repo
+        .find_blob(vb_toml_entry.id())
+        .context("failed to convert virtual_branches tree entry to blob")?;
+    let vbs_from_toml: VirtualBranchesState = toml::from_str(from_utf8(vb_toml_blob.content())?)?;
+    let restored_branch = vbs_from_toml
+        .branch(branch_id)
+        .with_context(|| format!("Branch {branch_id} isn't part of snapshot {snapshot_commit_id}"))?
+        .clone();
+
+    // make sure the commits of the branch are present, as they might have been garbage-collected since.
+    let commits_tree = snapshot_tree
+        .get_path(
+            &PathBuf::from("virtual_branches")
+                .join(branch_id.to_string())
+                .join("commits"),
+        )
+        .ok()
+        .map(|entry| repo.find_tree(entry.id()))
+        .transpose()?;
+    for commit_entry in commits_tree.iter().flat_map(|tree| tree.iter()) {
+        if let Some(commit_id) = commit_entry.name() {
+            ensure_commit_exists(&repo, commit_id, &commit_entry)?;
+        }
+    }
+
+    let vb_state = VirtualBranchesHandle::new(ctx.gb_dir());
+    let current_branch = vb_state.try_branch(branch_id)?;
+    vb_state.set_branch(restored_branch.clone())?;
+    repo.reference(
+        &restored_branch.refname()?.to_string(),
+        restored_branch.head,
+        true,
+        "restore virtual branch from snapshot",
+    )?;
+
+    if restored_branch.in_workspace {
+        let previous_tree_id = match current_branch.filter(|branch| branch.in_workspace) {
+            Some(branch) => branch.tree,
+            None => repo
+                .find_commit(vb_state.get_default_target()?.sha)?
+                .tree_id(),
+        };
+        let diff = repo.diff_tree_to_tree(
+            Some(&repo.find_tree(previous_tree_id)?),
+            Some(&repo.find_tree(restored_branch.tree)?),
+            None,
+        )?;
+        let changed_paths: Vec<_> = diff
+            .deltas()
+            .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+            .flatten()
+            .map(ToOwned::to_owned)
+            .unique()
+            .collect();
+        let workdir_tree_id = tree_from_applied_vbranches(&repo, snapshot_commit_id)?;
+        checkout_paths(&repo, workdir_tree_id, &changed_paths)?;
+    }
+
+    let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
+    let mut trailers = restored_from_trailers(&snapshot_commit);
+    trailers.push(Trailer {
+        key: "restored_branch".to_string(),
+        value: restored_branch.name,
+    });
+    let details = SnapshotDetails {
+        version: Default::default(),
+        operation: OperationKind::RestoreFromSnapshot,
+        title: "Restored branch from snapshot".to_string(),
+        body: None,
+        trailers,
+    };
+    commit_snapshot(
+        ctx,
+        before_restore_snapshot_tree_id,
+        details,
+        exclusive_access,
+    )
+}
+
+/// Returns the trailers that identify `snapshot_commit` as the source of a restoration.
+fn restored_from_trailers(snapshot_commit: &git2::Commit<'_>) -> Vec<Trailer> {
+    let restored_operation = snapshot_commit
+        .message()
+        .and_then(|msg| SnapshotDetails::from_str(msg).ok())
+        .map(|d| d.operation.to_string())
+        .unwrap_or_default();
+    let restored_date_ms = snapshot_commit.time().seconds() * 1000;
+    vec![
+        Trailer {
+            key: "restored_from".to_string(),
+            value: snapshot_commit.id().to_string(),
+        },
+        Trailer {
+            key: "restored_operation".to_string(),
+            value: restored_operation,
+        },
+        Trailer {
+            key: "restored_date".to_string(),
+            value: restored_date_ms.to_string(),
+        },
+    ]
+}
+
+/// Make the worktree-relative `paths` in the worktree of `repo` match their state in the tree with `tree_id`.
+/// Paths that aren't present in the tree are removed from the worktree.
+fn checkout_paths(repo: &git2::Repository, tree_id: git2::Oid, paths: &[PathBuf]) -> Result<()> {
+    let worktree_dir = repo
+        .workdir()
+        .context("Bare repositories aren't supported")?;
+    let tree = repo.find_tree(tree_id)?;
+
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.remove_untracked(true);
+    checkout_builder.force();
+    checkout_builder.update_index(false);
+    let mut has_paths_to_checkout = false;
+    for path in paths {
+        if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+            bail!(
+                "Path '{}' must be relative to the worktree and stay within it",
+                path.display()
+            );
+        }
+        if tree.get_path(path).is_ok() {
+            checkout_builder.path(path);
+            has_paths_to_checkout = true;
+            continue;
+        }
+        let worktree_path = worktree_dir.join(path);
+        match fs::symlink_metadata(&worktree_path) {
+            Ok(md) if md.is_dir() => fs::remove_dir_all(worktree_path)?,
+            Ok(_) => fs::remove_file(worktree_path)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    if has_paths_to_checkout {
+        repo.ignore_large_files_in_diffs(SNAPSHOT_FILE_LIMIT_BYTES)?;
+        repo.checkout_tree(tree.as_object(), Some(&mut checkout_builder))?;
+        smudge_lfs_pointers(repo, &tree, paths)?;
+    }
+    Ok(())
+}
+
+/// Return the tree `tree_id` with all LFS objects that aren't in `base_tree` replaced by their LFS pointers,
+/// after putting them into the LFS store like the `lfs` clean filter would.
+///
+/// This keeps large files that Git itself would never store in the object database out of snapshots.
+fn tree_with_lfs_pointers(
+    repo: &git2::Repository,
+    base_tree: &git2::Tree,
+    tree_id: git2::Oid,
+) -> Result<git2::Oid> {
+    let tree = repo.find_tree(tree_id)?;
+    let diff = repo.diff_tree_to_tree(Some(base_tree), Some(&tree), None)?;
+    let mut builder = git2::build::TreeUpdateBuilder::new();
+    let mut has_updates = false;
+    for delta in diff.deltas() {
+        let new_file = delta.new_file();
+        let Some(path) = new_file.path() else {
+            continue;
+        };
+        if new_file.id().is_zero()
+            || !matches!(new_file.mode(), FileMode::Blob | FileMode::BlobExecutable)
+            || !lfs::is_tracked(repo, path)?
+        {
+            continue;
+        }
+        let blob = repo.find_blob(new_file.id())?;
+        if LfsPointer::from_bytes(blob.content()).is_some() {
+            continue;
+        }
+        let pointer = lfs::store(repo, blob.content())?;
+        builder.upsert(path, repo.blob(&pointer.to_bytes())?, new_file.mode());
+        has_updates = true;
+    }
+    if !has_updates {
+        return Ok(tree_id);
+    }
+    Ok(builder.create_updated(repo, &tree)?)
+}
+
+/// Write the LFS objects of the LFS pointers at the worktree-relative `paths` in `tree` into the worktree,
+/// to restore what [`tree_with_lfs_pointers()`] put into the LFS store.
+/// Pointers whose objects aren't in the LFS store are left as they are.
+fn smudge_lfs_pointers(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    paths: &[PathBuf],
+) -> Result<()> {
+    let worktree_dir = repo
+        .workdir()
+        .context("Bare repositories aren't supported")?;
+    for path in paths {
+        let Ok(entry) = tree.get_path(path) else {
+            continue;
+        };
+        if entry.kind() != Some(git2::ObjectType::Blob) || !lfs::is_tracked(repo, path)? {
+            continue;
+        }
+        let blob = repo.find_blob(entry.id())?;
+        let Some(pointer) = LfsPointer::from_bytes(blob.content()) else {
+            continue;
+        };
+        let object_path = pointer.object_path(repo.path());
+        if object_path.exists() {
+            std::io::copy(
+                &mut fs::File::open(object_path)?,
+                &mut fs::File::create(worktree_dir.join(path))?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Re-create the commit named `commit_id` from the snapshot `commit_entry` if it isn't present in `repo` anymore,
+/// and return its id.
+fn ensure_commit_exists(
+    repo: &git2::Repository,
+    commit_id: &str,
+    commit_entry: &git2::TreeEntry<'_>,
+) -> Result<git2::Oid> {
+    // check for the oid in the repo
+    let commit_oid = git2::Oid::from_str(commit_id)?;
+    if repo.find_commit(commit_oid).is_err() {
+        // commit is not in the repo, let's build it from our data
+        let new_commit_oid = deserialize_commit(repo, commit_entry)?;
+        if new_commit_oid != commit_oid {
+            bail!("commit id mismatch: failed to recreate a commit from its parts");
+        }
+    }
+    Ok(commit_oid)
+}
+
 /// Restore the state of .git/base_merge_parent and .git/conflicts from the snapshot
 /// Will remove those files if they are not present in the snapshot
 fn restore_conflicts_tree(snapshot_tree: &git2::Tree, repo: &git2::Repository) -> Result<()> {
@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use gitbutler_project::OplogRetention;
+
+const HOUR: i64 = 60 * 60;
+const DAY: i64 = 24 * HOUR;
+
+/// Return the ids of the `snapshots` to keep according to `retention`, at the time `now` in seconds since the Unix epoch.
+///
+/// `snapshots` are `(id, created_at_seconds)` tuples ordered by creation time, most recent first.
+/// Within each hour or day that is thinned out, the most recent snapshot is kept. The most recent snapshot overall
+/// is always kept so the oplog never ends up empty.
+pub(crate) fn snapshots_to_keep(
+    snapshots: &[(git2::Oid, i64)],
+    retention: &OplogRetention,
+    now: i64,
+) -> HashSet<git2::Oid> {
+    let keep_all_until = i64::from(retention.keep_all_days) * DAY;
+    let hourly_until = keep_all_until + i64::from(retention.hourly_days) * DAY;
+    let daily_until = retention
+        .daily_days
+        .map(|days| hourly_until + i64::from(days) * DAY);
+
+    let mut seen_periods = HashSet::new();
+    let mut keep = HashSet::new();
+    for (idx, (id, created_at)) in snapshots.iter().enumerate() {
+        let age = now - created_at;
+        let keep_snapshot = if idx == 0 || age < keep_all_until {
+            true
+        } else if age < hourly_until {
+            seen_periods.insert((HOUR, created_at.div_euclid(HOUR)))
+        } else if daily_until.map_or(true, |until| age < until) {
+            seen_periods.insert((DAY, created_at.div_euclid(DAY)))
+        } else {
+            false
+        };
+        if keep_snapshot {
+            keep.insert(*id);
+        }
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use gitbutler_project::OplogRetention;
+
+    use super::{snapshots_to_keep, DAY, HOUR};
+
+    fn id(n: u8) -> git2::Oid {
+        git2::Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    fn kept(snapshots: &[(git2::Oid, i64)], retention: OplogRetention, now: i64) -> Vec<u8> {
+        let keep = snapshots_to_keep(snapshots, &retention, now);
+        snapshots
+            .iter()
+            .filter(|(id, _)| keep.contains(id))
+            .map(|(id, _)| id.as_bytes()[0])
+            .collect()
+    }
+
+    #[test]
+    fn recent_snapshots_are_all_kept() {
+        let now = 100 * DAY;
+        let snapshots = [(id(1), now - 10), (id(2), now - 20), (id(3), now - DAY + 1)];
+        let retention = OplogRetention {
+            keep_all_days: 1,
+            hourly_days: 0,
+            daily_days: Some(0),
+        };
+        assert_eq!(kept(&snapshots, retention, now), [1, 2, 3]);
+    }
+
+    #[test]
+    fn thinned_to_most_recent_per_hour_then_per_day() {
+        let now = 100 * DAY;
+        let two_days_ago = now - 2 * DAY;
+        let ten_days_ago = now - 10 * DAY;
+        let snapshots = [
+            (id(1), now - 10),
+            // in the same hour, two days ago
+            (id(2), two_days_ago + 20),
+            (id(3), two_days_ago + 10),
+            // in the next older hour
+            (id(4), two_days_ago - HOUR),
+            // in the same day, ten days ago
+            (id(5), ten_days_ago + 2 * HOUR),
+            (id(6), ten_days_ago + HOUR),
+            // too old
+            (id(7), now - 50 * DAY),
+        ];
+        let retention = OplogRetention {
+            keep_all_days: 1,
+            hourly_days: 6,
+            daily_days: Some(30),
+        };
+        assert_eq!(kept(&snapshots, retention, now), [1, 2, 4, 5]);
+
+        let retention = OplogRetention {
+            daily_days: None,
+            ..retention
+        };
+        assert_eq!(
+            kept(&snapshots, retention, now),
+            [1, 2, 4, 5, 7],
+            "daily snapshots are kept forever"
+        );
+    }
+
+    #[test]
+    fn most_recent_snapshot_is_always_kept() {
+        let now = 100 * DAY;
+        let snapshots = [(id(1), now - 5 * DAY), (id(2), now - 6 * DAY)];
+        let retention = OplogRetention {
+            keep_all_days: 0,
+            hourly_days: 0,
+            daily_days: Some(0),
+        };
+        assert_eq!(kept(&snapshots, retention, now), [1]);
+    }
+}
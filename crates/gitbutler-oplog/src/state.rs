@@ -34,6 +34,12 @@ pub struct Oplog {
         default = "unix_epoch"
     )]
     pub modified_at: SystemTime,
+    /// The time when the snapshots were last pruned according to the retention policy of the project.
+    #[serde(
+        deserialize_with = "unfailing_system_time_deserialize",
+        default = "unix_epoch"
+    )]
+    pub pruned_at: SystemTime,
 }
 
 impl Default for Oplog {
@@ -41,6 +47,7 @@ impl Default for Oplog {
         Self {
             head_sha: None,
             modified_at: SystemTime::UNIX_EPOCH,
+            pruned_at: SystemTime::UNIX_EPOCH,
         }
     }
 }
@@ -63,10 +70,21 @@ impl OplogHandle {
     pub fn set_oplog_head(&self, sha: git2::Oid) -> Result<()> {
         let mut oplog = self.read_file()?;
         oplog.head_sha = Some(sha);
+        oplog.modified_at = SystemTime::now();
         self.write_file(oplog)?;
         Ok(())
     }
 
+    /// Persists the oplog head for the given repository after the oplog was rewritten, without
+    /// changing when the last snapshot was created.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn replace_oplog_head(&self, sha: git2::Oid) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.head_sha = Some(sha);
+        self.write_file(oplog)
+    }
+
     /// Gets the oplog head sha for the given repository.
     ///
     /// Errors if the file cannot be read or written.
@@ -83,6 +101,23 @@ impl OplogHandle {
         Ok(oplog.modified_at)
     }
 
+    /// Gets the time when the snapshots were last pruned.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn pruned_at(&self) -> Result<SystemTime> {
+        let oplog = self.read_file()?;
+        Ok(oplog.pruned_at)
+    }
+
+    /// Persists that the snapshots were pruned just now.
+    ///
+    /// Errors if the file cannot be read or written.
+    pub fn set_pruned_now(&self) -> Result<()> {
+        let mut oplog = self.read_file()?;
+        oplog.pruned_at = SystemTime::now();
+        self.write_file(oplog)
+    }
+
     /// Reads and parses the state file.
     ///
     /// If the file does not exist, it will be created.
@@ -90,8 +125,7 @@ impl OplogHandle {
         read_toml_file_or_default(&self.file_path)
     }
 
-    fn write_file(&self, mut oplog: Oplog) -> Result<()> {
-        oplog.modified_at = SystemTime::now();
+    fn write_file(&self, oplog: Oplog) -> Result<()> {
         gitbutler_fs::write(&self.file_path, toml::to_string(&oplog)?)
     }
 }
@@ -175,3 +175,35 @@ mod snapshot_details {
         }
     }
 }
+
+mod bundle_manifest {
+    use std::path::Path;
+
+    use gitbutler_oplog::OplogBundleManifest;
+
+    #[test]
+    fn path_for() {
+        assert_eq!(
+            OplogBundleManifest::path_for(Path::new("dir/snapshots.bundle")),
+            Path::new("dir/snapshots.bundle.manifest.toml")
+        );
+        assert_eq!(
+            OplogBundleManifest::path_for(Path::new("snapshots")),
+            Path::new("snapshots.manifest.toml")
+        );
+    }
+
+    #[test]
+    fn path_for_is_unique_per_bundle() {
+        assert_ne!(
+            OplogBundleManifest::path_for(Path::new("snapshots.toml")),
+            Path::new("snapshots.toml"),
+            "a bundle is never overwritten by its manifest"
+        );
+        assert_ne!(
+            OplogBundleManifest::path_for(Path::new("snapshots.bundle")),
+            OplogBundleManifest::path_for(Path::new("snapshots.tar")),
+            "bundles that only differ by extension have their own manifests"
+        );
+    }
+}
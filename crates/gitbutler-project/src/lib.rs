@@ -5,7 +5,11 @@ mod project;
 mod storage;
 
 pub use controller::Controller;
-pub use project::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+pub use project::{
+    ApiProject, AuthKey, CodePushState, FetchResult, IntralineDiff, OplogRetention, Project,
+    ProjectId, RemoteFetchError, RemoteFetchErrorKind, RemoteFetchResult, RemoteFetchResults,
+    RenameDetection, WhitespaceMode,
+};
 pub use storage::UpdateRequest;
 
 /// A utility to be used from applications to optimize `git2` configuration.
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     path::{self, PathBuf},
     time,
 };
@@ -57,6 +58,68 @@ impl FetchResult {
             FetchResult::Fetched { timestamp } | FetchResult::Error { timestamp, .. } => timestamp,
         }
     }
+
+    /// Summarize the outcome of fetching each remote, listing the error of each remote that failed.
+    pub fn from_remotes(results: &RemoteFetchResults) -> Self {
+        let timestamp = results
+            .values()
+            .map(|result| result.timestamp)
+            .max()
+            .unwrap_or_else(time::SystemTime::now);
+        let errors: Vec<_> = results
+            .iter()
+            .filter_map(|(remote, result)| {
+                let error = result.error.as_ref()?;
+                Some(format!("{remote}: {}", error.message))
+            })
+            .collect();
+        if errors.is_empty() {
+            FetchResult::Fetched { timestamp }
+        } else {
+            FetchResult::Error {
+                timestamp,
+                error: errors.join("\n"),
+            }
+        }
+    }
+}
+
+/// The outcome of fetching each remote of a project, keyed by remote name.
+pub type RemoteFetchResults = BTreeMap<String, RemoteFetchResult>;
+
+/// The outcome of fetching a single remote.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteFetchResult {
+    /// The time at which the fetch finished.
+    pub timestamp: time::SystemTime,
+    /// How long the fetch took, in milliseconds.
+    pub duration_ms: u64,
+    /// What went wrong, or `None` if the fetch succeeded.
+    pub error: Option<RemoteFetchError>,
+}
+
+/// Describes why fetching a remote failed.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteFetchError {
+    pub kind: RemoteFetchErrorKind,
+    /// The error as reported by the fetch.
+    pub message: String,
+}
+
+/// The class of a [`RemoteFetchError`].
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteFetchErrorKind {
+    /// The remote rejected the credentials, or none were available.
+    Auth,
+    /// The refs to fetch don't exist on the remote.
+    RefNotFound,
+    /// The remote couldn't be reached.
+    Network,
+    /// Anything else.
+    Other,
 }
 
 #[derive(Debug, Deserialize, Serialize, Copy, Clone)]
@@ -66,6 +129,67 @@ pub struct CodePushState {
     pub timestamp: time::SystemTime,
 }
 
+/// Determines which snapshots of the operations log are kept as they age, with the most recent snapshot of each
+/// hour or day standing in for all others of that period.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OplogRetention {
+    /// Snapshots younger than this amount of days are all kept.
+    pub keep_all_days: u32,
+    /// After `keep_all_days`, one snapshot per hour is kept for this amount of days.
+    pub hourly_days: u32,
+    /// After `hourly_days`, one snapshot per day is kept for this amount of days, or forever if `None`.
+    /// Older snapshots are dropped.
+    pub daily_days: Option<u32>,
+}
+
+/// Determines if and how files that were moved or copied are detected when diffing, instead of showing them
+/// as a deletion and an addition.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameDetection {
+    /// How similar, in percent, a new file has to be to a removed one to be considered a rename of it.
+    pub threshold: u16,
+    /// If `true`, also detect new files which are copies of existing ones.
+    #[serde(default)]
+    pub copies: bool,
+}
+
+impl Default for RenameDetection {
+    fn default() -> Self {
+        // The same default as `git diff --find-renames`.
+        RenameDetection {
+            threshold: 50,
+            copies: false,
+        }
+    }
+}
+
+/// The granularity at which the changes within modified lines are computed, to highlight them.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum IntralineDiff {
+    /// Changed words, along with changed whitespace and punctuation.
+    Word,
+    /// Changed characters.
+    Char,
+}
+
+/// The whitespace changes that are ignored when diffing, so they neither produce hunks of their own nor
+/// change existing ones.
+///
+/// Ignored changes aren't part of any hunk, and thus can't be committed until the setting is removed.
+#[derive(Debug, Deserialize, Serialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WhitespaceMode {
+    /// Ignore all changes to whitespace, like `git diff --ignore-all-space`.
+    IgnoreAll,
+    /// Ignore changes to whitespace at the end of lines, like `git diff --ignore-space-at-eol`.
+    IgnoreAtEol,
+    /// Ignore added or removed blank lines, like `git diff --ignore-blank-lines`.
+    IgnoreBlankLines,
+}
+
 pub type ProjectId = Id<Project>;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -83,6 +207,10 @@ pub struct Project {
     /// for example, when updating base branch
     #[serde(default)]
     pub ok_with_force_push: DefaultTrue,
+    /// if run_git_hooks is true, the `pre-commit`, `commit-msg` and `pre-push` hooks of the repository
+    /// run when committing to or pushing virtual branches
+    #[serde(default)]
+    pub run_git_hooks: DefaultTrue,
     pub api: Option<ApiProject>,
     #[serde(default)]
     pub gitbutler_data_last_fetch: Option<FetchResult>,
@@ -90,12 +218,28 @@ pub struct Project {
     pub gitbutler_code_push_state: Option<CodePushState>,
     #[serde(default)]
     pub project_data_last_fetch: Option<FetchResult>,
+    /// The outcome of the last fetch of each remote.
+    #[serde(default)]
+    pub remotes_last_fetch: RemoteFetchResults,
     #[serde(default)]
     pub omit_certificate_check: Option<bool>,
     // The number of changed lines that will trigger a snapshot
     pub snapshot_lines_threshold: Option<usize>,
+    /// If set, older snapshots of the operations log are thinned out accordingly. Otherwise, all snapshots are kept.
+    #[serde(default)]
+    pub oplog_retention: Option<OplogRetention>,
     #[serde(default = "default_false")]
     pub succeeding_rebases: bool,
+    /// If set, renamed and copied files are detected in the worktree and kept on the branch that owns them.
+    #[serde(default)]
+    pub rename_detection: Option<RenameDetection>,
+    /// If set, the words or characters that changed within modified lines are computed for uncommitted changes,
+    /// commits and snapshots.
+    #[serde(default)]
+    pub intraline_diff: Option<IntralineDiff>,
+    /// If set, the given kind of whitespace changes is ignored when diffing the worktree and commits.
+    #[serde(default)]
+    pub whitespace_mode: Option<WhitespaceMode>,
 }
 
 fn default_false() -> bool {
@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{
+    ApiProject, AuthKey, CodePushState, FetchResult, IntralineDiff, OplogRetention, Project,
+    ProjectId, RemoteFetchResults, RenameDetection, WhitespaceMode,
+};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -22,12 +25,52 @@ pub struct UpdateRequest {
     pub gitbutler_data_last_fetched: Option<FetchResult>,
     pub preferred_key: Option<AuthKey>,
     pub ok_with_force_push: Option<bool>,
+    pub run_git_hooks: Option<bool>,
     pub gitbutler_code_push_state: Option<CodePushState>,
     pub project_data_last_fetched: Option<FetchResult>,
+    pub remotes_last_fetched: Option<RemoteFetchResults>,
     pub omit_certificate_check: Option<bool>,
     pub use_diff_context: Option<bool>,
     pub snapshot_lines_threshold: Option<usize>,
+    /// `Some(None)`, or `null` in JSON, removes the retention so all snapshots are kept.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub oplog_retention: Option<Option<OplogRetention>>,
     pub succeeding_rebases: Option<bool>,
+    /// `Some(None)`, or `null` in JSON, turns rename detection off.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rename_detection: Option<Option<RenameDetection>>,
+    /// `Some(None)`, or `null` in JSON, turns the highlighting of changes within lines off.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub intraline_diff: Option<Option<IntralineDiff>>,
+    /// `Some(None)`, or `null` in JSON, stops ignoring whitespace changes.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_clearable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub whitespace_mode: Option<Option<WhitespaceMode>>,
+}
+
+/// Deserialize a setting of an [`UpdateRequest`] that is left unchanged if the field is missing,
+/// and cleared if it's `null`.
+fn deserialize_clearable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 impl Storage {
@@ -108,6 +151,10 @@ impl Storage {
             project.project_data_last_fetch = Some(project_data_last_fetched.clone());
         }
 
+        if let Some(remotes_last_fetched) = &update_request.remotes_last_fetched {
+            project.remotes_last_fetch.clone_from(remotes_last_fetched);
+        }
+
         if let Some(state) = update_request.gitbutler_code_push_state {
             project.gitbutler_code_push_state = Some(state);
         }
@@ -116,6 +163,10 @@ impl Storage {
             *project.ok_with_force_push = ok_with_force_push;
         }
 
+        if let Some(run_git_hooks) = update_request.run_git_hooks {
+            *project.run_git_hooks = run_git_hooks;
+        }
+
         if let Some(omit_certificate_check) = update_request.omit_certificate_check {
             project.omit_certificate_check = Some(omit_certificate_check);
         }
@@ -124,10 +175,26 @@ impl Storage {
             project.snapshot_lines_threshold = Some(snapshot_lines_threshold);
         }
 
+        if let Some(oplog_retention) = update_request.oplog_retention {
+            project.oplog_retention = oplog_retention;
+        }
+
         if let Some(succeeding_rebases) = update_request.succeeding_rebases {
             project.succeeding_rebases = succeeding_rebases;
         }
 
+        if let Some(rename_detection) = update_request.rename_detection {
+            project.rename_detection = rename_detection;
+        }
+
+        if let Some(intraline_diff) = update_request.intraline_diff {
+            project.intraline_diff = intraline_diff;
+        }
+
+        if let Some(whitespace_mode) = update_request.whitespace_mode {
+            project.whitespace_mode = whitespace_mode;
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
@@ -131,3 +131,58 @@ mod delete {
         assert!(!project.path.join(".gitbutler.json").exists());
     }
 }
+
+mod update {
+    use gitbutler_project::{IntralineDiff, OplogRetention, RenameDetection, UpdateRequest};
+
+    use super::*;
+
+    #[test]
+    fn missing_settings_are_kept_and_null_clears_them() {
+        let (controller, _tmp) = new();
+        let repository = gitbutler_testsupport::TestProject::default();
+        let project = controller.add(repository.path()).unwrap();
+
+        let retention = OplogRetention {
+            keep_all_days: 1,
+            hourly_days: 2,
+            daily_days: None,
+        };
+        let project = controller
+            .update(&UpdateRequest {
+                id: project.id,
+                oplog_retention: Some(Some(retention)),
+                whitespace_mode: Some(Some(WhitespaceMode::IgnoreAll)),
+                intraline_diff: Some(Some(IntralineDiff::Word)),
+                rename_detection: Some(Some(RenameDetection::default())),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(project.oplog_retention, Some(retention));
+        assert_eq!(project.whitespace_mode, Some(WhitespaceMode::IgnoreAll));
+        assert_eq!(project.intraline_diff, Some(IntralineDiff::Word));
+        assert_eq!(project.rename_detection, Some(RenameDetection::default()));
+
+        let untouched: UpdateRequest =
+            serde_json::from_value(serde_json::json!({ "id": project.id })).unwrap();
+        let project = controller.update(&untouched).unwrap();
+        assert_eq!(project.oplog_retention, Some(retention));
+        assert_eq!(project.whitespace_mode, Some(WhitespaceMode::IgnoreAll));
+        assert_eq!(project.intraline_diff, Some(IntralineDiff::Word));
+        assert_eq!(project.rename_detection, Some(RenameDetection::default()));
+
+        let cleared: UpdateRequest = serde_json::from_value(serde_json::json!({
+            "id": project.id,
+            "oplog_retention": null,
+            "whitespace_mode": null,
+            "intraline_diff": null,
+            "rename_detection": null,
+        }))
+        .unwrap();
+        let project = controller.update(&cleared).unwrap();
+        assert_eq!(project.oplog_retention, None);
+        assert_eq!(project.whitespace_mode, None);
+        assert_eq!(project.intraline_diff, None);
+        assert_eq!(project.rename_detection, None);
+    }
+}
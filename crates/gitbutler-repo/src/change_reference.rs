@@ -11,9 +11,26 @@ use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_reference::ReferenceName;
 use itertools::Itertools;
+use serde::Serialize;
+
+/// A series of commits within the stack of a virtual branch. It's represented by the [`ChangeReference`]
+/// to its top-most commit and is pushed to the remote reference of the same name.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Series {
+    /// The reference to the top-most commit of the series.
+    pub reference: ChangeReference,
+    /// The reference of the series below this one, which is what it's based on,
+    /// or `None` if this is the bottom-most series which is based on the default target.
+    pub base: Option<ReferenceName>,
+    /// The commits that are exclusive to this series, top-most first.
+    #[serde(with = "gitbutler_serde::oid_vec")]
+    pub commits: Vec<git2::Oid>,
+}
 
 /// Given a branch id, returns the the GitButler references associated with the branch.
-/// References within the same branch effectively represent a stack of sub-branches.
+/// References within the same branch effectively represent a stack of sub-branches, and are
+/// ordered from the top of the stack to its bottom.
 pub fn list_branch_references(
     ctx: &CommandContext,
     branch_id: BranchId,
@@ -52,6 +69,7 @@ pub fn create_change_reference(
         name,
         branch_id,
         change_id: change_id.clone(),
+        pushed_commit: None,
     };
     let all_references = handle
         .list_all_branches()?
@@ -77,10 +95,20 @@ pub fn create_change_reference(
     }
     validate_commit(&vbranch, commit.id(), ctx, &handle)?;
     vbranch.references.push(branch_reference.clone());
+    sort_references(ctx, &mut vbranch, &handle)?;
     handle.set_branch(vbranch)?;
     Ok(branch_reference)
 }
 
+/// Given a branch id, returns the stack of series of the branch, from the top of the stack to its bottom.
+/// Each series consists of the commits of its reference that aren't part of the series below it.
+/// Commits above the top-most reference are not part of any series.
+pub fn list_series(ctx: &CommandContext, branch_id: BranchId) -> Result<Vec<Series>> {
+    let handle = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let vbranch = handle.get_branch(branch_id)?;
+    stack_series(ctx, &vbranch, &handle)
+}
+
 /// Updates an existing branch reference to point to a different commit.
 /// Only the commit and change_id can be updated.
 /// The reference is identified by the branch id and the reference name.
@@ -122,11 +150,17 @@ pub fn update_change_reference(
         ))?;
     reference.change_id = new_change_id;
     let new_reference = reference.clone();
+    sort_references(ctx, &mut vbranch, &handle)?;
     handle.set_branch(vbranch)?;
     Ok(new_reference)
 }
 
 /// Pushes a gitbutler branch reference to the remote repository.
+///
+/// As each series in the stack is based on the one below it, all series below that weren't pushed yet are pushed
+/// beforehand, and all series above that were pushed before are pushed afterwards as their base might have changed,
+/// for instance after rebasing a lower series. Series above whose commits were rewritten are force-pushed, but only
+/// if their remote branch still is where they were last pushed to.
 pub fn push_change_reference(
     ctx: &CommandContext,
     branch_id: BranchId,
@@ -135,24 +169,117 @@ pub fn push_change_reference(
     credentials: &Helper,
 ) -> Result<()> {
     let handle = VirtualBranchesHandle::new(ctx.project().gb_dir());
-    let vbranch = handle.get_branch(branch_id)?;
-    let reference = vbranch
-        .references
+    let mut vbranch = handle.get_branch(branch_id)?;
+    let series = stack_series(ctx, &vbranch, &handle)?;
+    let idx = series
         .iter()
-        .find(|r| r.name == name)
+        .position(|s| s.reference.name == name)
         .ok_or_else(|| anyhow!("Reference {} not found", name))?;
-    let upstream_refname = gitbutler_reference::RemoteRefname::from_str(&reference.name)
-        .context("Failed to parse the provided reference")?;
-    let commit =
-        commit_by_branch_id_and_change_id(ctx, &vbranch, &handle, reference.change_id.clone())?;
-    ctx.push(
-        commit.id(),
-        &upstream_refname,
-        with_force,
-        credentials,
-        None,
-        Some(Some(branch_id)),
-    )
+
+    // Push from the bottom so each series is pushed after the one it's based on.
+    for (series_idx, series) in series.iter().enumerate().rev() {
+        let head = series.commits[0];
+        let pushed_commit = series.reference.pushed_commit;
+        let needs_push = match series_idx.cmp(&idx) {
+            std::cmp::Ordering::Equal => true,
+            std::cmp::Ordering::Greater => pushed_commit.is_none(),
+            std::cmp::Ordering::Less => pushed_commit.is_some_and(|pushed| pushed != head),
+        };
+        if !needs_push {
+            continue;
+        }
+        let force_with_lease = match pushed_commit {
+            Some(pushed) if series_idx < idx => {
+                let rewritten = !ctx.repository().graph_descendant_of(head, pushed)?;
+                rewritten.then_some(pushed)
+            }
+            _ => None,
+        };
+        let upstream_refname = gitbutler_reference::RemoteRefname::from_str(&series.reference.name)
+            .context("Failed to parse the provided reference")?;
+        ctx.push(
+            head,
+            &upstream_refname,
+            with_force && series_idx >= idx,
+            force_with_lease,
+            credentials,
+            None,
+            Some(Some(branch_id)),
+        )?;
+        if let Some(reference) = vbranch
+            .references
+            .iter_mut()
+            .find(|r| r.name == series.reference.name)
+        {
+            reference.pushed_commit = Some(head);
+        }
+        handle.set_branch(vbranch.clone())?;
+    }
+    Ok(())
+}
+
+/// Order the references of `vbranch` from the top of its stack to the bottom, by the position of the commits they refer to.
+/// References whose commit isn't in the branch anymore are kept at the bottom.
+fn sort_references(
+    ctx: &CommandContext,
+    vbranch: &mut Branch,
+    handle: &VirtualBranchesHandle,
+) -> Result<()> {
+    let series = stack_series(ctx, vbranch, handle)?;
+    let orphans = vbranch
+        .references
+        .iter()
+        .filter(|reference| !series.iter().any(|s| s.reference.name == reference.name))
+        .cloned()
+        .collect_vec();
+    vbranch.references = series
+        .into_iter()
+        .map(|s| s.reference)
+        .chain(orphans)
+        .collect();
+    Ok(())
+}
+
+/// Split the commits of `vbranch` into the series of its references, from the top of the stack to the bottom.
+/// References whose commit isn't in the branch anymore, for instance because it was squashed or undone,
+/// don't form a series and are skipped.
+fn stack_series(
+    ctx: &CommandContext,
+    vbranch: &Branch,
+    handle: &VirtualBranchesHandle,
+) -> Result<Vec<Series>> {
+    let target = handle.get_default_target()?;
+    let commits = ctx.log(vbranch.head, LogUntil::Commit(target.sha))?;
+    let mut references_by_position = vbranch
+        .references
+        .iter()
+        .filter_map(|reference| {
+            let position = commits
+                .iter()
+                .position(|c| c.change_id().as_deref() == Some(&reference.change_id));
+            if position.is_none() {
+                tracing::warn!(
+                    reference = %reference.name,
+                    change_id = reference.change_id,
+                    "skipping reference whose commit isn't in the branch"
+                );
+            }
+            position.map(|position| (position, reference))
+        })
+        .collect_vec();
+    references_by_position.sort_by_key(|(position, _)| *position);
+
+    let mut series = Vec::with_capacity(references_by_position.len());
+    for (idx, (position, reference)) in references_by_position.iter().enumerate() {
+        let below = references_by_position.get(idx + 1);
+        let end = below.map_or(commits.len(), |(position, _)| *position);
+        series.push(Series {
+            reference: (*reference).clone(),
+            base: below.map(|(_, reference)| reference.name.clone()),
+            commits: commits[*position..end].iter().map(|c| c.id()).collect(),
+        });
+    }
+    Ok(series)
 }
 
 /// Given a branch id and a change id, returns the commit associated with the change id.
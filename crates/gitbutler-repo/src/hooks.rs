@@ -0,0 +1,205 @@
+//! Run the git hooks of a repository for operations that write commits or push without invoking `git` itself.
+//!
+//! As the workspace isn't a checkout of any single branch, hooks that inspect files run in a temporary worktree
+//! which has the commit to check out.
+use std::{
+    ffi::OsStr,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use anyhow::{anyhow, Context, Result};
+use gitbutler_error::error::Code;
+
+use crate::temporary_workdir::TemporaryWorkdir;
+
+const PRE_COMMIT: &str = "pre-commit";
+const COMMIT_MSG: &str = "commit-msg";
+const POST_COMMIT: &str = "post-commit";
+const PRE_PUSH: &str = "pre-push";
+
+/// Run the `pre-commit` and `commit-msg` hooks of `repo` for a commit of `tree_id` on top of `parent_id`.
+/// The hooks see `tree_id` checked out and staged, and `message` is updated with the changes `commit-msg` made to it.
+pub fn run_commit_hooks(
+    repo: &git2::Repository,
+    parent_id: git2::Oid,
+    tree_id: git2::Oid,
+    message: &mut String,
+) -> Result<()> {
+    let pre_commit = find_hook(repo, PRE_COMMIT);
+    let commit_msg = find_hook(repo, COMMIT_MSG);
+    if pre_commit.is_none() && commit_msg.is_none() {
+        return Ok(());
+    }
+
+    let workdir = checkout_in_temporary_workdir(repo, parent_id, Some(tree_id))
+        .context("failed to prepare worktree for hooks")
+        .context(Code::CommitHookFailed)?;
+    if let Some(hook) = pre_commit {
+        let output = run(&hook, workdir.path(), &[], None).context(Code::CommitHookFailed)?;
+        ensure_success("commit", &output)?;
+    }
+    if let Some(hook) = commit_msg {
+        let message_path = workdir.repository().path().join("COMMIT_EDITMSG");
+        std::fs::write(&message_path, message.as_bytes())?;
+        let output = run(&hook, workdir.path(), &[message_path.as_os_str()], None)
+            .context(Code::CommitHookFailed)?;
+        ensure_success(COMMIT_MSG, &output)?;
+        *message = std::fs::read_to_string(&message_path)?;
+    }
+    Ok(())
+}
+
+/// Run the `post-commit` hook of `repo` in its worktree. Like with `git`, its outcome doesn't matter.
+pub fn run_post_commit_hook(repo: &git2::Repository) -> Result<()> {
+    let (Some(hook), Some(workdir)) = (find_hook(repo, POST_COMMIT), repo.workdir()) else {
+        return Ok(());
+    };
+    let output = run(&hook, workdir, &[], None).context(Code::CommitHookFailed)?;
+    if let Err(err) = ensure_success(POST_COMMIT, &output) {
+        tracing::warn!(?err, "post-commit hook failed");
+    }
+    Ok(())
+}
+
+/// Run the `pre-push` hook of `repo` before `local_id`, known as `local_ref`, is pushed to `remote_ref`
+/// on `remote_name`, where it is currently at `remote_id` if it exists.
+/// The hook sees `local_id` checked out.
+pub fn run_pre_push_hook(
+    repo: &git2::Repository,
+    remote_name: &str,
+    local_ref: &str,
+    local_id: git2::Oid,
+    remote_ref: &str,
+    remote_id: Option<git2::Oid>,
+) -> Result<()> {
+    let Some(hook) = find_hook(repo, PRE_PUSH) else {
+        return Ok(());
+    };
+    let remote = repo.find_remote(remote_name)?;
+    let remote_url = remote.url().unwrap_or(remote_name);
+
+    let workdir = checkout_in_temporary_workdir(repo, local_id, None)
+        .context("failed to prepare worktree for hooks")?;
+    let stdin = format!(
+        "{local_ref} {local_id} {remote_ref} {}\n",
+        remote_id.unwrap_or_else(git2::Oid::zero)
+    );
+    let output = run(
+        &hook,
+        workdir.path(),
+        &[OsStr::new(remote_name), OsStr::new(remote_url)],
+        Some(stdin.as_bytes()),
+    )?;
+    ensure_success(PRE_PUSH, &output)
+}
+
+/// Find the executable hook called `name`, either in the configured hooks directory or where `husky` puts it.
+fn find_hook(repo: &git2::Repository, name: &str) -> Option<PathBuf> {
+    let workdir = repo.workdir()?;
+    let hooks_dir = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok())
+        .map_or_else(|| repo.commondir().join("hooks"), |path| workdir.join(path));
+    [hooks_dir, workdir.join(".husky")]
+        .into_iter()
+        .map(|dir| dir.join(name))
+        .find(|path| is_executable(path))
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata().map_or(false, |md| {
+            md.is_file() && md.permissions().mode() & 0o111 != 0
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// Create a temporary worktree with `commit_id` checked out, or with `tree_id` checked out and staged on top of it.
+fn checkout_in_temporary_workdir(
+    repo: &git2::Repository,
+    commit_id: git2::Oid,
+    tree_id: Option<git2::Oid>,
+) -> Result<TemporaryWorkdir> {
+    let workdir = TemporaryWorkdir::open(repo)?;
+    {
+        let worktree_repo = workdir.repository();
+        let commit = worktree_repo.find_commit(commit_id)?;
+        worktree_repo.reset(commit.as_object(), git2::ResetType::Hard, None)?;
+        if let Some(tree_id) = tree_id {
+            let tree = worktree_repo.find_tree(tree_id)?;
+            worktree_repo.checkout_tree(
+                tree.as_object(),
+                Some(
+                    git2::build::CheckoutBuilder::new()
+                        .force()
+                        .remove_untracked(true),
+                ),
+            )?;
+            let mut index = worktree_repo.index()?;
+            index.read_tree(&tree)?;
+            index.write()?;
+        }
+    }
+    Ok(workdir)
+}
+
+fn run(hook: &Path, workdir: &Path, args: &[&OsStr], stdin: Option<&[u8]>) -> Result<Output> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("sh");
+        cmd.arg(hook);
+        cmd
+    } else {
+        Command::new(hook)
+    };
+    cmd.current_dir(workdir)
+        .args(args)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("failed to run hook at {}", hook.display()))?;
+    if let Some(input) = stdin {
+        let mut child_stdin = child.stdin.take().expect("configured to be piped");
+        // Hooks that aren't interested in their input may exit before reading it.
+        if let Err(err) = child_stdin.write_all(input) {
+            if err.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(err.into());
+            }
+        }
+    }
+    Ok(child.wait_with_output()?)
+}
+
+fn ensure_success(hook_label: &str, output: &Output) -> Result<()> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let report = [stdout.trim(), stderr.trim()]
+        .into_iter()
+        .filter(|output| !output.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Err(anyhow!("{hook_label} hook rejected: {report}").context(Code::HookRejected))
+}
@@ -20,6 +20,11 @@ pub mod askpass;
 mod change_reference;
 
 pub use change_reference::{
-    create_change_reference, list_branch_references, push_change_reference, update_change_reference,
+    create_change_reference, list_branch_references, list_series, push_change_reference,
+    update_change_reference, Series,
 };
 pub mod temporary_workdir;
+
+pub mod hooks;
+
+pub mod message_policy;
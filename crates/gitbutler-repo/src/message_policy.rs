@@ -0,0 +1,258 @@
+//! Validate and rewrite commit messages according to the rules a repository configured for them.
+//!
+//! A [`MessagePolicy`] is a list of [`MessageRule`]s which are applied in order, each seeing the message
+//! as rewritten by the previous ones. All violations are collected so they can be reported at once.
+use anyhow::Result;
+use gitbutler_config::git::{GbConfig, GitConfig};
+use gitbutler_error::error::Code;
+use serde::Serialize;
+
+/// The prefixes allowed by the conventional commits specification, as used by `commitlint`.
+pub const CONVENTIONAL_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+/// A way in which a commit message doesn't comply with the [`MessagePolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum PolicyViolation {
+    /// The message has no subject line.
+    EmptySubject,
+    /// The subject line is `length` characters long, more than the `max` allowed.
+    SubjectTooLong { length: usize, max: usize },
+    /// The subject line doesn't look like `type(scope)!: description`.
+    NotConventional { subject: String },
+    /// The trailer with `key` is missing from the last paragraph of the message.
+    MissingTrailer { key: String },
+}
+
+impl std::fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyViolation::EmptySubject => f.write_str("the subject line is empty"),
+            PolicyViolation::SubjectTooLong { length, max } => write!(
+                f,
+                "the subject line is {length} characters long, but may only have {max}"
+            ),
+            PolicyViolation::NotConventional { subject } => write!(
+                f,
+                "the subject line '{subject}' must start with one of {} followed by ': '",
+                CONVENTIONAL_TYPES.join(", ")
+            ),
+            PolicyViolation::MissingTrailer { key } => {
+                write!(f, "the message must end with a '{key}: ' trailer")
+            }
+        }
+    }
+}
+
+/// The error returned if a message violates the policy, with all of its violations.
+///
+/// It's attached to [`Code::CommitMessagePolicy`] and can be obtained with `err.downcast_ref::<PolicyViolations>()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyViolations(pub Vec<PolicyViolation>);
+
+impl std::fmt::Display for PolicyViolations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("commit message violates the message policy: ")?;
+        for (idx, violation) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{violation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PolicyViolations {}
+
+/// A single rule of a [`MessagePolicy`].
+pub trait MessageRule: Send + Sync {
+    /// Check `message`, possibly rewriting it, and push everything that is wrong with it to `violations`.
+    fn apply(&self, message: &mut String, violations: &mut Vec<PolicyViolation>);
+
+    /// Return `true` if the rule only rewrites the message and never reports violations, which is why it's the
+    /// only kind of rule that applies to the messages of `fixup! ` and `squash! ` commits.
+    fn only_rewrites(&self) -> bool {
+        false
+    }
+}
+
+/// Strip trailing whitespace from all lines, and remove leading and trailing blank lines.
+pub struct Cleanup;
+
+impl MessageRule for Cleanup {
+    fn apply(&self, message: &mut String, _violations: &mut Vec<PolicyViolation>) {
+        let lines = message.lines().map(str::trim_end).collect::<Vec<_>>();
+        let mut cleaned = lines.join("\n").trim_matches('\n').to_owned();
+        while cleaned.contains("\n\n\n") {
+            cleaned = cleaned.replace("\n\n\n", "\n\n");
+        }
+        *message = cleaned;
+    }
+
+    fn only_rewrites(&self) -> bool {
+        true
+    }
+}
+
+/// Require the subject line to have at most the given amount of characters.
+pub struct MaxSubjectLength(pub usize);
+
+impl MessageRule for MaxSubjectLength {
+    fn apply(&self, message: &mut String, violations: &mut Vec<PolicyViolation>) {
+        let length = subject(message).chars().count();
+        if length > self.0 {
+            violations.push(PolicyViolation::SubjectTooLong {
+                length,
+                max: self.0,
+            });
+        }
+    }
+}
+
+/// Require the subject line to follow the [conventional commits](https://www.conventionalcommits.org) format.
+pub struct Conventional;
+
+impl MessageRule for Conventional {
+    fn apply(&self, message: &mut String, violations: &mut Vec<PolicyViolation>) {
+        let subject = subject(message);
+        if !subject.is_empty() && !is_conventional(subject) {
+            violations.push(PolicyViolation::NotConventional {
+                subject: subject.to_owned(),
+            });
+        }
+    }
+}
+
+/// Require a trailer with the given key, like `Ticket`, in the last paragraph of the message.
+pub struct RequiredTrailer(pub String);
+
+impl MessageRule for RequiredTrailer {
+    fn apply(&self, message: &mut String, violations: &mut Vec<PolicyViolation>) {
+        let prefix = format!("{}:", self.0.to_lowercase());
+        let mut paragraphs = message.trim_end().split("\n\n");
+        // The subject can't be a trailer, so there must be a body after it.
+        let has_trailer = paragraphs.next().is_some()
+            && paragraphs.last().map_or(false, |trailers| {
+                trailers.lines().any(|line| {
+                    line.to_lowercase()
+                        .strip_prefix(&prefix)
+                        .map_or(false, |value| !value.trim().is_empty())
+                })
+            });
+        if !has_trailer {
+            violations.push(PolicyViolation::MissingTrailer {
+                key: self.0.clone(),
+            });
+        }
+    }
+}
+
+/// Rules that commit messages have to follow before they can be written.
+#[derive(Default)]
+pub struct MessagePolicy {
+    rules: Vec<Box<dyn MessageRule>>,
+}
+
+impl MessagePolicy {
+    /// Create the policy configured in `config`, which doesn't do anything if nothing is configured.
+    pub fn from_config(config: &GbConfig) -> Self {
+        let mut policy = MessagePolicy::default();
+        if config.commit_message_cleanup.unwrap_or(false) {
+            policy = policy.with_rule(Cleanup);
+        }
+        if let Some(max) = config.commit_message_max_subject_length {
+            policy = policy.with_rule(MaxSubjectLength(max as usize));
+        }
+        if config.commit_message_conventional.unwrap_or(false) {
+            policy = policy.with_rule(Conventional);
+        }
+        if let Some(key) = config
+            .commit_message_required_trailer
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+        {
+            policy = policy.with_rule(RequiredTrailer(key.to_owned()));
+        }
+        policy
+    }
+
+    /// Create the policy configured in the git configuration of `repo`.
+    pub fn from_repository(repo: &git2::Repository) -> Result<Self> {
+        Ok(Self::from_config(&repo.gb_config()?))
+    }
+
+    /// Add `rule` to be applied after all rules added previously.
+    pub fn with_rule(mut self, rule: impl MessageRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Apply all rules to `message` and return it as rewritten by them, or fail with [`PolicyViolations`]
+    /// if any rule was violated.
+    ///
+    /// Like `commitlint`, messages starting with `fixup! ` or `squash! ` aren't validated, as these commits are
+    /// folded into the commit they target before they become part of the history. They are only rewritten by the
+    /// rules that [only rewrite](MessageRule::only_rewrites).
+    pub fn apply(&self, message: &str) -> Result<String> {
+        let mut message = message.to_owned();
+        let is_autosquash = is_autosquash_subject(subject(&message));
+        let mut violations = Vec::new();
+        if subject(&message).is_empty() && !self.rules.is_empty() {
+            violations.push(PolicyViolation::EmptySubject);
+        }
+        for rule in &self.rules {
+            if is_autosquash && !rule.only_rewrites() {
+                continue;
+            }
+            rule.apply(&mut message, &mut violations);
+        }
+        if violations.is_empty() {
+            Ok(message)
+        } else {
+            Err(anyhow::Error::from(PolicyViolations(violations))
+                .context(Code::CommitMessagePolicy))
+        }
+    }
+}
+
+/// Apply the message policy configured for `repo` to `message`.
+pub fn apply(repo: &git2::Repository, message: &str) -> Result<String> {
+    MessagePolicy::from_repository(repo)?.apply(message)
+}
+
+fn subject(message: &str) -> &str {
+    message
+        .trim_start()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+}
+
+fn is_autosquash_subject(subject: &str) -> bool {
+    subject.starts_with("fixup! ") || subject.starts_with("squash! ")
+}
+
+fn is_conventional(subject: &str) -> bool {
+    let Some((prefix, description)) = subject.split_once(": ") else {
+        return false;
+    };
+    let prefix = prefix.strip_suffix('!').unwrap_or(prefix);
+    let kind = match prefix.split_once('(') {
+        Some((kind, scope)) => {
+            let Some(scope) = scope.strip_suffix(')') else {
+                return false;
+            };
+            if scope.is_empty() || scope.contains(['(', ')']) {
+                return false;
+            }
+            kind
+        }
+        None => prefix,
+    };
+    CONVENTIONAL_TYPES.contains(&kind) && !description.trim().is_empty()
+}
@@ -41,17 +41,32 @@ pub fn cherry_rebase_group(
     ctx: &CommandContext,
     target_commit_oid: git2::Oid,
     ids_to_rebase: &mut [git2::Oid],
+) -> Result<git2::Oid> {
+    cherry_rebase_group_in(
+        ctx.repository(),
+        ctx.project().succeeding_rebases,
+        target_commit_oid,
+        ids_to_rebase,
+    )
+}
+
+/// Like [`cherry_rebase_group()`], but writes all objects into `repository`, which may be
+/// [an in-memory repository](crate::RepositoryExt::in_memory_repo) to not touch the disk.
+/// If `succeeding_rebases` is `true`, conflicts are recorded in conflicted commits instead of failing the rebase.
+pub fn cherry_rebase_group_in(
+    repository: &git2::Repository,
+    succeeding_rebases: bool,
+    target_commit_oid: git2::Oid,
+    ids_to_rebase: &mut [git2::Oid],
 ) -> Result<git2::Oid> {
     ids_to_rebase.reverse();
     // now, rebase unchanged commits onto the new commit
     let commits_to_rebase = ids_to_rebase
         .iter()
-        .map(|oid| ctx.repository().find_commit(oid.to_owned()))
+        .map(|oid| repository.find_commit(oid.to_owned()))
         .collect::<Result<Vec<_>, _>>()
         .context("failed to read commits to rebase")?;
 
-    let repository = ctx.repository();
-
     let new_head_id = commits_to_rebase
         .into_iter()
         .fold(
@@ -70,12 +85,12 @@ pub fn cherry_rebase_group(
                     .context("failed to cherry pick")?;
 
                 if cherrypick_index.has_conflicts() {
-                    if !ctx.project().succeeding_rebases {
+                    if !succeeding_rebases {
                         return Err(anyhow!("failed to rebase")).context(Marker::BranchConflict);
                     }
-                    commit_conflicted_cherry_result(ctx, head, to_rebase, cherrypick_index)
+                    commit_conflicted_cherry_result(repository, head, to_rebase, cherrypick_index)
                 } else {
-                    commit_unconflicted_cherry_result(ctx, head, to_rebase, cherrypick_index)
+                    commit_unconflicted_cherry_result(repository, head, to_rebase, cherrypick_index)
                 }
             },
         )?
@@ -85,12 +100,11 @@ pub fn cherry_rebase_group(
 }
 
 fn commit_unconflicted_cherry_result<'repository>(
-    ctx: &'repository CommandContext,
+    repository: &'repository git2::Repository,
     head: git2::Commit<'repository>,
     to_rebase: git2::Commit,
     mut cherrypick_index: git2::Index,
 ) -> Result<git2::Commit<'repository>> {
-    let repository = ctx.repository();
     let commit_headers = to_rebase.gitbutler_headers();
 
     let is_merge_commit = to_rebase.parent_count() > 0;
@@ -131,12 +145,11 @@ fn commit_unconflicted_cherry_result<'repository>(
 }
 
 fn commit_conflicted_cherry_result<'repository>(
-    ctx: &'repository CommandContext,
+    repository: &'repository git2::Repository,
     head: git2::Commit,
     to_rebase: git2::Commit,
     cherrypick_index: git2::Index,
 ) -> Result<git2::Commit<'repository>> {
-    let repository = ctx.repository();
     let commit_headers = to_rebase.gitbutler_headers();
 
     // If the commit we're rebasing is conflicted, use the commits original base.
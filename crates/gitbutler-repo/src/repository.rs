@@ -1,22 +1,38 @@
-use std::str::FromStr;
+use std::{
+    path::PathBuf,
+    str::FromStr,
+    time::{Instant, SystemTime},
+};
 
 use anyhow::{anyhow, Context, Result};
 use gitbutler_branch::{gix_to_git2_signature, Branch, BranchId, SignaturePurpose};
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_headers::CommitHeadersV2;
-use gitbutler_error::error::Code;
-use gitbutler_project::AuthKey;
+use gitbutler_error::error::{AnyhowContextExt, Code};
+use gitbutler_project::{
+    AuthKey, RemoteFetchError, RemoteFetchErrorKind, RemoteFetchResult, RemoteFetchResults,
+};
 use gitbutler_reference::{Refname, RemoteRefname};
 
 use crate::{askpass, credentials::Helper, Config, RepositoryExt};
 pub trait RepoActionsExt {
     fn fetch(&self, remote_name: &str, credentials: &Helper, askpass: Option<String>)
         -> Result<()>;
+    /// Fetch all remotes concurrently, and return how fetching each of them went.
+    fn fetch_remotes(
+        &self,
+        credentials: &Helper,
+        askpass: Option<String>,
+    ) -> Result<RemoteFetchResults>;
+    /// Push `head` to `branch`. With `force_with_lease` set to the commit the remote branch is expected to point to,
+    /// the push may rewrite the remote branch, but fails if someone else updated it in the meantime.
+    #[allow(clippy::too_many_arguments)]
     fn push(
         &self,
         head: git2::Oid,
         branch: &RemoteRefname,
         with_force: bool,
+        force_with_lease: Option<git2::Oid>,
         credentials: &Helper,
         refspec: Option<String>,
         askpass_broker: Option<Option<BranchId>>,
@@ -67,7 +83,7 @@ impl RepoActionsExt for CommandContext {
         let refname =
             RemoteRefname::from_str(&format!("refs/remotes/{remote_name}/{branch_name}",))?;
 
-        match self.push(commit_id, &refname, false, credentials, None, askpass) {
+        match self.push(commit_id, &refname, false, None, credentials, None, askpass) {
             Ok(()) => Ok(()),
             Err(e) => Err(anyhow::anyhow!(e.to_string())),
         }?;
@@ -77,6 +93,7 @@ impl RepoActionsExt for CommandContext {
             commit_id,
             &refname,
             false,
+            None,
             credentials,
             empty_refspec,
             askpass,
@@ -257,12 +274,13 @@ impl RepoActionsExt for CommandContext {
         head: git2::Oid,
         branch: &RemoteRefname,
         with_force: bool,
+        force_with_lease: Option<git2::Oid>,
         credentials: &Helper,
         refspec: Option<String>,
         askpass_broker: Option<Option<BranchId>>,
     ) -> Result<()> {
         let refspec = refspec.unwrap_or_else(|| {
-            if with_force {
+            if with_force || force_with_lease.is_some() {
                 format!("+{}:refs/heads/{}", head, branch.branch())
             } else {
                 format!("{}:refs/heads/{}", head, branch.branch())
@@ -286,6 +304,7 @@ impl RepoActionsExt for CommandContext {
                         &remote,
                         gitbutler_git::RefSpec::parse(refspec).unwrap(),
                         with_force,
+                        force_with_lease.map(|expected| expected.to_string()),
                         handle_git_prompt_push,
                         askpass_broker,
                     ))
@@ -303,6 +322,23 @@ impl RepoActionsExt for CommandContext {
                 if self.project().omit_certificate_check.unwrap_or(false) {
                     cbs.certificate_check(|_, _| Ok(git2::CertificateCheckStatus::CertificateOk));
                 }
+                if let Some(expected) = force_with_lease {
+                    let destination = format!("refs/heads/{}", branch.branch());
+                    cbs.push_negotiation(move |updates| {
+                        match updates
+                            .iter()
+                            .find(|update| update.dst_refname() == Some(destination.as_str()))
+                        {
+                            Some(update) if update.src() != expected => {
+                                Err(git2::Error::from_str(&format!(
+                                    "stale info: {destination} is at {} on the remote but was expected at {expected}",
+                                    update.src()
+                                )))
+                            }
+                            _ => Ok(()),
+                        }
+                    });
+                }
                 cbs.push_update_reference(|_reference: &str, status: Option<&str>| {
                     if let Some(status) = status {
                         update_refs_error = Some(git2::Error::from_str(status));
@@ -385,6 +421,7 @@ impl RepoActionsExt for CommandContext {
         }
 
         let auth_flows = credentials.help(self, remote_name)?;
+        let mut last_err = None;
         for (mut remote, callbacks) in auth_flows {
             for callback in callbacks {
                 let mut fetch_opts = git2::FetchOptions::new();
@@ -403,11 +440,13 @@ impl RepoActionsExt for CommandContext {
                     Err(err) => match err.class() {
                         git2::ErrorClass::Net | git2::ErrorClass::Http => {
                             tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to network");
+                            last_err = Some(err);
                             continue;
                         }
                         _ => match err.code() {
                             git2::ErrorCode::Auth => {
                                 tracing::warn!(project_id = %self.project().id, ?err, "fetch failed due to auth");
+                                last_err = Some(err);
                                 continue;
                             }
                             _ => {
@@ -419,7 +458,53 @@ impl RepoActionsExt for CommandContext {
             }
         }
 
-        Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth)
+        // Only report authentication failures as such, network failures are passed on as they are.
+        match last_err {
+            Some(err) if err.code() != git2::ErrorCode::Auth => Err(err.into()),
+            _ => Err(anyhow!("authentication failed")).context(Code::ProjectGitAuth),
+        }
+    }
+
+    fn fetch_remotes(
+        &self,
+        credentials: &Helper,
+        askpass: Option<String>,
+    ) -> Result<RemoteFetchResults> {
+        let remotes = self.repository().remotes_as_string()?;
+
+        if self.project().preferred_key == AuthKey::SystemExecutable {
+            let path = self.project().worktree_path();
+            return std::thread::spawn(move || {
+                Ok(tokio::runtime::Runtime::new()?
+                    .block_on(fetch_remotes_with_executable(path, remotes, askpass)))
+            })
+            .join()
+            .map_err(|_| anyhow!("fetch thread panicked"))?;
+        }
+
+        // `git2` remotes are bound to their repository, so each fetch gets its own.
+        let project = self.project();
+        let results = std::thread::scope(|scope| {
+            let fetches: Vec<_> = remotes
+                .into_iter()
+                .map(|remote| {
+                    let askpass = askpass.clone();
+                    scope.spawn(move || {
+                        let started = Instant::now();
+                        let error = CommandContext::open(project)
+                            .and_then(|ctx| ctx.fetch(&remote, credentials, askpass))
+                            .err()
+                            .map(|err| fetch_error_from_anyhow(&err));
+                        (remote, remote_fetch_result(started, error))
+                    })
+                })
+                .collect();
+            fetches
+                .into_iter()
+                .map(|fetch| fetch.join().map_err(|_| anyhow!("fetch thread panicked")))
+                .collect::<Result<_>>()
+        })?;
+        Ok(results)
     }
 
     fn signatures(&self) -> Result<(git2::Signature, git2::Signature)> {
@@ -478,6 +563,105 @@ async fn handle_git_prompt_push(
     }
 }
 
+async fn fetch_remotes_with_executable(
+    worktree_path: PathBuf,
+    remotes: Vec<String>,
+    askpass: Option<String>,
+) -> RemoteFetchResults {
+    let fetches = remotes.into_iter().map(|remote| {
+        let worktree_path = worktree_path.clone();
+        let askpass = askpass.clone();
+        async move {
+            let started = Instant::now();
+            let refspec = format!("+refs/heads/*:refs/remotes/{}/*", remote);
+            let error = gitbutler_git::fetch(
+                worktree_path,
+                gitbutler_git::tokio::TokioExecutor,
+                &remote,
+                gitbutler_git::RefSpec::parse(refspec).unwrap(),
+                handle_git_prompt_fetch,
+                askpass,
+            )
+            .await
+            .err()
+            .map(|err| {
+                let kind = match &err {
+                    gitbutler_git::Error::RefNotFound(_) => RemoteFetchErrorKind::RefNotFound,
+                    gitbutler_git::Error::AuthorizationFailed(_) => RemoteFetchErrorKind::Auth,
+                    _ => fetch_error_kind_from_message(&err.to_string()),
+                };
+                RemoteFetchError {
+                    kind,
+                    message: err.to_string(),
+                }
+            });
+            (remote, remote_fetch_result(started, error))
+        }
+    });
+    futures::future::join_all(fetches)
+        .await
+        .into_iter()
+        .collect()
+}
+
+fn remote_fetch_result(started: Instant, error: Option<RemoteFetchError>) -> RemoteFetchResult {
+    RemoteFetchResult {
+        timestamp: SystemTime::now(),
+        duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+        error,
+    }
+}
+
+fn fetch_error_from_anyhow(err: &anyhow::Error) -> RemoteFetchError {
+    let message = format!("{err:#}");
+    let kind = if err
+        .custom_context()
+        .map_or(false, |ctx| ctx.code == Code::ProjectGitAuth)
+    {
+        RemoteFetchErrorKind::Auth
+    } else {
+        match err.downcast_ref::<git2::Error>() {
+            Some(err) if err.code() == git2::ErrorCode::Auth => RemoteFetchErrorKind::Auth,
+            Some(err) if matches!(err.class(), git2::ErrorClass::Net | git2::ErrorClass::Http) => {
+                RemoteFetchErrorKind::Network
+            }
+            _ => fetch_error_kind_from_message(&message),
+        }
+    };
+    RemoteFetchError { kind, message }
+}
+
+/// Classify the error messages of `git fetch` for lack of anything more structured.
+///
+/// Note that `git` says it "could not read from remote repository" after most failures, whether the remote
+/// doesn't exist, rejected the credentials or couldn't be reached, so only the lines before it tell them apart.
+fn fetch_error_kind_from_message(message: &str) -> RemoteFetchErrorKind {
+    let message = message.to_lowercase();
+    let contains_any = |needles: &[&str]| needles.iter().any(|needle| message.contains(needle));
+    if contains_any(&["couldn't find remote ref", "a ref-spec was not found"]) {
+        RemoteFetchErrorKind::RefNotFound
+    } else if contains_any(&[
+        "permission denied",
+        "authentication failed",
+        "authorization",
+        "could not read username",
+        "could not read password",
+    ]) {
+        RemoteFetchErrorKind::Auth
+    } else if contains_any(&[
+        "could not resolve host",
+        "connection refused",
+        "connection reset",
+        "timed out",
+        "network is unreachable",
+        "unable to access",
+    ]) {
+        RemoteFetchErrorKind::Network
+    } else {
+        RemoteFetchErrorKind::Other
+    }
+}
+
 async fn handle_git_prompt_fetch(prompt: String, askpass: Option<String>) -> Option<String> {
     if let Some(action) = askpass {
         tracing::info!("received prompt for fetch with action {action:?}: {prompt:?}");
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use tempfile::tempdir;
@@ -45,6 +45,15 @@ impl TemporaryWorkdir {
         &self.repository
     }
 
+    /// The directory of the worktree.
+    pub fn path(&self) -> &Path {
+        if self.cleaned_up {
+            panic!("Can not access path after its been closed")
+        }
+
+        self.worktree.path()
+    }
+
     pub fn close(&mut self) -> Result<()> {
         if self.cleaned_up {
             return Ok(());
@@ -1,10 +1,11 @@
 use anyhow::Result;
-use gitbutler_branch::VirtualBranchesHandle;
+use gitbutler_branch::{ChangeReference, VirtualBranchesHandle};
 use gitbutler_command_context::CommandContext;
-use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_commit::{commit_ext::CommitExt, commit_headers::HasCommitHeaders};
 use gitbutler_repo::{
-    create_change_reference, credentials::Helper, list_branch_references, push_change_reference,
-    update_change_reference, LogUntil, RepoActionsExt,
+    create_change_reference, credentials::Helper, list_branch_references, list_series,
+    push_change_reference, rebase::cherry_rebase_group, update_change_reference, LogUntil,
+    RepoActionsExt,
 };
 use tempfile::TempDir;
 
@@ -228,6 +229,250 @@ fn push_success() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn references_are_ordered_as_stack() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    let bottom = create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/bottom".into(),
+        test_ctx.commits.last().unwrap().change_id().unwrap(),
+    )?;
+    let top = create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/top".into(),
+        test_ctx.commits.first().unwrap().change_id().unwrap(),
+    )?;
+    assert_eq!(
+        list_branch_references(&ctx, test_ctx.branch.id)?,
+        [top.clone(), bottom.clone()],
+        "the top of the stack comes first, no matter the order of creation"
+    );
+
+    let series = list_series(&ctx, test_ctx.branch.id)?;
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].reference, top);
+    assert_eq!(series[0].base, Some(bottom.name.clone()));
+    assert_eq!(
+        series[0].commits,
+        [test_ctx.commits[0].id(), test_ctx.commits[1].id()]
+    );
+    assert_eq!(series[1].reference, bottom);
+    assert_eq!(series[1].base, None, "the bottom is based on the target");
+    assert_eq!(series[1].commits, [test_ctx.commits[2].id()]);
+    Ok(())
+}
+
+#[test]
+fn push_includes_unpushed_series_below() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/bottom".into(),
+        test_ctx.commits.last().unwrap().change_id().unwrap(),
+    )?;
+    let middle = create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/middle".into(),
+        test_ctx.commits[1].change_id().unwrap(),
+    )?;
+    create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/top".into(),
+        test_ctx.commits.first().unwrap().change_id().unwrap(),
+    )?;
+
+    push_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        middle.name,
+        false,
+        &Helper::default(),
+    )?;
+    let pushed: Vec<_> = list_branch_references(&ctx, test_ctx.branch.id)?
+        .into_iter()
+        .map(|r| r.pushed_commit)
+        .collect();
+    assert_eq!(
+        pushed,
+        [
+            None,
+            Some(test_ctx.commits[1].id()),
+            Some(test_ctx.commits[2].id())
+        ],
+        "the series it's based on was pushed as well, but not the one above"
+    );
+    Ok(())
+}
+
+#[test]
+fn references_without_commit_in_branch_are_skipped() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/bottom".into(),
+        test_ctx.commits.last().unwrap().change_id().unwrap(),
+    )?;
+    let top = create_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/top".into(),
+        test_ctx.commits.first().unwrap().change_id().unwrap(),
+    )?;
+
+    // The commit of the bottom reference disappears, as if it was squashed.
+    let handle = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let mut branch = handle.get_branch(test_ctx.branch.id)?;
+    branch.references[1].change_id = "does-not-exist".into();
+    handle.set_branch(branch)?;
+
+    let series = list_series(&ctx, test_ctx.branch.id)?;
+    assert_eq!(
+        series.len(),
+        1,
+        "the orphaned reference doesn't fail the listing"
+    );
+    assert_eq!(series[0].reference, top);
+    assert_eq!(series[0].base, None);
+    assert_eq!(
+        series[0].commits,
+        test_ctx.commits.iter().map(|c| c.id()).collect::<Vec<_>>()
+    );
+
+    push_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        top.name,
+        false,
+        &Helper::default(),
+    )?;
+    let references = list_branch_references(&ctx, test_ctx.branch.id)?;
+    assert_eq!(references.len(), 2, "the orphaned reference is kept");
+    assert_eq!(references[1].pushed_commit, None);
+    Ok(())
+}
+
+#[test]
+fn push_force_pushes_rewritten_series_above() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    let (bottom, top) = push_stack_of_two(&ctx, &test_ctx)?;
+    let new_head = reword_middle_commit(&ctx, &test_ctx)?;
+
+    push_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        bottom.name,
+        false,
+        &Helper::default(),
+    )?;
+    let references = list_branch_references(&ctx, test_ctx.branch.id)?;
+    assert_eq!(references[0].name, top.name);
+    assert_eq!(
+        references[0].pushed_commit,
+        Some(new_head),
+        "the rebased series above was pushed even though it isn't a fast-forward"
+    );
+    assert_eq!(remote_head(&ctx, "top")?, new_head);
+    Ok(())
+}
+
+#[test]
+fn push_keeps_series_above_that_were_updated_on_the_remote() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    let (bottom, top) = push_stack_of_two(&ctx, &test_ctx)?;
+    reword_middle_commit(&ctx, &test_ctx)?;
+
+    // Someone else pushes to the series above.
+    let remote = remote_repository(&ctx)?;
+    let foreign_commit = test_ctx.commits[1].id();
+    remote.reference(
+        "refs/heads/top",
+        foreign_commit,
+        true,
+        "push by someone else",
+    )?;
+
+    let result = push_change_reference(
+        &ctx,
+        test_ctx.branch.id,
+        bottom.name,
+        false,
+        &Helper::default(),
+    );
+    assert!(result.is_err(), "the lease protects the remote series");
+    assert_eq!(remote_head(&ctx, "top")?, foreign_commit);
+    let references = list_branch_references(&ctx, test_ctx.branch.id)?;
+    assert_eq!(references[0].name, top.name);
+    assert_eq!(references[0].pushed_commit, Some(test_ctx.commits[0].id()));
+    Ok(())
+}
+
+/// Create and push a `top` and a `bottom` series, with the middle commit belonging to `top`.
+fn push_stack_of_two(
+    ctx: &CommandContext,
+    test_ctx: &TestContext,
+) -> Result<(ChangeReference, ChangeReference)> {
+    let bottom = create_change_reference(
+        ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/bottom".into(),
+        test_ctx.commits.last().unwrap().change_id().unwrap(),
+    )?;
+    let top = create_change_reference(
+        ctx,
+        test_ctx.branch.id,
+        "refs/remotes/origin/top".into(),
+        test_ctx.commits.first().unwrap().change_id().unwrap(),
+    )?;
+    push_change_reference(
+        ctx,
+        test_ctx.branch.id,
+        top.name.clone(),
+        false,
+        &Helper::default(),
+    )?;
+    Ok((bottom, top))
+}
+
+/// Rewrite the middle commit of the stack and rebase the top commit onto it, returning the new head of the branch.
+fn reword_middle_commit(ctx: &CommandContext, test_ctx: &TestContext) -> Result<git2::Oid> {
+    let middle = &test_ctx.commits[1];
+    let reworded = ctx.commit(
+        "second commit, reworded",
+        &middle.tree()?,
+        &[&test_ctx.commits[2]],
+        middle.gitbutler_headers(),
+    )?;
+    let new_head = cherry_rebase_group(ctx, reworded, &mut [test_ctx.commits[0].id()])?;
+
+    let handle = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let mut branch = handle.get_branch(test_ctx.branch.id)?;
+    branch.head = new_head;
+    handle.set_branch(branch)?;
+    Ok(new_head)
+}
+
+fn remote_repository(ctx: &CommandContext) -> Result<git2::Repository> {
+    let remote = ctx.repository().find_remote("origin")?;
+    Ok(git2::Repository::open(remote.url().unwrap())?)
+}
+
+fn remote_head(ctx: &CommandContext, branch: &str) -> Result<git2::Oid> {
+    let remote = remote_repository(ctx)?;
+    let head = remote.refname_to_id(&format!("refs/heads/{branch}"))?;
+    Ok(head)
+}
+
 fn command_ctx(name: &str) -> Result<(CommandContext, TempDir)> {
     gitbutler_testsupport::writable::fixture("stacking.sh", name)
 }
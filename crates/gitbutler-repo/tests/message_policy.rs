@@ -0,0 +1,142 @@
+use gitbutler_config::git::GbConfig;
+use gitbutler_error::error::Code;
+use gitbutler_repo::message_policy::{MessagePolicy, PolicyViolation, PolicyViolations};
+
+fn violations(policy: &MessagePolicy, message: &str) -> Vec<PolicyViolation> {
+    let err = policy.apply(message).unwrap_err();
+    assert_eq!(err.downcast_ref::<Code>(), Some(&Code::CommitMessagePolicy));
+    err.downcast_ref::<PolicyViolations>().unwrap().0.clone()
+}
+
+#[test]
+fn nothing_configured_accepts_anything() {
+    let policy = MessagePolicy::from_config(&GbConfig::default());
+    assert_eq!(
+        policy.apply("  whatever \n\n\n").unwrap(),
+        "  whatever \n\n\n"
+    );
+}
+
+#[test]
+fn cleanup_rewrites_message() {
+    let policy = MessagePolicy::from_config(&GbConfig {
+        commit_message_cleanup: Some(true),
+        ..GbConfig::default()
+    });
+    assert_eq!(
+        policy.apply("\nsubject  \n\n\n\nbody\t\n\n").unwrap(),
+        "subject\n\nbody"
+    );
+}
+
+#[test]
+fn all_violations_are_reported() {
+    let policy = MessagePolicy::from_config(&GbConfig {
+        commit_message_max_subject_length: Some(10),
+        commit_message_conventional: Some(true),
+        commit_message_required_trailer: Some("Ticket".into()),
+        ..GbConfig::default()
+    });
+    assert_eq!(
+        violations(&policy, "a subject which is too long"),
+        vec![
+            PolicyViolation::SubjectTooLong {
+                length: 27,
+                max: 10
+            },
+            PolicyViolation::NotConventional {
+                subject: "a subject which is too long".into()
+            },
+            PolicyViolation::MissingTrailer {
+                key: "Ticket".into()
+            },
+        ]
+    );
+    assert_eq!(
+        violations(&policy, ""),
+        vec![
+            PolicyViolation::EmptySubject,
+            PolicyViolation::MissingTrailer {
+                key: "Ticket".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn conventional_subjects() {
+    let policy = MessagePolicy::from_config(&GbConfig {
+        commit_message_conventional: Some(true),
+        ..GbConfig::default()
+    });
+    for subject in ["feat: add it", "fix(ui): fix it", "refactor!: break it"] {
+        policy.apply(subject).unwrap();
+    }
+    for subject in ["add it", "feature: add it", "fix(): fix it", "fix:fix it"] {
+        assert_eq!(
+            violations(&policy, subject),
+            vec![PolicyViolation::NotConventional {
+                subject: subject.into()
+            }]
+        );
+    }
+}
+
+#[test]
+fn trailer_must_be_in_last_paragraph() {
+    let policy = MessagePolicy::from_config(&GbConfig {
+        commit_message_required_trailer: Some("Ticket".into()),
+        ..GbConfig::default()
+    });
+    policy
+        .apply("subject\n\nbody\n\nticket: GB-1\nSigned-off-by: me\n")
+        .unwrap();
+    for message in [
+        "Ticket: GB-1",
+        "subject\n\nTicket: GB-1\n\nbody",
+        "subject\n\nTicket:",
+    ] {
+        assert_eq!(
+            violations(&policy, message),
+            vec![PolicyViolation::MissingTrailer {
+                key: "Ticket".into()
+            }]
+        );
+    }
+}
+
+#[test]
+fn fixup_messages_are_only_rewritten() {
+    let policy = MessagePolicy::from_config(&GbConfig {
+        commit_message_cleanup: Some(true),
+        commit_message_max_subject_length: Some(10),
+        commit_message_conventional: Some(true),
+        commit_message_required_trailer: Some("Ticket".into()),
+        ..GbConfig::default()
+    });
+    assert_eq!(
+        policy
+            .apply("fixup! feat: a long subject  \n\n\n\nTicket: 1\n")
+            .unwrap(),
+        "fixup! feat: a long subject\n\nTicket: 1",
+        "the message is still cleaned up"
+    );
+    assert_eq!(
+        policy
+            .apply("squash! feat: a long subject\n\nbody\n\nTicket: 1")
+            .unwrap(),
+        "squash! feat: a long subject\n\nbody\n\nTicket: 1"
+    );
+    assert_eq!(
+        policy.apply("fixup! a long subject").unwrap(),
+        "fixup! a long subject",
+        "the trailer is only required once it's folded into its target"
+    );
+    assert_eq!(
+        violations(&policy, "fixup!\n\nTicket: 1"),
+        vec![PolicyViolation::NotConventional {
+            subject: "fixup!".into()
+        }],
+        "without a subject to refer to, it's not a fixup"
+    );
+}
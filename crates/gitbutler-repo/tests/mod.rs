@@ -1,2 +1,3 @@
 mod change_reference;
 mod credentials;
+mod message_policy;
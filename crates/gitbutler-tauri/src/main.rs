@@ -158,10 +158,14 @@ fn main() {
                     virtual_branches::commands::create_virtual_branch,
                     virtual_branches::commands::delete_local_branch,
                     virtual_branches::commands::commit_virtual_branch,
+                    virtual_branches::commands::commit_lines,
+                    virtual_branches::commands::create_fixup_commit,
                     virtual_branches::commands::get_base_branch_data,
                     virtual_branches::commands::set_base_branch,
                     virtual_branches::commands::update_base_branch,
+                    virtual_branches::commands::preview_update_base_branch,
                     virtual_branches::commands::integrate_upstream_commits,
+                    virtual_branches::commands::preview_integrate_upstream_commits,
                     virtual_branches::commands::update_virtual_branch,
                     virtual_branches::commands::update_branch_order,
                     virtual_branches::commands::delete_virtual_branch,
@@ -174,12 +178,17 @@ fn main() {
                     virtual_branches::commands::list_remote_commit_files,
                     virtual_branches::commands::reset_virtual_branch,
                     virtual_branches::commands::amend_virtual_branch,
+                    virtual_branches::commands::absorb,
                     virtual_branches::commands::move_commit_file,
+                    virtual_branches::commands::split_commit,
                     virtual_branches::commands::undo_commit,
+                    virtual_branches::commands::cherry_pick,
+                    virtual_branches::commands::revert_commit,
                     virtual_branches::commands::insert_blank_commit,
                     virtual_branches::commands::create_change_reference,
                     virtual_branches::commands::push_change_reference,
                     virtual_branches::commands::update_change_reference,
+                    virtual_branches::commands::list_series,
                     virtual_branches::commands::reorder_commit,
                     virtual_branches::commands::update_commit_message,
                     virtual_branches::commands::list_local_branches,
@@ -187,12 +196,14 @@ fn main() {
                     virtual_branches::commands::get_branch_listing_details,
                     virtual_branches::commands::get_remote_branch_data,
                     virtual_branches::commands::squash_branch_commit,
+                    virtual_branches::commands::autosquash_branch,
                     virtual_branches::commands::fetch_from_remotes,
                     virtual_branches::commands::move_commit,
                     virtual_branches::commands::normalize_branch_name,
                     secret::secret_get_global,
                     secret::secret_set_global,
                     undo::list_snapshots,
+                    undo::query_snapshots,
                     undo::restore_snapshot,
                     undo::snapshot_diff,
                     config::get_gb_config,
@@ -2,7 +2,10 @@ use std::{collections::HashMap, path::PathBuf};
 
 use anyhow::Context;
 use gitbutler_diff::FileDiff;
-use gitbutler_oplog::{entry::Snapshot, OplogExt};
+use gitbutler_oplog::{
+    entry::{Snapshot, SnapshotQuery},
+    OplogExt,
+};
 use gitbutler_project as projects;
 use gitbutler_project::ProjectId;
 use tauri::State;
@@ -27,6 +30,25 @@ pub fn list_snapshots(
     Ok(snapshots)
 }
 
+#[tauri::command(async)]
+#[instrument(skip(projects), err(Debug))]
+pub fn query_snapshots(
+    projects: State<'_, projects::Controller>,
+    project_id: ProjectId,
+    query: SnapshotQuery,
+    limit: usize,
+    sha: Option<String>,
+) -> Result<Vec<Snapshot>, Error> {
+    let project = projects.get(project_id).context("failed to get project")?;
+    let snapshots = project.query_snapshots(
+        &query,
+        limit,
+        sha.map(|hex| hex.parse().map_err(anyhow::Error::from))
+            .transpose()?,
+    )?;
+    Ok(snapshots)
+}
+
 #[tauri::command(async)]
 #[instrument(skip(projects), err(Debug))]
 pub fn restore_snapshot(
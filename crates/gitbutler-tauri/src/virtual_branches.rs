@@ -4,8 +4,10 @@ pub mod commands {
         BranchCreateRequest, BranchId, BranchOwnershipClaims, BranchUpdateRequest,
     };
     use gitbutler_branch_actions::{
-        BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, RemoteBranch,
-        RemoteBranchData, RemoteBranchFile, VirtualBranchActions, VirtualBranches,
+        BaseBranch, BaseBranchUpdatePreview, BranchListing, BranchListingDetails,
+        BranchListingFilter, CommitSplit, LineSelection, RemoteBranch, RemoteBranchData,
+        RemoteBranchFile, RevertDestination, UpstreamIntegrationPreview, VirtualBranchActions,
+        VirtualBranches,
     };
     use gitbutler_command_context::CommandContext;
     use gitbutler_error::error::Code;
@@ -14,6 +16,7 @@ pub mod commands {
     use gitbutler_reference::{
         normalize_branch_name as normalize_name, ReferenceName, Refname, RemoteRefname,
     };
+    use gitbutler_repo::Series;
     use tauri::State;
     use tracing::instrument;
 
@@ -48,6 +51,47 @@ pub mod commands {
         Ok(oid.to_string())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn commit_lines(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+        message: &str,
+        selection: LineSelection,
+        run_hooks: bool,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let oid =
+            VirtualBranchActions.commit_lines(&project, branch, message, &selection, run_hooks)?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn create_fixup_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+        target_change_id: &str,
+        ownership: Option<BranchOwnershipClaims>,
+        run_hooks: bool,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let oid = VirtualBranchActions.create_fixup_commit(
+            &project,
+            branch,
+            target_change_id,
+            ownership.as_ref(),
+            run_hooks,
+        )?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.to_string())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn list_virtual_branches(
@@ -123,6 +167,17 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn preview_integrate_upstream_commits(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch: BranchId,
+    ) -> Result<UpstreamIntegrationPreview, Error> {
+        let project = projects.get(project_id)?;
+        Ok(VirtualBranchActions.preview_integrate_upstream_commits(&project, branch)?)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn get_base_branch_data(
@@ -173,6 +228,16 @@ pub mod commands {
         Ok(unapplied_branches)
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn preview_update_base_branch(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+    ) -> Result<BaseBranchUpdatePreview, Error> {
+        let project = projects.get(project_id)?;
+        Ok(VirtualBranchActions.preview_update_base_branch(&project)?)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn update_virtual_branch(
@@ -338,6 +403,20 @@ pub mod commands {
         Ok(oid.to_string())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn absorb(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+    ) -> Result<usize, Error> {
+        let project = projects.get(project_id)?;
+        let absorbed = VirtualBranchActions.absorb(&project, branch_id)?;
+        emit_vbranches(&windows, project_id);
+        Ok(absorbed)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn move_commit_file(
@@ -363,6 +442,23 @@ pub mod commands {
         Ok(oid.to_string())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn split_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        commit_oid: String,
+        splits: Vec<CommitSplit>,
+    ) -> Result<Vec<String>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let oids = VirtualBranchActions.split_commit(&project, branch_id, commit_oid, &splits)?;
+        emit_vbranches(&windows, project_id);
+        Ok(oids.iter().map(ToString::to_string).collect())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn undo_commit(
@@ -379,6 +475,39 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn cherry_pick(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        commit_oid: String,
+    ) -> Result<String, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let oid = VirtualBranchActions.cherry_pick(&project, branch_id, commit_oid)?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.to_string())
+    }
+
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn revert_commit(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+        commit_oid: String,
+        destination: RevertDestination,
+    ) -> Result<Option<String>, Error> {
+        let project = projects.get(project_id)?;
+        let commit_oid = git2::Oid::from_str(&commit_oid).map_err(|e| anyhow!(e))?;
+        let oid = VirtualBranchActions.revert(&project, branch_id, commit_oid, destination)?;
+        emit_vbranches(&windows, project_id);
+        Ok(oid.map(|oid| oid.to_string()))
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn insert_blank_commit(
@@ -428,6 +557,17 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects), err(Debug))]
+    pub fn list_series(
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+    ) -> Result<Vec<Series>, Error> {
+        let project = projects.get(project_id)?;
+        Ok(VirtualBranchActions.list_series(&project, branch_id)?)
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects, windows), err(Debug))]
     pub fn update_change_reference(
@@ -524,6 +664,20 @@ pub mod commands {
         Ok(())
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(projects, windows), err(Debug))]
+    pub fn autosquash_branch(
+        windows: State<'_, WindowState>,
+        projects: State<'_, projects::Controller>,
+        project_id: ProjectId,
+        branch_id: BranchId,
+    ) -> Result<(), Error> {
+        let project = projects.get(project_id)?;
+        VirtualBranchActions.autosquash(&project, branch_id)?;
+        emit_vbranches(&windows, project_id);
+        Ok(())
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(projects), err(Debug))]
     pub fn fetch_from_remotes(
@@ -533,10 +687,11 @@ pub mod commands {
     ) -> Result<BaseBranch, Error> {
         let project = projects.get(project_id)?;
 
-        let project_data_last_fetched = VirtualBranchActions.fetch_from_remotes(
+        let remotes_last_fetched = VirtualBranchActions.fetch_from_remotes(
             &project,
             Some(action.unwrap_or_else(|| "unknown".to_string())),
         )?;
+        let project_data_last_fetched = FetchResult::from_remotes(&remotes_last_fetched);
 
         // Updates the project controller with the last fetched timestamp
         //
@@ -545,6 +700,7 @@ pub mod commands {
             .update(&projects::UpdateRequest {
                 id: project.id,
                 project_data_last_fetched: Some(project_data_last_fetched.clone()),
+                remotes_last_fetched: Some(remotes_last_fetched),
                 ..Default::default()
             })
             .context("failed to update project with last fetched timestamp")?;
@@ -176,6 +176,12 @@ impl Handler {
                 SnapshotDetails::new(OperationKind::FileChanges),
                 guard.write_permission(),
             )?;
+            if project
+                .should_prune_snapshots(std::time::Duration::from_secs(24 * 60 * 60))
+                .unwrap_or_default()
+            {
+                project.prune_snapshots(guard.write_permission())?;
+            }
         }
         Ok(())
     }